@@ -1,14 +1,22 @@
 //! Main application module
 
+use gpui::prelude::FluentBuilder;
 use gpui::*;
-use gpui_component::*;
+use gpui_component::{
+    button::*,
+    input::{Input, InputState},
+    *,
+};
+use std::time::{Duration, Instant};
 
+use crate::crypto;
 use crate::ui::{accounts::AccountsView, dashboard::DashboardView, settings::SettingsView};
 
-/// Main application view
-pub struct CloudBridgeApp {
-    /// Current navigation item
-    current_view: CurrentView,
+/// How often the lock state and idle time are checked
+const LOCK_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Views shown while unlocked
+struct MainViews {
     /// Dashboard view
     dashboard_view: Entity<DashboardView>,
     /// Accounts view
@@ -17,6 +25,20 @@ pub struct CloudBridgeApp {
     settings_view: Entity<SettingsView>,
 }
 
+/// Main application view
+pub struct CloudBridgeApp {
+    /// Current navigation item
+    current_view: CurrentView,
+    /// Views, created once unlocked (None while locked in master passphrase mode)
+    views: Option<MainViews>,
+    /// Master passphrase input of the lock screen
+    passphrase_input: Entity<InputState>,
+    /// Error of the last unlock attempt
+    unlock_error: Option<String>,
+    /// Last mouse or keyboard input, for auto-lock
+    last_activity: Instant,
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Default)]
 pub enum CurrentView {
     #[default]
@@ -27,16 +49,127 @@ pub enum CurrentView {
 
 impl CloudBridgeApp {
     pub fn new(window: &mut Window, cx: &mut Context<Self>) -> Self {
-        let dashboard_view = cx.new(|cx| DashboardView::new(window, cx));
-        let accounts_view = cx.new(|cx| AccountsView::new(window, cx));
-        let settings_view = cx.new(|cx| SettingsView::new(window, cx));
+        let passphrase_input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .placeholder("Master passphrase")
+                .masked(true)
+        });
+
+        // Lock after the configured idle time
+        cx.spawn(async move |this, cx| loop {
+            smol::Timer::after(LOCK_CHECK_INTERVAL).await;
+            let alive = cx
+                .update(|cx| this.update(cx, |this, cx| this.lock_tick(cx)).is_ok())
+                .unwrap_or(false);
+            if !alive {
+                break;
+            }
+        })
+        .detach();
+
+        let views = if crypto::is_locked() {
+            None
+        } else {
+            Some(Self::create_views(window, cx))
+        };
 
         Self {
             current_view: CurrentView::Dashboard,
-            dashboard_view,
-            accounts_view,
-            settings_view,
+            views,
+            passphrase_input,
+            unlock_error: None,
+            last_activity: Instant::now(),
+        }
+    }
+
+    fn create_views(window: &mut Window, cx: &mut Context<Self>) -> MainViews {
+        // Background work that needs the unlocked secrets
+        match crate::db::migrate_legacy_credentials() {
+            Ok(0) => {}
+            Ok(moved) => tracing::info!("Moved stored credentials of {} account(s)", moved),
+            Err(e) => tracing::warn!("Failed to move stored credentials: {}", e),
+        }
+        crate::digest::start_background_delivery();
+
+        MainViews {
+            dashboard_view: cx.new(|cx| DashboardView::new(window, cx)),
+            accounts_view: cx.new(|cx| AccountsView::new(window, cx)),
+            settings_view: cx.new(|cx| SettingsView::new(window, cx)),
+        }
+    }
+
+    fn unlock(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let passphrase = self.passphrase_input.read(cx).value().to_string();
+        match crypto::unlock(&passphrase) {
+            Ok(()) => {
+                self.unlock_error = None;
+                self.last_activity = Instant::now();
+                self.passphrase_input
+                    .update(cx, |input, cx| input.set_value("", window, cx));
+                self.views = Some(Self::create_views(window, cx));
+            }
+            Err(e) => self.unlock_error = Some(e.to_string()),
         }
+        cx.notify();
+    }
+
+    /// Drop the views (and the data they hold) when the app got locked, either from
+    /// Settings or after being idle for `auto_lock_minutes`
+    fn lock_tick(&mut self, cx: &mut Context<Self>) {
+        if self.views.is_none() {
+            return;
+        }
+        let auto_lock_minutes = crypto::auto_lock_minutes();
+        if auto_lock_minutes > 0
+            && self.last_activity.elapsed() >= Duration::from_secs(auto_lock_minutes as u64 * 60)
+        {
+            crypto::lock();
+        }
+        if crypto::is_locked() {
+            self.views = None;
+            self.current_view = CurrentView::Dashboard;
+            cx.notify();
+        }
+    }
+
+    fn render_lock_screen(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        div()
+            .size_full()
+            .bg(cx.theme().background)
+            .v_flex()
+            .items_center()
+            .justify_center()
+            .child(
+                div()
+                    .w(px(360.0))
+                    .v_flex()
+                    .gap_3()
+                    .child(
+                        div()
+                            .text_xl()
+                            .font_weight(FontWeight::BOLD)
+                            .text_color(cx.theme().foreground)
+                            .child("CloudBridge is locked"),
+                    )
+                    .child(
+                        div()
+                            .text_sm()
+                            .text_color(cx.theme().muted_foreground)
+                            .child("Enter the master passphrase to unlock your accounts."),
+                    )
+                    .child(Input::new(&self.passphrase_input))
+                    .when_some(self.unlock_error.clone(), |this, error| {
+                        this.child(div().text_sm().text_color(cx.theme().danger).child(error))
+                    })
+                    .child(
+                        Button::new("unlock")
+                            .label("Unlock")
+                            .primary()
+                            .on_click(cx.listener(|this, _, window, cx| {
+                                this.unlock(window, cx);
+                            })),
+                    ),
+            )
     }
 
     fn render_sidebar(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
@@ -120,32 +253,34 @@ impl CloudBridgeApp {
             }))
     }
 
-    fn render_content(
-        &mut self,
-        _window: &mut Window,
-        _cx: &mut Context<Self>,
-    ) -> impl IntoElement {
-        match self.current_view {
-            CurrentView::Dashboard => div().size_full().child(self.dashboard_view.clone()),
-            CurrentView::Accounts => div().size_full().child(self.accounts_view.clone()),
-            CurrentView::Settings => div().size_full().child(self.settings_view.clone()),
+    fn render_content(views: &MainViews, current_view: CurrentView) -> impl IntoElement {
+        match current_view {
+            CurrentView::Dashboard => div().size_full().child(views.dashboard_view.clone()),
+            CurrentView::Accounts => div().size_full().child(views.accounts_view.clone()),
+            CurrentView::Settings => div().size_full().child(views.settings_view.clone()),
         }
     }
 }
 
 impl Render for CloudBridgeApp {
     fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let Some(views) = &self.views else {
+            return self.render_lock_screen(cx).into_any_element();
+        };
+        let content = Self::render_content(views, self.current_view);
+
         div()
             .size_full()
             .bg(cx.theme().background)
             .h_flex()
+            .on_mouse_move(cx.listener(|this, _: &MouseMoveEvent, _, _| {
+                this.last_activity = Instant::now();
+            }))
+            .capture_key_down(cx.listener(|this, _: &KeyDownEvent, _, _| {
+                this.last_activity = Instant::now();
+            }))
             .child(self.render_sidebar(window, cx))
-            .child(
-                div()
-                    .flex_1()
-                    .h_full()
-                    .overflow_hidden()
-                    .child(self.render_content(window, cx)),
-            )
+            .child(div().flex_1().h_full().overflow_hidden().child(content))
+            .into_any_element()
     }
 }
@@ -0,0 +1,529 @@
+//! Backup and restore
+//!
+//! A backup is a gzip-compressed tar archive holding a snapshot of the database,
+//! `config.json` and a manifest with the size and SHA-256 checksum of each file.
+//! Secrets are not part of it: cloud credentials and the SMTP password stay in the OS
+//! keyring, and the encryption key is removed from the archived configuration.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use duckdb::Connection;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+
+use crate::config::{get_app_data_dir, load_config, save_config, AppConfig};
+use crate::db;
+use crate::migrations;
+
+/// Archive layout version
+const FORMAT_VERSION: u32 = 1;
+
+const MANIFEST_ENTRY: &str = "manifest.json";
+const DATABASE_ENTRY: &str = "cloudbridge.duckdb";
+const CONFIG_ENTRY: &str = "config.json";
+
+/// File name prefix of automatic backups, which are rotated
+const AUTO_BACKUP_PREFIX: &str = "cloudbridge-auto-";
+
+/// Contents of a backup archive
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub format_version: u32,
+    pub app_version: String,
+    pub created_at: DateTime<Utc>,
+    /// Schema version of the archived database
+    pub schema_version: i64,
+    pub files: Vec<BackupFile>,
+}
+
+/// An archived file and its checksum
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupFile {
+    pub name: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+/// Directory holding automatic backups
+pub fn backup_dir() -> Result<PathBuf> {
+    let dir = get_app_data_dir()?.join("backups");
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// Scratch directory for snapshots and extracted archives
+fn staging_dir() -> Result<PathBuf> {
+    crate::config::scratch_dir("backup")
+}
+
+fn checksum(path: &Path) -> Result<BackupFile> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 64 * 1024];
+    let mut size = 0u64;
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        size += read as u64;
+    }
+
+    Ok(BackupFile {
+        name: path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        size,
+        sha256: hex::encode(hasher.finalize()),
+    })
+}
+
+/// Copy the database behind `conn` into a new database file at `target`
+fn snapshot_database(conn: &Connection, target: &Path) -> Result<()> {
+    let database: String = conn.query_row("SELECT current_database()", [], |row| row.get(0))?;
+    conn.execute_batch(&format!(
+        "ATTACH {} AS backup_snapshot; COPY FROM DATABASE \"{}\" TO backup_snapshot; DETACH backup_snapshot;",
+        db::sql_literal(&target.to_string_lossy()),
+        database.replace('"', "\"\"")
+    ))
+    .context("Failed to snapshot the database")?;
+    Ok(())
+}
+
+/// Write the archive for a snapshot in `staging` (which must contain the database copy)
+fn write_archive(
+    output: &Path,
+    staging: &Path,
+    config: &AppConfig,
+    schema_version: i64,
+) -> Result<BackupManifest> {
+    // Secrets never leave the machine
+    let mut config = config.clone();
+    config.encryption_key = None;
+    config.master_key = None;
+    fs::write(
+        staging.join(CONFIG_ENTRY),
+        serde_json::to_string_pretty(&config)?,
+    )?;
+
+    let manifest = BackupManifest {
+        format_version: FORMAT_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        created_at: Utc::now(),
+        schema_version,
+        files: vec![
+            checksum(&staging.join(DATABASE_ENTRY))?,
+            checksum(&staging.join(CONFIG_ENTRY))?,
+        ],
+    };
+    pack(output, &manifest, staging)?;
+    Ok(manifest)
+}
+
+/// Write `manifest` and the files it lists from `dir` into a .tar.gz at `output`
+fn pack(output: &Path, manifest: &BackupManifest, dir: &Path) -> Result<()> {
+    // Write next to the target first so an interrupted backup never leaves a partial archive
+    let partial = output.with_extension("partial");
+    let encoder = GzEncoder::new(File::create(&partial)?, Compression::default());
+    let mut archive = tar::Builder::new(encoder);
+
+    let manifest_json = serde_json::to_vec_pretty(manifest)?;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(manifest_json.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(manifest.created_at.timestamp().max(0) as u64);
+    header.set_cksum();
+    archive.append_data(&mut header, MANIFEST_ENTRY, manifest_json.as_slice())?;
+
+    for file in &manifest.files {
+        archive.append_path_with_name(dir.join(&file.name), &file.name)?;
+    }
+    archive.into_inner()?.finish()?;
+
+    fs::rename(&partial, output)?;
+    Ok(())
+}
+
+/// Extract an archive into `dir` and verify it against its manifest.
+///
+/// The manifest must be the first entry. Only regular files it lists, under the known
+/// flat names, are extracted; links and any other entry type are rejected.
+fn unpack(archive_path: &Path, dir: &Path) -> Result<BackupManifest> {
+    let file = File::open(archive_path)
+        .with_context(|| format!("Failed to open {}", archive_path.display()))?;
+    let mut archive = tar::Archive::new(GzDecoder::new(BufReader::new(file)));
+    let mut entries = archive.entries().context("Not a CloudBridge backup")?;
+
+    let mut manifest_entry = match entries.next() {
+        Some(entry) => entry?,
+        None => anyhow::bail!("Backup has no manifest"),
+    };
+    if manifest_entry.path()?.as_os_str() != MANIFEST_ENTRY
+        || manifest_entry.header().entry_type() != tar::EntryType::Regular
+    {
+        anyhow::bail!("Backup has no manifest");
+    }
+    let mut manifest_json = String::new();
+    manifest_entry.read_to_string(&mut manifest_json)?;
+    let manifest: BackupManifest =
+        serde_json::from_str(&manifest_json).context("Backup manifest is invalid")?;
+
+    let mut extracted: Vec<String> = Vec::new();
+    for entry in entries {
+        let mut entry = entry?;
+        let name = entry.path()?.to_string_lossy().into_owned();
+        match entry.header().entry_type() {
+            tar::EntryType::Directory => continue,
+            tar::EntryType::Regular => {}
+            other => anyhow::bail!("Unsupported entry '{}' ({:?}) in backup", name, other),
+        }
+        if ![DATABASE_ENTRY, CONFIG_ENTRY].contains(&name.as_str())
+            || !manifest.files.iter().any(|f| f.name == name)
+            || extracted.contains(&name)
+        {
+            anyhow::bail!("Unexpected file '{}' in backup", name);
+        }
+        let mut output = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(dir.join(&name))?;
+        std::io::copy(&mut entry, &mut output)?;
+        extracted.push(name);
+    }
+
+    if manifest.format_version > FORMAT_VERSION {
+        anyhow::bail!(
+            "Backup format {} is newer than this version of CloudBridge supports",
+            manifest.format_version
+        );
+    }
+
+    for required in [DATABASE_ENTRY, CONFIG_ENTRY] {
+        if !manifest.files.iter().any(|f| f.name == required) {
+            anyhow::bail!("Backup is missing {}", required);
+        }
+    }
+    for expected in &manifest.files {
+        let path = dir.join(&expected.name);
+        if !path.exists() {
+            anyhow::bail!("Backup is missing {}", expected.name);
+        }
+        let actual = checksum(&path)?;
+        if actual.size != expected.size || actual.sha256 != expected.sha256 {
+            anyhow::bail!(
+                "Checksum mismatch for {}; the backup is corrupt",
+                expected.name
+            );
+        }
+    }
+
+    if manifest.schema_version > migrations::latest_version() {
+        anyhow::bail!(
+            "Backup schema version {} is newer than this version of CloudBridge supports ({})",
+            manifest.schema_version,
+            migrations::latest_version()
+        );
+    }
+    let _: AppConfig = serde_json::from_str(&fs::read_to_string(dir.join(CONFIG_ENTRY))?)
+        .context("Backup configuration is invalid")?;
+    let conn =
+        Connection::open(dir.join(DATABASE_ENTRY)).context("Backup database is unreadable")?;
+    conn.query_row("SELECT COUNT(*) FROM cloud_accounts", [], |row| {
+        row.get::<_, i64>(0)
+    })
+    .context("Backup database has no accounts table")?;
+
+    Ok(manifest)
+}
+
+/// Run `f` with a scratch directory that is removed afterwards
+fn with_staging<T>(f: impl FnOnce(&Path) -> Result<T>) -> Result<T> {
+    let staging = staging_dir()?;
+    let result = f(&staging);
+    if let Err(e) = fs::remove_dir_all(&staging) {
+        tracing::warn!("Failed to remove {}: {}", staging.display(), e);
+    }
+    result
+}
+
+/// Back up `conn` and the saved configuration to `output`
+fn backup_connection(conn: &Connection, output: &Path) -> Result<BackupManifest> {
+    let config = load_config().unwrap_or_default();
+    with_staging(|staging| {
+        snapshot_database(conn, &staging.join(DATABASE_ENTRY))?;
+        write_archive(output, staging, &config, migrations::schema_version(conn)?)
+    })
+}
+
+/// Back up the open database and configuration
+pub fn create_backup(output: &Path) -> Result<BackupManifest> {
+    let manifest = db::with_connection(|conn| backup_connection(conn, output))?;
+    tracing::info!("Backup written to {}", output.display());
+    Ok(manifest)
+}
+
+/// Check an archive without restoring it
+pub fn verify_backup(archive: &Path) -> Result<BackupManifest> {
+    with_staging(|staging| unpack(archive, staging))
+}
+
+/// Write an automatic backup into `backup_dir()`, keeping the newest `keep`
+pub(crate) fn create_auto_backup(conn: &Connection, reason: &str, keep: u32) -> Result<PathBuf> {
+    let dir = backup_dir()?;
+    let path = dir.join(auto_backup_name(reason));
+    backup_connection(conn, &path)?;
+    rotate_backups(&dir, keep)?;
+    tracing::info!("Automatic backup written to {}", path.display());
+    Ok(path)
+}
+
+/// File name of a new automatic backup: timestamp first so names sort by age, and a
+/// random suffix so backups within the same second do not overwrite each other
+fn auto_backup_name(reason: &str) -> String {
+    format!(
+        "{}{}-{}-{}.tar.gz",
+        AUTO_BACKUP_PREFIX,
+        Utc::now().format("%Y%m%d-%H%M%S"),
+        reason,
+        &uuid::Uuid::new_v4().simple().to_string()[..8]
+    )
+}
+
+/// Delete all but the newest `keep` automatic backups in `dir`
+fn rotate_backups(dir: &Path, keep: u32) -> Result<()> {
+    let mut backups: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with(AUTO_BACKUP_PREFIX) && n.ends_with(".tar.gz"))
+        })
+        .collect();
+    // Names start with the timestamp, so they sort oldest first
+    backups.sort();
+
+    let excess = backups.len().saturating_sub(keep as usize);
+    for path in backups.into_iter().take(excess) {
+        fs::remove_file(&path)
+            .with_context(|| format!("Failed to remove old backup {}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Replace the database and configuration with a backup.
+///
+/// The archive is verified first, and the current state is saved as an automatic
+/// backup. The encryption key of this machine is kept, and the restored database is
+/// migrated to the current schema.
+pub fn restore_backup(archive: &Path) -> Result<BackupManifest> {
+    let current_config = load_config()?;
+
+    with_staging(|staging| {
+        let manifest = unpack(archive, staging)?;
+
+        let keep = current_config.auto_backups.max(1);
+        let saved = db::with_connection(|conn| create_auto_backup(conn, "pre-restore", keep))?;
+        tracing::info!("Saved current data to {}", saved.display());
+
+        let mut config: AppConfig =
+            serde_json::from_str(&fs::read_to_string(staging.join(CONFIG_ENTRY))?)?;
+        // Credentials in the keyring are protected by this machine's keys
+        config.encryption_key = current_config.encryption_key.clone();
+        config.master_key = current_config.master_key.clone();
+        config.secret_backend = current_config.secret_backend;
+
+        db::replace_database(&staging.join(DATABASE_ENTRY))?;
+        save_config(&config)?;
+
+        tracing::info!("Restored backup from {}", archive.display());
+        Ok(manifest)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_database() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        migrations::migrate(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO cloud_accounts (id, name, provider, region, created_at, last_synced_at, enabled)
+             VALUES ('acc-1', 'Prod', 'AWS', NULL, '2024-01-01T00:00:00+00:00', NULL, true)",
+            [],
+        )
+        .unwrap();
+        conn
+    }
+
+    fn snapshot(conn: &Connection, dir: &Path, output: &Path, config: &AppConfig) {
+        snapshot_database(conn, &dir.join(DATABASE_ENTRY)).unwrap();
+        write_archive(
+            output,
+            dir,
+            config,
+            migrations::schema_version(conn).unwrap(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_backup_round_trip_without_secrets() {
+        let conn = test_database();
+        let config = AppConfig {
+            encryption_key: Some("secret-key".to_string()),
+            refresh_interval_minutes: 60,
+            ..Default::default()
+        };
+
+        let source = staging_dir().unwrap();
+        let output = source.join("backup.tar.gz");
+        snapshot(&conn, &source, &output, &config);
+
+        let target = staging_dir().unwrap();
+        let manifest = unpack(&output, &target).unwrap();
+        assert_eq!(manifest.schema_version, migrations::latest_version());
+        assert_eq!(manifest.files.len(), 2);
+
+        let restored_config = fs::read_to_string(target.join(CONFIG_ENTRY)).unwrap();
+        assert!(!restored_config.contains("secret-key"));
+        let restored_config: AppConfig = serde_json::from_str(&restored_config).unwrap();
+        assert_eq!(restored_config.refresh_interval_minutes, 60);
+
+        let restored = Connection::open(target.join(DATABASE_ENTRY)).unwrap();
+        let name: String = restored
+            .query_row("SELECT name FROM cloud_accounts", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(name, "Prod");
+        drop(restored);
+
+        fs::remove_dir_all(source).unwrap();
+        fs::remove_dir_all(target).unwrap();
+    }
+
+    #[test]
+    fn test_corrupt_backup_is_rejected() {
+        let conn = test_database();
+        let source = staging_dir().unwrap();
+        let output = source.join("backup.tar.gz");
+        snapshot(&conn, &source, &output, &AppConfig::default());
+
+        // Repack with a config that no longer matches the manifest checksum
+        let manifest = verify_backup(&output).unwrap();
+        fs::write(source.join(CONFIG_ENTRY), "{\"tampered\": true}").unwrap();
+        let tampered = source.join("tampered.tar.gz");
+        pack(&tampered, &manifest, &source).unwrap();
+
+        let error = verify_backup(&tampered).unwrap_err().to_string();
+        assert!(
+            error.contains("Checksum mismatch for config.json"),
+            "{}",
+            error
+        );
+
+        fs::write(source.join("garbage.tar.gz"), b"not an archive").unwrap();
+        assert!(verify_backup(&source.join("garbage.tar.gz")).is_err());
+
+        fs::remove_dir_all(source).unwrap();
+    }
+
+    #[test]
+    fn test_rotate_backups() {
+        let dir = staging_dir().unwrap();
+        for stamp in ["20240101-000000", "20240102-000000", "20240103-000000"] {
+            fs::write(
+                dir.join(format!(
+                    "{}{}-pre-migration.tar.gz",
+                    AUTO_BACKUP_PREFIX, stamp
+                )),
+                b"",
+            )
+            .unwrap();
+        }
+        fs::write(dir.join("manual.tar.gz"), b"").unwrap();
+
+        rotate_backups(&dir, 2).unwrap();
+
+        let mut remaining: Vec<String> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        remaining.sort();
+        assert_eq!(
+            remaining,
+            vec![
+                "cloudbridge-auto-20240102-000000-pre-migration.tar.gz",
+                "cloudbridge-auto-20240103-000000-pre-migration.tar.gz",
+                "manual.tar.gz",
+            ]
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+
+        let first = auto_backup_name("pre-restore");
+        assert!(first.starts_with(AUTO_BACKUP_PREFIX) && first.ends_with(".tar.gz"));
+        assert_ne!(first, auto_backup_name("pre-restore"));
+    }
+
+    #[test]
+    fn test_links_and_unlisted_entries_are_rejected() {
+        let conn = test_database();
+        let source = staging_dir().unwrap();
+        let output = source.join("backup.tar.gz");
+        snapshot(&conn, &source, &output, &AppConfig::default());
+        let manifest = verify_backup(&output).unwrap();
+
+        // Manifest followed by a symlink named like a listed file
+        let linked = source.join("linked.tar.gz");
+        let mut archive = tar::Builder::new(GzEncoder::new(
+            File::create(&linked).unwrap(),
+            Compression::default(),
+        ));
+        let manifest_json = serde_json::to_vec(&manifest).unwrap();
+        let mut header = tar::Header::new_gnu();
+        header.set_size(manifest_json.len() as u64);
+        header.set_cksum();
+        archive
+            .append_data(&mut header, MANIFEST_ENTRY, manifest_json.as_slice())
+            .unwrap();
+        let mut link = tar::Header::new_gnu();
+        link.set_entry_type(tar::EntryType::Symlink);
+        link.set_size(0);
+        archive
+            .append_link(&mut link, CONFIG_ENTRY, "/etc/passwd")
+            .unwrap();
+        archive.into_inner().unwrap().finish().unwrap();
+        let error = verify_backup(&linked).unwrap_err().to_string();
+        assert!(error.contains("Unsupported entry"), "{}", error);
+
+        // A known name the manifest does not list
+        let mut partial = manifest.clone();
+        partial.files.retain(|f| f.name != CONFIG_ENTRY);
+        let unlisted = source.join("unlisted.tar.gz");
+        let mut archive = tar::Builder::new(GzEncoder::new(
+            File::create(&unlisted).unwrap(),
+            Compression::default(),
+        ));
+        let manifest_json = serde_json::to_vec(&partial).unwrap();
+        let mut header = tar::Header::new_gnu();
+        header.set_size(manifest_json.len() as u64);
+        header.set_cksum();
+        archive
+            .append_data(&mut header, MANIFEST_ENTRY, manifest_json.as_slice())
+            .unwrap();
+        archive
+            .append_path_with_name(source.join(CONFIG_ENTRY), CONFIG_ENTRY)
+            .unwrap();
+        archive.into_inner().unwrap().finish().unwrap();
+        let error = verify_backup(&unlisted).unwrap_err().to_string();
+        assert!(error.contains("Unexpected file 'config.json'"), "{}", error);
+
+        fs::remove_dir_all(source).unwrap();
+    }
+}
@@ -0,0 +1,235 @@
+//! Cache policy
+//!
+//! Decides how long cached provider data stays fresh. The time-to-live depends on the
+//! provider (Cost Explorer refreshes about three times a day, Aliyun bills daily,
+//! DeepSeek balances change in real time) and on the dataset, and can be overridden
+//! in config.json. Daily costs of closed months no longer change and never expire.
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+
+use crate::cloud::CloudProvider;
+use crate::config::{load_config, AppConfig};
+
+/// Time-to-live for providers without a specific default (hours)
+const DEFAULT_TTL_HOURS: f64 = 6.0;
+
+/// Days after a month ends during which providers may still revise its costs
+const CLOSED_MONTH_SETTLE_DAYS: i64 = 3;
+
+/// Kinds of cached provider data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheDataset {
+    Summary,
+    Trend,
+    Breakdown,
+    Commitments,
+    Anomalies,
+}
+
+impl CacheDataset {
+    pub const ALL: [CacheDataset; 5] = [
+        CacheDataset::Summary,
+        CacheDataset::Trend,
+        CacheDataset::Breakdown,
+        CacheDataset::Commitments,
+        CacheDataset::Anomalies,
+    ];
+
+    /// Key used in `cache_ttl_hours` overrides
+    pub fn key(&self) -> &'static str {
+        match self {
+            CacheDataset::Summary => "summary",
+            CacheDataset::Trend => "trend",
+            CacheDataset::Breakdown => "breakdown",
+            CacheDataset::Commitments => "commitments",
+            CacheDataset::Anomalies => "anomalies",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            CacheDataset::Summary => "Cost summary",
+            CacheDataset::Trend => "Daily trend",
+            CacheDataset::Breakdown => "Cost breakdown",
+            CacheDataset::Commitments => "Commitments",
+            CacheDataset::Anomalies => "Anomalies",
+        }
+    }
+
+    /// Cache table holding the dataset
+    pub(crate) fn table(&self) -> &'static str {
+        match self {
+            CacheDataset::Summary => "cost_summary_cache",
+            CacheDataset::Trend => "cost_trend_cache",
+            CacheDataset::Breakdown => "cost_breakdown_cache",
+            CacheDataset::Commitments => "commitment_cache",
+            CacheDataset::Anomalies => "anomaly_cache",
+        }
+    }
+}
+
+/// Cached data of one account and dataset
+#[derive(Debug, Clone)]
+pub struct CacheEntry {
+    pub account_id: String,
+    pub dataset: CacheDataset,
+    /// Cached rows (days for trends, records for breakdowns)
+    pub rows: usize,
+    /// When the dataset was last written
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Built-in time-to-live in hours
+fn default_ttl_hours(provider: CloudProvider, dataset: CacheDataset) -> f64 {
+    match (provider, dataset) {
+        // Savings Plans / RI utilization and anomaly detection are computed daily
+        (_, CacheDataset::Commitments | CacheDataset::Anomalies) => 24.0,
+        // Cost Explorer refreshes about three times a day
+        (CloudProvider::AWS, _) => 8.0,
+        // Aliyun bills are generated daily
+        (CloudProvider::Aliyun, _) => 12.0,
+        // DeepSeek balances are real time
+        (CloudProvider::DeepSeek, _) => 0.25,
+        _ => DEFAULT_TTL_HOURS,
+    }
+}
+
+/// Time-to-live of a dataset.
+///
+/// `cache_ttl_hours` entries keyed `provider.dataset` (e.g. `aws.trend`) take precedence
+/// over `provider` and then `dataset` keys, which take precedence over the defaults.
+pub fn ttl(config: &AppConfig, provider: CloudProvider, dataset: CacheDataset) -> Duration {
+    let provider_key = provider.short_name().to_lowercase();
+    let hours = [
+        format!("{}.{}", provider_key, dataset.key()),
+        provider_key,
+        dataset.key().to_string(),
+    ]
+    .iter()
+    .find_map(|key| config.cache_ttl_hours.get(key).copied())
+    .unwrap_or_else(|| default_ttl_hours(provider, dataset));
+
+    Duration::seconds((hours.max(0.0) * 3600.0) as i64)
+}
+
+/// Time-to-live of a dataset with the saved configuration
+pub fn ttl_for(provider: CloudProvider, dataset: CacheDataset) -> Duration {
+    ttl(&load_config().unwrap_or_default(), provider, dataset)
+}
+
+/// Whether data cached at `cached_at` is older than `ttl`
+pub fn is_expired(cached_at: DateTime<Utc>, ttl: Duration, now: DateTime<Utc>) -> bool {
+    now - cached_at > ttl
+}
+
+/// First day of the month after `date`
+fn next_month_start(date: NaiveDate) -> NaiveDate {
+    let (year, month) = if date.month() == 12 {
+        (date.year() + 1, 1)
+    } else {
+        (date.year(), date.month() + 1)
+    };
+    NaiveDate::from_ymd_opt(year, month, 1).unwrap_or(date)
+}
+
+/// Whether the cost of `date` (YYYY-MM-DD) was final when it was cached: its month had
+/// closed and settled by `cached_at`. Final rows are kept regardless of their age.
+pub fn is_final(date: &str, cached_at: DateTime<Utc>) -> bool {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map(|date| {
+            cached_at.date_naive()
+                >= next_month_start(date) + Duration::days(CLOSED_MONTH_SETTLE_DAYS)
+        })
+        .unwrap_or(false)
+}
+
+/// First day of the current month; dates from here on are still open
+pub fn open_month_start(today: NaiveDate) -> NaiveDate {
+    today.with_day(1).unwrap_or(today)
+}
+
+/// Human readable age, e.g. "just now", "25m ago" or "3h ago"
+pub fn format_age(age: Duration) -> String {
+    let minutes = age.num_minutes().max(0);
+    if minutes < 1 {
+        "just now".to_string()
+    } else if minutes < 60 {
+        format!("{}m ago", minutes)
+    } else if minutes < 48 * 60 {
+        format!("{}h ago", minutes / 60)
+    } else {
+        format!("{}d ago", minutes / (24 * 60))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ttl_overrides() {
+        let mut config = AppConfig::default();
+        assert_eq!(
+            ttl(&config, CloudProvider::AWS, CacheDataset::Summary),
+            Duration::hours(8)
+        );
+        assert_eq!(
+            ttl(&config, CloudProvider::DeepSeek, CacheDataset::Summary),
+            Duration::minutes(15)
+        );
+        assert_eq!(
+            ttl(&config, CloudProvider::Azure, CacheDataset::Trend),
+            Duration::hours(6)
+        );
+
+        config.cache_ttl_hours.insert("trend".to_string(), 2.0);
+        config.cache_ttl_hours.insert("aws".to_string(), 4.0);
+        config.cache_ttl_hours.insert("aws.trend".to_string(), 1.5);
+        assert_eq!(
+            ttl(&config, CloudProvider::AWS, CacheDataset::Trend),
+            Duration::minutes(90)
+        );
+        assert_eq!(
+            ttl(&config, CloudProvider::AWS, CacheDataset::Summary),
+            Duration::hours(4)
+        );
+        assert_eq!(
+            ttl(&config, CloudProvider::Aliyun, CacheDataset::Trend),
+            Duration::hours(2)
+        );
+        assert_eq!(
+            ttl(&config, CloudProvider::Aliyun, CacheDataset::Summary),
+            Duration::hours(12)
+        );
+    }
+
+    #[test]
+    fn test_closed_months_are_final() {
+        let at = |date: &str| {
+            DateTime::parse_from_rfc3339(&format!("{}T12:00:00Z", date))
+                .unwrap()
+                .with_timezone(&Utc)
+        };
+
+        // Cached after the month settled
+        assert!(is_final("2024-04-30", at("2024-05-04")));
+        assert!(is_final("2023-12-15", at("2024-01-04")));
+        // Cached while the month was open or still settling
+        assert!(!is_final("2024-04-30", at("2024-05-02")));
+        assert!(!is_final("2024-05-10", at("2024-05-20")));
+        assert!(!is_final("not-a-date", at("2024-05-20")));
+
+        let now = at("2024-05-20");
+        assert!(is_expired(
+            now - Duration::hours(9),
+            Duration::hours(8),
+            now
+        ));
+        assert!(!is_expired(
+            now - Duration::hours(7),
+            Duration::hours(8),
+            now
+        ));
+        assert_eq!(format_age(Duration::minutes(150)), "2h ago");
+    }
+}
@@ -0,0 +1,411 @@
+//! Command-line interface
+//!
+//! `cloudbridge <command> [options]` runs a task against the local store without
+//! opening the desktop window. Without a command the GUI starts as usual.
+
+use anyhow::{Context, Result};
+use chrono::{Datelike, NaiveDate, Utc};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::backup::{self, BackupManifest};
+use crate::crypto;
+use crate::db;
+use crate::digest;
+use crate::export::{self, ExportDataset, ExportFormat, ExportRequest};
+use crate::report::{self, ReportPeriod};
+use crate::transfer::{self, ConflictPolicy};
+
+/// Environment variable holding the passphrase for account exports
+const PASSPHRASE_ENV: &str = "CLOUDBRIDGE_PASSPHRASE";
+
+/// Environment variable holding the master passphrase, when master passphrase mode is on
+const MASTER_PASSPHRASE_ENV: &str = "CLOUDBRIDGE_MASTER_PASSPHRASE";
+
+const USAGE: &str = "\
+Usage: cloudbridge [command] [options]
+
+Without a command, the desktop application starts.
+
+Commands:
+  export         Export cost summaries, service breakdowns and daily trends
+      --output <file>       Output file (.csv, .jsonl, .parquet or .xlsx)
+      --format <format>     csv, jsonl, parquet or xlsx (default: from the file extension)
+      --from <YYYY-MM-DD>   Start date, inclusive (default: first day of this month)
+      --to <YYYY-MM-DD>     End date, exclusive (default: tomorrow)
+      --accounts <list>     Comma-separated account names or IDs (default: all enabled)
+      --datasets <list>     Comma-separated summary, services, daily (default: all)
+
+  export-focus   Export a FOCUS 1.0 dataset
+      --output <file>       Output file (.csv or .parquet)
+      --from <YYYY-MM-DD>   Start date, inclusive (default: first day of last month)
+      --to <YYYY-MM-DD>     End date, exclusive (default: tomorrow)
+
+  report         Render the monthly cost report
+      --output <file>       Output file (.html, or .pdf using a local Chrome/Chromium/Edge)
+      --month <YYYY-MM>     Month to report (default: last month)
+
+  send-digests   Email the scheduled report digests that are due
+      --force               Resend the latest period of every digest
+
+  backup         Archive the database and configuration (without secrets)
+      --output <file>       Output file (.tar.gz)
+
+  restore        Replace the database and configuration with a backup
+      --input <file>        Backup archive to restore
+      --verify-only         Check the archive's integrity without restoring
+
+  export-accounts  Export accounts with credentials, budgets and settings, encrypted
+      --output <file>       Output file
+                            (passphrase from CLOUDBRIDGE_PASSPHRASE or standard input)
+
+  import-accounts  Import an encrypted account export
+      --input <file>        Exported file
+      --on-conflict <mode>  skip, replace or keep-both for existing account IDs (default: skip)
+      --settings            Also replace settings (the local encryption keys are kept)
+
+  help           Show this message
+
+With a master passphrase set in Settings, commands that need credentials read it from
+CLOUDBRIDGE_MASTER_PASSPHRASE.";
+
+/// Run the command named by `args` (without the program name).
+///
+/// Returns None when no command was given and the GUI should start.
+pub fn run(args: &[String]) -> Option<Result<()>> {
+    let (command, rest) = args.split_first()?;
+    if let Ok(passphrase) = std::env::var(MASTER_PASSPHRASE_ENV) {
+        if let Err(e) = crypto::unlock(&passphrase) {
+            return Some(Err(e.context("Failed to unlock with the master passphrase")));
+        }
+    }
+    let result = match command.as_str() {
+        "export" => export_command(rest),
+        "export-focus" => export_focus_command(rest),
+        "report" => report_command(rest),
+        "send-digests" => send_digests_command(rest),
+        "backup" => backup_command(rest),
+        "restore" => restore_command(rest),
+        "export-accounts" => export_accounts_command(rest),
+        "import-accounts" => import_accounts_command(rest),
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        // Flags passed by the OS launcher (e.g. macOS -psn_*) are not commands
+        other if other.starts_with('-') => return None,
+        other => Err(anyhow::anyhow!(
+            "Unknown command '{}'. Run 'cloudbridge help' for usage.",
+            other
+        )),
+    };
+    Some(result)
+}
+
+/// Parse `--name value` and `--name=value` options, rejecting names not in `allowed`
+fn parse_options(args: &[String], allowed: &[&str]) -> Result<HashMap<String, String>> {
+    let mut options = HashMap::new();
+    let mut iter = args.iter();
+
+    while let Some(arg) = iter.next() {
+        let name = arg
+            .strip_prefix("--")
+            .ok_or_else(|| anyhow::anyhow!("Unexpected argument '{}'", arg))?;
+        let (name, value) = match name.split_once('=') {
+            Some((name, value)) => (name.to_string(), value.to_string()),
+            None => {
+                let value = iter
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("Missing value for --{}", name))?;
+                (name.to_string(), value.clone())
+            }
+        };
+        if !allowed.contains(&name.as_str()) {
+            anyhow::bail!("Unknown option --{}", name);
+        }
+        options.insert(name, value);
+    }
+
+    Ok(options)
+}
+
+/// Read a YYYY-MM-DD option, falling back to `default`
+fn date_option(
+    options: &HashMap<String, String>,
+    name: &str,
+    default: NaiveDate,
+) -> Result<String> {
+    let date = match options.get(name) {
+        Some(value) => NaiveDate::parse_from_str(value, "%Y-%m-%d")
+            .with_context(|| format!("--{} must be a date in YYYY-MM-DD format", name))?,
+        None => default,
+    };
+    Ok(date.format("%Y-%m-%d").to_string())
+}
+
+fn comma_list(value: &str) -> impl Iterator<Item = &str> {
+    value.split(',').map(str::trim).filter(|s| !s.is_empty())
+}
+
+fn export_command(args: &[String]) -> Result<()> {
+    let options = parse_options(
+        args,
+        &["output", "format", "from", "to", "accounts", "datasets"],
+    )?;
+
+    let output = PathBuf::from(
+        options
+            .get("output")
+            .ok_or_else(|| anyhow::anyhow!("--output is required"))?,
+    );
+    let format = match options.get("format") {
+        Some(name) => ExportFormat::from_name(name)
+            .ok_or_else(|| anyhow::anyhow!("Unsupported format '{}'", name))?,
+        None => ExportFormat::from_path(&output).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Cannot infer the format of {}; pass --format",
+                output.display()
+            )
+        })?,
+    };
+    let datasets = match options.get("datasets") {
+        Some(list) => comma_list(list)
+            .map(|name| {
+                ExportDataset::from_name(name)
+                    .ok_or_else(|| anyhow::anyhow!("Unknown dataset '{}'", name))
+            })
+            .collect::<Result<Vec<_>>>()?,
+        None => ExportDataset::ALL.to_vec(),
+    };
+
+    let today = Utc::now().date_naive();
+    let start_date = date_option(&options, "from", today.with_day(1).unwrap_or(today))?;
+    let end_date = date_option(&options, "to", today + chrono::Duration::days(1))?;
+
+    db::init_database()?;
+
+    let account_ids = match options.get("accounts") {
+        Some(list) => {
+            let accounts = db::get_all_accounts()?;
+            comma_list(list)
+                .map(|wanted| {
+                    accounts
+                        .iter()
+                        .find(|a| a.id == wanted || a.name.eq_ignore_ascii_case(wanted))
+                        .map(|a| a.id.clone())
+                        .ok_or_else(|| anyhow::anyhow!("Unknown account '{}'", wanted))
+                })
+                .collect::<Result<Vec<_>>>()?
+        }
+        None => Vec::new(),
+    };
+
+    let paths = export::export_costs(&ExportRequest {
+        account_ids,
+        start_date,
+        end_date,
+        datasets,
+        format,
+        output,
+    })?;
+    for path in paths {
+        println!("{}", path.display());
+    }
+    Ok(())
+}
+
+fn export_focus_command(args: &[String]) -> Result<()> {
+    let options = parse_options(args, &["output", "from", "to"])?;
+
+    let output = PathBuf::from(
+        options
+            .get("output")
+            .ok_or_else(|| anyhow::anyhow!("--output is required"))?,
+    );
+    let today = Utc::now().date_naive();
+    let last_month = today.with_day(1).unwrap_or(today) - chrono::Duration::days(1);
+    let start_date = date_option(
+        &options,
+        "from",
+        last_month.with_day(1).unwrap_or(last_month),
+    )?;
+    let end_date = date_option(&options, "to", today + chrono::Duration::days(1))?;
+
+    db::init_database()?;
+
+    let rows = export::export_focus(&output, &start_date, &end_date)?;
+    println!("Exported {} rows to {}", rows, output.display());
+    Ok(())
+}
+
+fn report_command(args: &[String]) -> Result<()> {
+    let options = parse_options(args, &["output", "month"])?;
+
+    let output = PathBuf::from(
+        options
+            .get("output")
+            .ok_or_else(|| anyhow::anyhow!("--output is required"))?,
+    );
+    let period = match options.get("month") {
+        Some(month) => ReportPeriod::month(month)?,
+        None => ReportPeriod::last_month(),
+    };
+
+    db::init_database()?;
+
+    let report = report::build_report(&period, &[])?;
+    report::write_report(&report, &output)?;
+    println!("{}", output.display());
+    Ok(())
+}
+
+fn send_digests_command(args: &[String]) -> Result<()> {
+    let force = args.iter().any(|a| a == "--force");
+    let rest: Vec<String> = args.iter().filter(|a| *a != "--force").cloned().collect();
+    parse_options(&rest, &[])?;
+
+    db::init_database()?;
+
+    let sent = digest::run_due_digests(force)?;
+    if sent.is_empty() {
+        println!("No digests due");
+    }
+    for name in sent {
+        println!("Sent {}", name);
+    }
+    Ok(())
+}
+
+fn backup_command(args: &[String]) -> Result<()> {
+    let options = parse_options(args, &["output"])?;
+    let output = PathBuf::from(
+        options
+            .get("output")
+            .ok_or_else(|| anyhow::anyhow!("--output is required"))?,
+    );
+
+    db::init_database()?;
+
+    let manifest = backup::create_backup(&output)?;
+    print_manifest(&manifest);
+    println!("{}", output.display());
+    Ok(())
+}
+
+fn restore_command(args: &[String]) -> Result<()> {
+    let verify_only = args.iter().any(|a| a == "--verify-only");
+    let rest: Vec<String> = args
+        .iter()
+        .filter(|a| *a != "--verify-only")
+        .cloned()
+        .collect();
+    let options = parse_options(&rest, &["input"])?;
+    let input = PathBuf::from(
+        options
+            .get("input")
+            .ok_or_else(|| anyhow::anyhow!("--input is required"))?,
+    );
+
+    if verify_only {
+        print_manifest(&backup::verify_backup(&input)?);
+        println!("Backup is intact");
+        return Ok(());
+    }
+
+    db::init_database()?;
+
+    print_manifest(&backup::restore_backup(&input)?);
+    println!("Restored {}", input.display());
+    Ok(())
+}
+
+/// Passphrase from the environment, or the first line of standard input
+fn read_passphrase() -> Result<String> {
+    if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV) {
+        return Ok(passphrase);
+    }
+    eprint!("Passphrase: ");
+    let mut line = String::new();
+    std::io::stdin().read_line(&mut line)?;
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+fn export_accounts_command(args: &[String]) -> Result<()> {
+    let options = parse_options(args, &["output"])?;
+    let output = PathBuf::from(
+        options
+            .get("output")
+            .ok_or_else(|| anyhow::anyhow!("--output is required"))?,
+    );
+    let passphrase = read_passphrase()?;
+
+    db::init_database()?;
+
+    let count = transfer::export_accounts(&output, &passphrase)?;
+    println!("Exported {} accounts to {}", count, output.display());
+    Ok(())
+}
+
+fn import_accounts_command(args: &[String]) -> Result<()> {
+    let include_settings = args.iter().any(|a| a == "--settings");
+    let rest: Vec<String> = args
+        .iter()
+        .filter(|a| *a != "--settings")
+        .cloned()
+        .collect();
+    let options = parse_options(&rest, &["input", "on-conflict"])?;
+    let input = PathBuf::from(
+        options
+            .get("input")
+            .ok_or_else(|| anyhow::anyhow!("--input is required"))?,
+    );
+    let policy = match options.get("on-conflict") {
+        Some(name) => ConflictPolicy::from_name(name)
+            .ok_or_else(|| anyhow::anyhow!("Unknown conflict mode '{}'", name))?,
+        None => ConflictPolicy::Skip,
+    };
+    let passphrase = read_passphrase()?;
+
+    db::init_database()?;
+
+    let summary = transfer::import_accounts(&input, &passphrase, policy, include_settings)?;
+    println!("{}", summary.describe());
+    Ok(())
+}
+
+fn print_manifest(manifest: &BackupManifest) {
+    println!(
+        "Backup from {} (CloudBridge {}, schema version {})",
+        manifest.created_at.format("%Y-%m-%d %H:%M UTC"),
+        manifest.app_version,
+        manifest.schema_version
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let options = parse_options(
+            &args(&["--output", "costs.csv", "--from=2024-05-01"]),
+            &["output", "from"],
+        )
+        .unwrap();
+        assert_eq!(options["output"], "costs.csv");
+        assert_eq!(options["from"], "2024-05-01");
+
+        assert!(parse_options(&args(&["--output"]), &["output"]).is_err());
+        assert!(parse_options(&args(&["--bogus", "1"]), &["output"]).is_err());
+        assert!(parse_options(&args(&["costs.csv"]), &["output"]).is_err());
+
+        assert!(run(&[]).is_none());
+        assert!(run(&args(&["-psn_0_12345"])).is_none());
+        assert!(run(&args(&["frobnicate"])).unwrap().is_err());
+    }
+}
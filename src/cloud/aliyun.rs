@@ -1,15 +1,212 @@
-//! Alibaba Cloud service implementation - using ureq + Alibaba Cloud signature
+//! Alibaba Cloud service implementation - using ureq + Alibaba Cloud signature V3
+//! (ACS3-HMAC-SHA256), optionally acting through a RAM role assumed with STS
 
-use anyhow::{anyhow, Result};
-use chrono::{Datelike, Utc};
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Datelike, Utc};
 use hmac::{Hmac, Mac};
 use serde::Deserialize;
-use sha1::Sha1;
-use std::collections::BTreeMap;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Mutex, OnceLock};
+
+use super::focus::{self, ChargeCategory};
+use super::http::{self, Endpoint, HttpRequest};
+use super::{
+    CloudAccount, CloudProvider, CloudService, CostData, CostDimension, CostSummary, FocusCost,
+    ProviderError, ServiceCost,
+};
+use crate::config::{AliyunMemberBilling, AliyunRole};
+use crate::redact;
+
+type HmacSha256 = Hmac<Sha256>;
+
+const ACS3_ALGORITHM: &str = "ACS3-HMAC-SHA256";
+const BSS_VERSION: &str = "2017-12-14";
+const STS_VERSION: &str = "2015-04-01";
+
+/// Assumed role credentials are renewed this long before they expire
+const ROLE_REFRESH_MARGIN_SECONDS: i64 = 300;
+
+/// Credentials from AssumeRole by access key and role ARN, shared by all service
+/// instances until shortly before they expire
+static ASSUMED_ROLES: OnceLock<Mutex<HashMap<String, AliyunCredentials>>> = OnceLock::new();
+
+/// Alibaba Cloud site an account is registered on, which serves its bills
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AliyunSite {
+    /// China site (aliyun.com), billed in CNY
+    China,
+    /// International site (alibabacloud.com), billed in USD
+    International,
+}
+
+impl AliyunSite {
+    /// Site from an account's region setting: `cn-*` regions (and no region) are the
+    /// China site, other regions or `intl` the international site
+    pub fn from_region(region: Option<&str>) -> Self {
+        let region = region.map(|r| r.trim().to_lowercase()).unwrap_or_default();
+        match region.as_str() {
+            "" | "cn" | "china" => AliyunSite::China,
+            "intl" | "international" => AliyunSite::International,
+            r if r.starts_with("cn-") => AliyunSite::China,
+            _ => AliyunSite::International,
+        }
+    }
+
+    pub fn bss_host(&self) -> &'static str {
+        match self {
+            AliyunSite::China => "business.aliyuncs.com",
+            AliyunSite::International => "business.ap-southeast-1.aliyuncs.com",
+        }
+    }
+
+    pub fn sts_host(&self) -> &'static str {
+        match self {
+            AliyunSite::China => "sts.aliyuncs.com",
+            AliyunSite::International => "sts.ap-southeast-1.aliyuncs.com",
+        }
+    }
+
+    /// Billing currency, for results that do not carry a `Currency`
+    pub fn currency(&self) -> &'static str {
+        match self {
+            AliyunSite::China => "CNY",
+            AliyunSite::International => "USD",
+        }
+    }
+}
+
+/// Credentials a request is signed with
+#[derive(Debug, Clone)]
+struct AliyunCredentials {
+    access_key_id: String,
+    access_key_secret: String,
+    /// STS token of an assumed role
+    security_token: Option<String>,
+    expiration: Option<DateTime<Utc>>,
+}
+
+impl AliyunCredentials {
+    fn access_key(access_key_id: &str, access_key_secret: &str) -> Self {
+        Self {
+            access_key_id: access_key_id.to_string(),
+            access_key_secret: access_key_secret.to_string(),
+            security_token: None,
+            expiration: None,
+        }
+    }
+
+    /// Whether the credentials stay valid past the refresh margin
+    fn is_fresh(&self, now: DateTime<Utc>) -> bool {
+        match self.expiration {
+            Some(expiration) => {
+                expiration - now > chrono::Duration::seconds(ROLE_REFRESH_MARGIN_SECONDS)
+            }
+            None => true,
+        }
+    }
+}
+
+/// RPC request signed with ACS3-HMAC-SHA256; parameters are sent in the query string
+struct Acs3Request<'a> {
+    method: &'a str,
+    host: &'a str,
+    action: &'a str,
+    version: &'a str,
+    query: &'a BTreeMap<String, String>,
+    body: &'a str,
+}
+
+impl Acs3Request<'_> {
+    fn canonical_query(&self) -> String {
+        self.query
+            .iter()
+            .map(|(k, v)| format!("{}={}", percent_encode(k), percent_encode(v)))
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+
+    /// Headers to send, `Authorization` included
+    fn sign(
+        &self,
+        credentials: &AliyunCredentials,
+        date: &str,
+        nonce: &str,
+    ) -> Vec<(String, String)> {
+        let content_hash = hex::encode(Sha256::digest(self.body.as_bytes()));
+
+        // 1. Canonical headers: host and every x-acs-* header, sorted by lowercase name
+        let mut headers: BTreeMap<&str, String> = BTreeMap::new();
+        headers.insert("host", self.host.to_string());
+        headers.insert("x-acs-action", self.action.to_string());
+        headers.insert("x-acs-content-sha256", content_hash.clone());
+        headers.insert("x-acs-date", date.to_string());
+        headers.insert("x-acs-signature-nonce", nonce.to_string());
+        headers.insert("x-acs-version", self.version.to_string());
+        if let Some(token) = &credentials.security_token {
+            headers.insert("x-acs-security-token", token.clone());
+        }
+
+        let canonical_headers: String = headers
+            .iter()
+            .map(|(k, v)| format!("{}:{}\n", k, v.trim()))
+            .collect();
+        let signed_headers = headers.keys().copied().collect::<Vec<_>>().join(";");
+
+        let canonical_request = format!(
+            "{}\n/\n{}\n{}\n{}\n{}",
+            self.method,
+            self.canonical_query(),
+            canonical_headers,
+            signed_headers,
+            content_hash
+        );
+
+        // 2. String to sign
+        let string_to_sign = format!(
+            "{}\n{}",
+            ACS3_ALGORITHM,
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
 
-use super::{CloudProvider, CloudService, CostData, CostSummary, ServiceCost};
+        // 3. Signature
+        let mut mac = HmacSha256::new_from_slice(credentials.access_key_secret.as_bytes())
+            .expect("HMAC can take key of any size");
+        mac.update(string_to_sign.as_bytes());
+        let signature = hex::encode(mac.finalize().into_bytes());
 
-type HmacSha1 = Hmac<Sha1>;
+        let mut result: Vec<(String, String)> = headers
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect();
+        result.push((
+            "Authorization".to_string(),
+            format!(
+                "{} Credential={},SignedHeaders={},Signature={}",
+                ACS3_ALGORITHM, credentials.access_key_id, signed_headers, signature
+            ),
+        ));
+        result
+    }
+}
+
+/// URL encoding (Alibaba Cloud's special encoding requirements)
+fn percent_encode(s: &str) -> String {
+    let mut result = String::new();
+    for c in s.chars() {
+        match c {
+            'A'..='Z' | 'a'..='z' | '0'..='9' | '-' | '_' | '.' | '~' => {
+                result.push(c);
+            }
+            _ => {
+                for byte in c.to_string().as_bytes() {
+                    result.push_str(&format!("%{:02X}", byte));
+                }
+            }
+        }
+    }
+    result
+}
 
 /// Alibaba Cloud service
 pub struct AliyunCloudService {
@@ -17,6 +214,13 @@ pub struct AliyunCloudService {
     account_name: String,
     access_key_id: String,
     access_key_secret: String,
+    /// RAM role the account acts through (`aliyun_roles` in config.json)
+    role: Option<AliyunRole>,
+    site: AliyunSite,
+    /// Member account whose share of the payer account's bill is queried (`BillOwnerId`)
+    bill_owner_id: Option<String>,
+    /// Endpoint overrides by service (`bss`, `sts`)
+    endpoints: BTreeMap<String, String>,
 }
 
 impl AliyunCloudService {
@@ -25,156 +229,317 @@ impl AliyunCloudService {
         account_name: String,
         access_key_id: String,
         access_key_secret: String,
-        _region: Option<String>,
+        region: Option<String>,
     ) -> Self {
+        let endpoints = http::endpoint_overrides(&account_id, &account_name, CloudProvider::Aliyun);
+        let role = crate::config::current_config()
+            .ok()
+            .and_then(|config| config.aliyun_role_for(&account_id, &account_name));
         Self {
             account_id,
             account_name,
             access_key_id,
             access_key_secret,
+            role,
+            site: AliyunSite::from_region(region.as_deref()),
+            bill_owner_id: None,
+            endpoints,
         }
     }
 
-    /// Calculate HMAC-SHA1 and return Base64 encoded result
-    fn hmac_sha1_base64(key: &str, data: &str) -> String {
-        let mut mac =
-            HmacSha1::new_from_slice(key.as_bytes()).expect("HMAC can take key of any size");
-        mac.update(data.as_bytes());
-        let result = mac.finalize().into_bytes();
-        base64::Engine::encode(&base64::engine::general_purpose::STANDARD, result)
+    /// Service for a stored account or a member sub-account of a payer account
+    pub fn for_account(account: &CloudAccount) -> Self {
+        let (parent_id, member_id) = super::split_member_account_id(&account.id);
+        let service = Self::new(
+            parent_id.to_string(),
+            account.name.clone(),
+            account.access_key_id.clone(),
+            account.secret_access_key.clone(),
+            account.region.clone(),
+        );
+        match member_id {
+            Some(member_id) => service.with_bill_owner(member_id),
+            None => service,
+        }
     }
 
-    /// URL encoding (Alibaba Cloud's special encoding requirements)
-    fn percent_encode(s: &str) -> String {
-        let mut result = String::new();
-        for c in s.chars() {
-            match c {
-                'A'..='Z' | 'a'..='z' | '0'..='9' | '-' | '_' | '.' | '~' => {
-                    result.push(c);
-                }
-                _ => {
-                    for byte in c.to_string().as_bytes() {
-                        result.push_str(&format!("%{:02X}", byte));
-                    }
-                }
-            }
-        }
-        result
+    /// Query only the costs of a member billed to this (payer) account; cost records
+    /// carry the member's sub-account ID
+    pub fn with_bill_owner(mut self, member_id: &str) -> Self {
+        self.account_id = super::member_account_id(&self.account_id, member_id);
+        self.bill_owner_id = Some(member_id.to_string());
+        self
     }
 
-    /// Generate Alibaba Cloud signature V1
-    fn sign_request(&self, params: &BTreeMap<String, String>) -> String {
-        // 1. Build canonical query string
-        let canonical_query: String = params
+    /// Send a signed RPC request and return the response body
+    fn call_rpc(
+        &self,
+        endpoint: &Endpoint,
+        action: &str,
+        version: &str,
+        params: &[(&str, &str)],
+        credentials: &AliyunCredentials,
+    ) -> Result<String> {
+        let query: BTreeMap<String, String> = params
             .iter()
-            .map(|(k, v)| format!("{}={}", Self::percent_encode(k), Self::percent_encode(v)))
-            .collect::<Vec<_>>()
-            .join("&");
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let request = Acs3Request {
+            method: "GET",
+            host: &endpoint.host,
+            action,
+            version,
+            query: &query,
+            body: "",
+        };
+        let url = endpoint.url(&format!("/?{}", request.canonical_query()));
+
+        // Signed again for every attempt: the nonce may be used only once
+        // Member sub-accounts are queried with, and limited as, their payer account
+        let (payer_id, _) = super::split_member_account_id(&self.account_id);
+        let response = http::send(CloudProvider::Aliyun, payer_id, "Alibaba Cloud API", || {
+            let date = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+            let nonce = uuid::Uuid::new_v4().simple().to_string();
+
+            let mut http_request = HttpRequest::get(url.as_str());
+            for (name, value) in request.sign(credentials, &date, &nonce) {
+                http_request = http_request.header(&name, &value);
+            }
+            Ok(http_request)
+        })?;
 
-        // 2. Build string to sign
-        let string_to_sign = format!(
-            "GET&{}&{}",
-            Self::percent_encode("/"),
-            Self::percent_encode(&canonical_query)
-        );
+        let status = response.status;
+        let retry_after = response.retry_after;
+        let body = response.body;
 
-        // 3. Calculate signature (key needs trailing &)
-        let sign_key = format!("{}&", self.access_key_secret);
-        Self::hmac_sha1_base64(&sign_key, &string_to_sign)
-    }
-
-    /// Generate common request parameters
-    fn common_params(&self, action: &str) -> BTreeMap<String, String> {
-        let mut params = BTreeMap::new();
-        params.insert("Format".to_string(), "JSON".to_string());
-        params.insert("Version".to_string(), "2017-12-14".to_string());
-        params.insert("AccessKeyId".to_string(), self.access_key_id.clone());
-        params.insert("SignatureMethod".to_string(), "HMAC-SHA1".to_string());
-        params.insert(
-            "Timestamp".to_string(),
-            Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string(),
-        );
-        params.insert("SignatureVersion".to_string(), "1.0".to_string());
-        params.insert(
-            "SignatureNonce".to_string(),
-            uuid::Uuid::new_v4().to_string(),
-        );
-        params.insert("Action".to_string(), action.to_string());
-        params
-    }
+        // Always print response body for debugging
+        tracing::debug!("Alibaba Cloud API response: {} HTTP {}", action, status);
 
-    /// Call Alibaba Cloud BSS API
-    fn call_bss_api(&self, action: &str, extra_params: &[(&str, &str)]) -> Result<String> {
-        let mut params = self.common_params(action);
+        let error = serde_json::from_str::<AliyunErrorResponse>(&body).ok();
 
-        for (k, v) in extra_params {
-            params.insert(k.to_string(), v.to_string());
+        if status >= 400 {
+            tracing::error!(
+                "Alibaba Cloud API error (HTTP {}): {}",
+                status,
+                redact::excerpt(&body)
+            );
+            let (code, message) = match &error {
+                Some(AliyunErrorResponse {
+                    code: Some(code),
+                    message,
+                }) => (code.as_str(), message.clone().unwrap_or_default()),
+                _ => ("", redact::excerpt(&body)),
+            };
+            return Err(classify_error(status, code, &message, retry_after).into());
         }
 
-        // Calculate signature
-        let signature = self.sign_request(&params);
-        params.insert("Signature".to_string(), signature);
+        // Check for business errors - Note: Alibaba Cloud returns "Success" as code on success
+        if let Some(AliyunErrorResponse {
+            code: Some(code),
+            message,
+        }) = &error
+        {
+            // Only treat as error when code is not "Success"
+            if code != "Success" {
+                let msg = message.clone().unwrap_or_default();
+                tracing::error!("Alibaba Cloud business error: {} - {}", code, msg);
+                return Err(classify_error(status, code, &msg, retry_after).into());
+            }
+        }
 
-        // Build URL
-        let query: String = params
-            .iter()
-            .map(|(k, v)| format!("{}={}", Self::percent_encode(k), Self::percent_encode(v)))
-            .collect::<Vec<_>>()
-            .join("&");
+        Ok(body)
+    }
 
-        let url = format!("https://business.aliyuncs.com/?{}", query);
+    /// Key of the account's assumed role in the shared cache
+    fn role_cache_key(&self, role_arn: &str) -> String {
+        format!("{}|{}", self.access_key_id, role_arn)
+    }
 
-        // Send request
-        let agent = ureq::Agent::config_builder()
-            .http_status_as_error(false)
-            .timeout_global(Some(std::time::Duration::from_secs(30)))
-            .build()
-            .new_agent();
+    /// Credentials to sign BSS requests with: the account's access key, or temporary
+    /// credentials of its RAM role, assumed again shortly before they expire
+    fn credentials(&self) -> Result<AliyunCredentials> {
+        let access_key =
+            AliyunCredentials::access_key(&self.access_key_id, &self.access_key_secret);
+        let Some(role) = &self.role else {
+            return Ok(access_key);
+        };
+        let role_arn = role.arn().ok_or_else(|| {
+            anyhow!(
+                "RAM role for {} needs role_arn or member_account_id",
+                self.account_name
+            )
+        })?;
+
+        let key = self.role_cache_key(&role_arn);
+        let cache = ASSUMED_ROLES.get_or_init(Default::default);
+        if let Some(cached) = cache.lock().ok().and_then(|c| c.get(&key).cloned()) {
+            if cached.is_fresh(Utc::now()) {
+                return Ok(cached);
+            }
+        }
 
-        let response = agent
-            .get(&url)
-            .call()
-            .map_err(|e| anyhow!("Alibaba Cloud API request failed: {}", e))?;
+        let assumed = self
+            .assume_role(&access_key, &role_arn, role)
+            .with_context(|| format!("Failed to assume RAM role {}", role_arn))?;
+        if let Ok(mut cache) = cache.lock() {
+            cache.insert(key, assumed.clone());
+        }
+        Ok(assumed)
+    }
 
-        let status = response.status().as_u16();
-        let body = response
-            .into_body()
-            .read_to_string()
-            .map_err(|e| anyhow!("Failed to read response: {}", e))?;
+    /// Drop the cached role credentials, e.g. after they were rejected
+    fn forget_assumed_role(&self) {
+        let Some(role_arn) = self.role.as_ref().and_then(|role| role.arn()) else {
+            return;
+        };
+        if let Some(Ok(mut cache)) = ASSUMED_ROLES.get().map(|c| c.lock()) {
+            cache.remove(&self.role_cache_key(&role_arn));
+        }
+    }
 
-        // Always print response body for debugging
-        tracing::debug!("Alibaba Cloud API response: HTTP {}", status);
+    /// STS AssumeRole
+    fn assume_role(
+        &self,
+        access_key: &AliyunCredentials,
+        role_arn: &str,
+        role: &AliyunRole,
+    ) -> Result<AliyunCredentials> {
+        let endpoint = Endpoint::resolve(&self.endpoints, "sts", self.site.sts_host())?;
+        let session_name = role.session_name.as_deref().unwrap_or("cloudbridge");
+        let duration = role.duration_seconds.to_string();
+
+        let body = self.call_rpc(
+            &endpoint,
+            "AssumeRole",
+            STS_VERSION,
+            &[
+                ("RoleArn", role_arn),
+                ("RoleSessionName", session_name),
+                ("DurationSeconds", &duration),
+            ],
+            access_key,
+        )?;
 
-        if status >= 400 {
-            tracing::error!("Alibaba Cloud API error (HTTP {}): {}", status, body);
-            return Err(anyhow!(
-                "Alibaba Cloud API request failed: HTTP {} - {}",
-                status,
-                body
-            ));
+        let credentials = parse_assume_role_response(&body)?;
+        redact::register_secret(&credentials.access_key_secret);
+        if let Some(token) = &credentials.security_token {
+            redact::register_secret(token);
         }
+        tracing::info!(
+            "Assumed RAM role for {} until {}",
+            self.account_name,
+            credentials
+                .expiration
+                .map(|e| e.to_rfc3339())
+                .unwrap_or_default()
+        );
+        Ok(credentials)
+    }
 
-        // Check for business errors - Note: Alibaba Cloud returns "Success" as code on success
-        if let Ok(error) = serde_json::from_str::<AliyunErrorResponse>(&body) {
-            if let Some(ref code) = error.code {
-                // Only treat as error when code is not "Success"
-                if code != "Success" {
-                    let msg = error.message.clone().unwrap_or_default();
-                    tracing::error!("Alibaba Cloud business error: {} - {}", code, msg);
-                    return Err(anyhow!("Alibaba Cloud API error: {} - {}", code, msg));
-                }
+    /// Call Alibaba Cloud BSS API
+    fn call_bss_api(&self, action: &str, extra_params: &[(&str, &str)]) -> Result<String> {
+        let endpoint = Endpoint::resolve(&self.endpoints, "bss", self.site.bss_host())?;
+        let credentials = self.credentials()?;
+
+        let mut params = extra_params.to_vec();
+        if let Some(owner) = &self.bill_owner_id {
+            params.push(("BillOwnerId", owner.as_str()));
+        }
+        let extra_params = params.as_slice();
+
+        match self.call_rpc(&endpoint, action, BSS_VERSION, extra_params, &credentials) {
+            // Role credentials revoked or expired early: assume the role again once
+            Err(e)
+                if credentials.security_token.is_some()
+                    && matches!(ProviderError::find(&e), Some(ProviderError::AuthFailed(_))) =>
+            {
+                tracing::warn!(
+                    "Role credentials of {} were rejected, assuming the role again",
+                    self.account_name
+                );
+                self.forget_assumed_role();
+                let credentials = self.credentials()?;
+                self.call_rpc(&endpoint, action, BSS_VERSION, extra_params, &credentials)
             }
+            result => result,
         }
-
-        Ok(body)
     }
 
     /// Query bill overview
     fn query_bill_overview(&self, billing_cycle: &str) -> Result<BillOverviewResponse> {
         let body = self.call_bss_api("QueryBillOverview", &[("BillingCycle", billing_cycle)])?;
 
-        serde_json::from_str(&body)
-            .map_err(|e| anyhow!("Failed to parse bill overview: {} - {}", e, body))
+        serde_json::from_str(&body).map_err(|e| {
+            anyhow!(
+                "Failed to parse bill overview: {} - {}",
+                e,
+                redact::excerpt(&body)
+            )
+        })
+    }
+
+    /// Query all account bill items of a billing cycle, split by owner account and product
+    fn query_account_bill_all(&self, billing_cycle: &str) -> Result<Vec<AccountBillItem>> {
+        let mut items = Vec::new();
+
+        for page in 1..=MAX_INSTANCE_BILL_PAGES {
+            let page_num = page.to_string();
+            let body = self.call_bss_api(
+                "QueryAccountBill",
+                &[
+                    ("BillingCycle", billing_cycle),
+                    ("IsGroupByProduct", "true"),
+                    ("PageNum", &page_num),
+                    ("PageSize", "300"),
+                ],
+            )?;
+            let response: AccountBillResponse = serde_json::from_str(&body).map_err(|e| {
+                anyhow!(
+                    "Failed to parse account bill: {} - {}",
+                    e,
+                    redact::excerpt(&body)
+                )
+            })?;
+
+            let data = response.data.unwrap_or_default();
+            let page_items = data.items.and_then(|i| i.item).unwrap_or_default();
+            let done = page_items.is_empty()
+                || items.len() + page_items.len() >= data.total_count.unwrap_or(0) as usize;
+            items.extend(page_items);
+            if done {
+                return Ok(items);
+            }
+        }
+
+        tracing::warn!(
+            "Account bill for {} truncated after {} pages",
+            billing_cycle,
+            MAX_INSTANCE_BILL_PAGES
+        );
+        Ok(items)
+    }
+
+    /// Summaries of the members billed to this payer account, as sub-accounts
+    pub fn get_member_cost_summaries(
+        &self,
+        billing: &AliyunMemberBilling,
+    ) -> Result<Vec<CostSummary>> {
+        let now = Utc::now();
+        let current_month = format!("{}-{:02}", now.year(), now.month());
+        let last_month_date = now - chrono::Duration::days(now.day() as i64 + 1);
+        let last_month = format!("{}-{:02}", last_month_date.year(), last_month_date.month());
+
+        let current = self.query_account_bill_all(&current_month)?;
+        let last = self.query_account_bill_all(&last_month)?;
+
+        Ok(member_cost_summaries(
+            &self.account_id,
+            &self.account_name,
+            billing,
+            &current,
+            &last,
+            self.site.currency(),
+        ))
     }
 
     /// Query instance bill (daily details)
@@ -192,8 +557,13 @@ impl AliyunCloudService {
             ],
         )?;
 
-        serde_json::from_str(&body)
-            .map_err(|e| anyhow!("Failed to parse instance bill: {} - {}", e, body))
+        serde_json::from_str(&body).map_err(|e| {
+            anyhow!(
+                "Failed to parse instance bill: {} - {}",
+                e,
+                redact::excerpt(&body)
+            )
+        })
     }
 
     /// Query instance bill for a specific date (daily granularity requires BillingDate)
@@ -212,11 +582,62 @@ impl AliyunCloudService {
             ],
         )?;
 
-        serde_json::from_str(&body)
-            .map_err(|e| anyhow!("Failed to parse instance bill: {} - {}", e, body))
+        serde_json::from_str(&body).map_err(|e| {
+            anyhow!(
+                "Failed to parse instance bill: {} - {}",
+                e,
+                redact::excerpt(&body)
+            )
+        })
+    }
+
+    /// Query all monthly instance bill items of a billing cycle, following NextToken pagination
+    fn describe_instance_bill_all(&self, billing_cycle: &str) -> Result<Vec<InstanceBillItem>> {
+        let mut items = Vec::new();
+        let mut next_token: Option<String> = None;
+
+        for _ in 0..MAX_INSTANCE_BILL_PAGES {
+            let mut params = vec![
+                ("BillingCycle", billing_cycle),
+                ("Granularity", "MONTHLY"),
+                ("MaxResults", "300"),
+            ];
+            if let Some(token) = next_token.as_deref() {
+                params.push(("NextToken", token));
+            }
+
+            let body = self.call_bss_api("DescribeInstanceBill", &params)?;
+            let response: InstanceBillResponse = serde_json::from_str(&body).map_err(|e| {
+                anyhow!(
+                    "Failed to parse instance bill: {} - {}",
+                    e,
+                    redact::excerpt(&body)
+                )
+            })?;
+
+            let Some(data) = response.data else {
+                break;
+            };
+            items.extend(data.items.unwrap_or_default());
+
+            match data.next_token {
+                Some(token) if !token.is_empty() => next_token = Some(token),
+                _ => return Ok(items),
+            }
+        }
+
+        tracing::warn!(
+            "Instance bill for {} truncated after {} pages",
+            billing_cycle,
+            MAX_INSTANCE_BILL_PAGES
+        );
+        Ok(items)
     }
 }
 
+/// Maximum number of DescribeInstanceBill pages fetched per billing cycle
+const MAX_INSTANCE_BILL_PAGES: usize = 20;
+
 impl CloudService for AliyunCloudService {
     fn validate_credentials(&self) -> Result<bool> {
         // Try calling a simple API to validate credentials
@@ -225,10 +646,11 @@ impl CloudService for AliyunCloudService {
 
         match self.query_bill_overview(&billing_cycle) {
             Ok(_) => Ok(true),
-            Err(e) => {
+            Err(e) if matches!(ProviderError::find(&e), Some(ProviderError::AuthFailed(_))) => {
                 tracing::error!("Alibaba Cloud credential validation failed: {}", e);
                 Ok(false)
             }
+            Err(e) => Err(e),
         }
     }
 
@@ -249,7 +671,10 @@ impl CloudService for AliyunCloudService {
                         date,
                         service: item.product_name.unwrap_or_else(|| "Unknown".to_string()),
                         amount: item.pretax_amount.unwrap_or(0.0),
-                        currency: "CNY".to_string(),
+                        currency: item
+                            .currency
+                            .unwrap_or_else(|| self.site.currency().to_string()),
+                        dimensions: BTreeMap::new(),
                     });
                 }
             }
@@ -272,8 +697,10 @@ impl CloudService for AliyunCloudService {
         let last_overview = self.query_bill_overview(&last_month)?;
 
         // Parse current month costs
-        let (current_month_cost, current_month_details) = parse_bill_overview(&current_overview);
-        let (last_month_cost, last_month_details) = parse_bill_overview(&last_overview);
+        let (current_month_cost, current_month_details, currency) =
+            parse_bill_overview(&current_overview, self.site.currency());
+        let (last_month_cost, last_month_details, _) =
+            parse_bill_overview(&last_overview, self.site.currency());
 
         // Calculate month-over-month change
         let month_over_month_change = if last_month_cost > 0.0 {
@@ -290,7 +717,7 @@ impl CloudService for AliyunCloudService {
             provider: CloudProvider::Aliyun,
             current_month_cost,
             last_month_cost,
-            currency: "CNY".to_string(),
+            currency,
             month_over_month_change,
             current_month_details,
             last_month_details,
@@ -310,6 +737,7 @@ impl CloudService for AliyunCloudService {
         let end = NaiveDate::parse_from_str(end_date, "%Y-%m-%d")
             .map_err(|e| anyhow!("Invalid end date: {}", e))?;
 
+        let mut currency: Option<String> = None;
         let mut current = start;
         while current < end {
             let date_str = current.format("%Y-%m-%d").to_string();
@@ -322,6 +750,9 @@ impl CloudService for AliyunCloudService {
                         for item in items {
                             let amount = item.pretax_amount.unwrap_or(0.0);
                             day_total += amount;
+                            if currency.is_none() {
+                                currency = item.currency;
+                            }
                         }
                         if day_total > 0.0 {
                             daily_map.insert(date_str.clone(), day_total);
@@ -346,16 +777,305 @@ impl CloudService for AliyunCloudService {
 
         Ok(super::CostTrend {
             account_id: self.account_id.clone(),
-            currency: "CNY".to_string(),
+            currency: currency.unwrap_or_else(|| self.site.currency().to_string()),
             daily_costs,
         })
     }
+
+    fn get_cost_breakdown(
+        &self,
+        start_date: &str,
+        end_date: &str,
+        _dimension: &CostDimension,
+    ) -> Result<Vec<CostData>> {
+        // Instance bills carry every dimension, so all of them are filled in at once
+        let mut costs = Vec::new();
+        for billing_cycle in billing_cycles(start_date, end_date)? {
+            let items = self.describe_instance_bill_all(&billing_cycle)?;
+            let date = format!("{}-01", billing_cycle);
+            costs.extend(items.into_iter().filter_map(|item| {
+                instance_bill_item_to_cost(&self.account_id, &date, self.site.currency(), item)
+            }));
+        }
+
+        Ok(costs)
+    }
+
+    fn get_focus_costs(&self, start_date: &str, end_date: &str) -> Result<Vec<FocusCost>> {
+        let mut costs = Vec::new();
+        for billing_cycle in billing_cycles(start_date, end_date)? {
+            let items = self.describe_instance_bill_all(&billing_cycle)?;
+            let date = format!("{}-01", billing_cycle);
+            let period_end = focus::next_month_start(&date);
+            costs.extend(items.into_iter().filter_map(|item| {
+                instance_bill_item_to_focus(
+                    &self.account_id,
+                    &self.account_name,
+                    &date,
+                    &period_end,
+                    self.site.currency(),
+                    item,
+                )
+            }));
+        }
+
+        Ok(costs)
+    }
 }
 
-/// Parse bill overview
-fn parse_bill_overview(response: &BillOverviewResponse) -> (f64, Vec<ServiceCost>) {
+/// Map an instance bill item to a FOCUS record (gross amount is the list cost)
+fn instance_bill_item_to_focus(
+    account_id: &str,
+    account_name: &str,
+    date: &str,
+    period_end: &str,
+    default_currency: &str,
+    item: InstanceBillItem,
+) -> Option<FocusCost> {
+    let billed_cost = item.pretax_amount.unwrap_or(0.0);
+    let list_cost = item.pretax_gross_amount.unwrap_or(billed_cost);
+    if billed_cost == 0.0 && list_cost == 0.0 {
+        return None;
+    }
+
+    let service = item
+        .product_name
+        .clone()
+        .unwrap_or_else(|| "Unknown".to_string());
+    let non_empty = |value: Option<String>| value.filter(|v| !v.is_empty());
+    // Product codes (ecs, oss, rds, ...) are more reliable than localized product names
+    let service_category = match focus::service_category(item.product_code.as_deref().unwrap_or(""))
+    {
+        "Other" => focus::service_category(&service),
+        category => category,
+    };
+
+    Some(FocusCost {
+        account_id: account_id.to_string(),
+        billed_cost,
+        effective_cost: billed_cost,
+        list_cost,
+        billing_currency: item
+            .currency
+            .clone()
+            .unwrap_or_else(|| default_currency.to_string()),
+        charge_category: ChargeCategory::infer(&service, billed_cost),
+        charge_period_start: date.to_string(),
+        charge_period_end: period_end.to_string(),
+        provider_name: focus::provider_name(CloudProvider::Aliyun).to_string(),
+        service_category: service_category.to_string(),
+        service_name: service,
+        region_id: non_empty(item.region),
+        resource_id: non_empty(item.instance_id),
+        sub_account_id: None,
+        sub_account_name: Some(account_name.to_string()),
+        tags: parse_tag_string(item.tag.as_deref().unwrap_or_default())
+            .into_iter()
+            .filter(|(_, value)| !value.is_empty())
+            .collect(),
+    })
+}
+
+/// List billing cycles (YYYY-MM) covered by [start_date, end_date)
+fn billing_cycles(start_date: &str, end_date: &str) -> Result<Vec<String>> {
+    use chrono::NaiveDate;
+
+    let start = NaiveDate::parse_from_str(start_date, "%Y-%m-%d")
+        .map_err(|e| anyhow!("Invalid start date: {}", e))?;
+    let end = NaiveDate::parse_from_str(end_date, "%Y-%m-%d")
+        .map_err(|e| anyhow!("Invalid end date: {}", e))?;
+
+    let mut cycles = Vec::new();
+    let mut current = start.with_day(1).unwrap_or(start);
+    while current < end {
+        cycles.push(current.format("%Y-%m").to_string());
+        current = current
+            .checked_add_months(chrono::Months::new(1))
+            .ok_or_else(|| anyhow!("Date out of range"))?;
+    }
+
+    Ok(cycles)
+}
+
+/// Parse an instance bill tag string ("key:team value:platform; key:env value:prod")
+fn parse_tag_string(tags: &str) -> Vec<(String, String)> {
+    tags.split(';')
+        .filter_map(|pair| {
+            let pair = pair.trim();
+            let rest = pair.strip_prefix("key:")?;
+            let (key, value) = match rest.split_once(" value:") {
+                Some((k, v)) => (k.trim(), v.trim()),
+                None => (rest.trim(), ""),
+            };
+            if key.is_empty() {
+                None
+            } else {
+                Some((key.to_string(), value.to_string()))
+            }
+        })
+        .collect()
+}
+
+/// Convert an instance bill item into a cost record carrying all known dimensions
+fn instance_bill_item_to_cost(
+    account_id: &str,
+    date: &str,
+    default_currency: &str,
+    item: InstanceBillItem,
+) -> Option<CostData> {
+    let amount = item.pretax_amount.unwrap_or(0.0);
+    if amount == 0.0 {
+        return None;
+    }
+
+    let service = item.product_name.unwrap_or_else(|| "Unknown".to_string());
+    let mut dimensions = BTreeMap::new();
+    dimensions.insert(CostDimension::Service.key(), service.clone());
+
+    let fields = [
+        (CostDimension::Region, item.region),
+        (CostDimension::UsageType, item.billing_item),
+        (CostDimension::InstanceType, item.instance_spec),
+        (CostDimension::ResourceGroup, item.resource_group),
+    ];
+    for (dimension, value) in fields {
+        if let Some(value) = value.filter(|v| !v.is_empty()) {
+            dimensions.insert(dimension.key(), value);
+        }
+    }
+    for (key, value) in parse_tag_string(item.tag.as_deref().unwrap_or_default()) {
+        dimensions.insert(CostDimension::Tag(key).key(), value);
+    }
+
+    Some(CostData {
+        account_id: account_id.to_string(),
+        date: date.to_string(),
+        service,
+        amount,
+        currency: item
+            .currency
+            .unwrap_or_else(|| default_currency.to_string()),
+        dimensions,
+    })
+}
+
+/// Per-member summaries from the account bills of the current and last month. Configured
+/// members are listed in order (also without costs), discovered ones by current cost.
+fn member_cost_summaries(
+    parent_id: &str,
+    parent_name: &str,
+    billing: &AliyunMemberBilling,
+    current: &[AccountBillItem],
+    last: &[AccountBillItem],
+    default_currency: &str,
+) -> Vec<CostSummary> {
+    let members: Vec<(String, String)> = if billing.members.is_empty() {
+        let mut owners: Vec<(String, String)> = Vec::new();
+        for item in current.iter().chain(last) {
+            let Some(owner_id) = item.owner_id.as_deref().filter(|id| !id.is_empty()) else {
+                continue;
+            };
+            if !owners.iter().any(|(id, _)| id == owner_id) {
+                let name = item
+                    .owner_name
+                    .clone()
+                    .filter(|name| !name.is_empty())
+                    .unwrap_or_else(|| owner_id.to_string());
+                owners.push((owner_id.to_string(), name));
+            }
+        }
+        owners
+    } else {
+        billing
+            .members
+            .iter()
+            .map(|member| (member.id.clone(), member.label().to_string()))
+            .collect()
+    };
+
+    let details_of = |items: &[AccountBillItem], owner_id: &str| {
+        let mut by_product: BTreeMap<String, f64> = BTreeMap::new();
+        let mut currency = None;
+        for item in items
+            .iter()
+            .filter(|item| item.owner_id.as_deref() == Some(owner_id))
+        {
+            let product = item
+                .product_name
+                .clone()
+                .unwrap_or_else(|| "Unknown".to_string());
+            *by_product.entry(product).or_insert(0.0) += item.pretax_amount.unwrap_or(0.0);
+            currency = currency.or_else(|| item.currency.clone());
+        }
+
+        let currency = currency.unwrap_or_else(|| default_currency.to_string());
+        let mut details: Vec<ServiceCost> = by_product
+            .into_iter()
+            .filter(|(_, amount)| *amount > 0.0)
+            .map(|(service, amount)| ServiceCost {
+                service,
+                amount,
+                currency: currency.clone(),
+            })
+            .collect();
+        details.sort_by(|a, b| {
+            b.amount
+                .partial_cmp(&a.amount)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        (
+            details.iter().map(|d| d.amount).sum::<f64>(),
+            details,
+            currency,
+        )
+    };
+
+    let mut summaries: Vec<CostSummary> = members
+        .into_iter()
+        .map(|(member_id, member_name)| {
+            let (current_month_cost, current_month_details, currency) =
+                details_of(current, &member_id);
+            let (last_month_cost, last_month_details, _) = details_of(last, &member_id);
+            let month_over_month_change = if last_month_cost > 0.0 {
+                ((current_month_cost - last_month_cost) / last_month_cost) * 100.0
+            } else if current_month_cost > 0.0 {
+                100.0
+            } else {
+                0.0
+            };
+
+            CostSummary {
+                account_id: super::member_account_id(parent_id, &member_id),
+                account_name: super::member_account_name(parent_name, &member_name),
+                provider: CloudProvider::Aliyun,
+                current_month_cost,
+                last_month_cost,
+                currency,
+                month_over_month_change,
+                current_month_details,
+                last_month_details,
+            }
+        })
+        .collect();
+
+    if billing.members.is_empty() {
+        summaries.sort_by(|a, b| {
+            b.current_month_cost
+                .partial_cmp(&a.current_month_cost)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+    }
+    summaries
+}
+
+/// Parse bill overview into the total, costs by product and the bill currency
+fn parse_bill_overview(
+    response: &BillOverviewResponse,
+    default_currency: &str,
+) -> (f64, Vec<ServiceCost>, String) {
     let mut total_cost = 0.0;
     let mut details = Vec::new();
+    let mut bill_currency = None;
 
     if let Some(data) = &response.data {
         if let Some(items_wrapper) = &data.items {
@@ -363,6 +1083,11 @@ fn parse_bill_overview(response: &BillOverviewResponse) -> (f64, Vec<ServiceCost
                 for item in items {
                     let amount = item.pretax_amount.unwrap_or(0.0);
                     total_cost += amount;
+                    let currency = item
+                        .currency
+                        .clone()
+                        .unwrap_or_else(|| default_currency.to_string());
+                    bill_currency.get_or_insert_with(|| currency.clone());
 
                     if amount > 0.0 {
                         details.push(ServiceCost {
@@ -371,7 +1096,7 @@ fn parse_bill_overview(response: &BillOverviewResponse) -> (f64, Vec<ServiceCost
                                 .clone()
                                 .unwrap_or_else(|| "Unknown".to_string()),
                             amount,
-                            currency: "CNY".to_string(),
+                            currency,
                         });
                     }
                 }
@@ -386,7 +1111,8 @@ fn parse_bill_overview(response: &BillOverviewResponse) -> (f64, Vec<ServiceCost
             .unwrap_or(std::cmp::Ordering::Equal)
     });
 
-    (total_cost, details)
+    let currency = bill_currency.unwrap_or_else(|| default_currency.to_string());
+    (total_cost, details, currency)
 }
 
 // ==================== Response Structs ====================
@@ -401,6 +1127,86 @@ struct AliyunErrorResponse {
     message: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct AssumeRoleResponse {
+    credentials: Option<AssumeRoleCredentials>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct AssumeRoleCredentials {
+    access_key_id: String,
+    access_key_secret: String,
+    security_token: String,
+    /// e.g. "2015-04-09T11:52:19Z"
+    expiration: String,
+}
+
+/// Parse an STS AssumeRole response into temporary credentials
+fn parse_assume_role_response(json: &str) -> Result<AliyunCredentials> {
+    let response: AssumeRoleResponse = serde_json::from_str(json)
+        .map_err(|e| anyhow!("Failed to parse AssumeRole response: {}", e))?;
+    let credentials = response
+        .credentials
+        .ok_or_else(|| anyhow!("AssumeRole response has no credentials"))?;
+    let expiration = DateTime::parse_from_rfc3339(&credentials.expiration)
+        .map_err(|e| anyhow!("Invalid AssumeRole expiration: {}", e))?
+        .with_timezone(&Utc);
+
+    Ok(AliyunCredentials {
+        access_key_id: credentials.access_key_id,
+        access_key_secret: credentials.access_key_secret,
+        security_token: Some(credentials.security_token),
+        expiration: Some(expiration),
+    })
+}
+
+/// Classify an Alibaba Cloud error code (e.g. `InvalidAccessKeyId.NotFound`,
+/// `Forbidden.RAM`, `Throttling.User`)
+fn classify_error(
+    status: u16,
+    code: &str,
+    message: &str,
+    retry_after: Option<std::time::Duration>,
+) -> ProviderError {
+    let detail = if code.is_empty() {
+        format!(
+            "Alibaba Cloud API request failed: HTTP {} - {}",
+            status, message
+        )
+    } else {
+        format!(
+            "Alibaba Cloud API error: {} - {}",
+            code,
+            redact::redact(message)
+        )
+    };
+
+    let family = code.split('.').next().unwrap_or_default();
+    match family {
+        "InvalidAccessKeyId"
+        | "InvalidAccessKeySecret"
+        | "SignatureDoesNotMatch"
+        | "IncompleteSignature"
+        | "InvalidSecurityToken" => ProviderError::AuthFailed(detail),
+        "Forbidden" | "NoPermission" | "NotAuthorized" => ProviderError::PermissionDenied {
+            action: None,
+            message: detail,
+        },
+        "Throttling" | "ServiceUnavailable" => ProviderError::Throttled(retry_after),
+        _ => match status {
+            401 => ProviderError::AuthFailed(detail),
+            403 => ProviderError::PermissionDenied {
+                action: None,
+                message: detail,
+            },
+            429 => ProviderError::Throttled(retry_after),
+            _ => ProviderError::Unexpected(detail),
+        },
+    }
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 #[allow(dead_code)]
@@ -441,6 +1247,37 @@ struct BillOverviewItem {
     currency: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct AccountBillResponse {
+    data: Option<AccountBillData>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct AccountBillData {
+    total_count: Option<i32>,
+    items: Option<AccountBillItems>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct AccountBillItems {
+    item: Option<Vec<AccountBillItem>>,
+}
+
+/// QueryAccountBill item: costs of one owner account (and product)
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct AccountBillItem {
+    #[serde(rename = "OwnerID")]
+    owner_id: Option<String>,
+    owner_name: Option<String>,
+    product_name: Option<String>,
+    pretax_amount: Option<f64>,
+    currency: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 #[allow(dead_code)]
@@ -477,4 +1314,313 @@ struct InstanceBillItem {
     #[serde(rename = "PretaxGrossAmount")]
     pretax_gross_amount: Option<f64>,
     currency: Option<String>,
+    region: Option<String>,
+    billing_item: Option<String>,
+    instance_spec: Option<String>,
+    resource_group: Option<String>,
+    /// Tags in the form "key:team value:platform; key:env value:prod"
+    tag: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_error() {
+        assert!(matches!(
+            classify_error(
+                404,
+                "InvalidAccessKeyId.NotFound",
+                "Specified access key is not found.",
+                None
+            ),
+            ProviderError::AuthFailed(_)
+        ));
+        assert!(matches!(
+            classify_error(
+                400,
+                "SignatureDoesNotMatch",
+                "Specified signature is not matched",
+                None
+            ),
+            ProviderError::AuthFailed(_)
+        ));
+        assert_eq!(
+            classify_error(
+                403,
+                "Forbidden.RAM",
+                "User not authorized to operate on the specified resource.",
+                None
+            ),
+            ProviderError::PermissionDenied {
+                action: None,
+                message: "Alibaba Cloud API error: Forbidden.RAM - User not authorized to operate on the specified resource.".to_string(),
+            }
+        );
+        assert_eq!(
+            classify_error(
+                400,
+                "Throttling.User",
+                "Request was denied due to user flow control.",
+                None
+            ),
+            ProviderError::Throttled(None)
+        );
+        assert!(matches!(
+            classify_error(500, "", "<html>Bad Gateway</html>", None),
+            ProviderError::Unexpected(_)
+        ));
+    }
+
+    #[test]
+    fn test_acs3_signature() {
+        // Example from the Alibaba Cloud signature V3 documentation
+        let credentials = AliyunCredentials::access_key("YourAccessKeyId", "YourAccessKeySecret");
+        let mut query = BTreeMap::new();
+        query.insert(
+            "ImageId".to_string(),
+            "win2019_1809_x64_dtc_zh-cn_40G_alibase_20230811.vhd".to_string(),
+        );
+        query.insert("RegionId".to_string(), "cn-shanghai".to_string());
+        let request = Acs3Request {
+            method: "POST",
+            host: "ecs.cn-shanghai.aliyuncs.com",
+            action: "RunInstances",
+            version: "2014-05-26",
+            query: &query,
+            body: "",
+        };
+
+        let headers = request.sign(
+            &credentials,
+            "2023-10-26T10:22:32Z",
+            "3156853299f313e23d1673dc12e1703d",
+        );
+        let header = |name: &str| {
+            headers
+                .iter()
+                .find(|(k, _)| k == name)
+                .map(|(_, v)| v.as_str())
+        };
+        assert_eq!(
+            header("Authorization"),
+            Some(
+                "ACS3-HMAC-SHA256 Credential=YourAccessKeyId,\
+                 SignedHeaders=host;x-acs-action;x-acs-content-sha256;x-acs-date;x-acs-signature-nonce;x-acs-version,\
+                 Signature=06563a9e1b43f5dfe96b81484da74bceab24a1d853912eee15083a6f0f3283c0"
+            )
+        );
+        assert_eq!(
+            header("x-acs-content-sha256"),
+            Some("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855")
+        );
+        assert_eq!(header("x-acs-security-token"), None);
+
+        // STS tokens are sent and signed
+        let assumed = AliyunCredentials {
+            security_token: Some("CAIS-token".to_string()),
+            ..credentials
+        };
+        let headers = request.sign(&assumed, "2023-10-26T10:22:32Z", "nonce");
+        assert!(headers
+            .iter()
+            .any(|(k, v)| k == "x-acs-security-token" && v == "CAIS-token"));
+        assert!(headers.iter().any(|(k, v)| k == "Authorization"
+            && v.contains("x-acs-date;x-acs-security-token;x-acs-signature-nonce")));
+    }
+
+    #[test]
+    fn test_parse_assume_role_response() {
+        let json = r#"{
+            "RequestId": "6894B13B-6D71-4EF5-88FA-F32781734A7F",
+            "AssumedRoleUser": {"Arn": "acs:ram::123456789012****:role/billing/alice", "AssumedRoleId": "34458433936495****:alice"},
+            "Credentials": {
+                "SecurityToken": "CAIS-token",
+                "AccessKeyId": "STS.L4aBSCSJVMuKg5U1****",
+                "AccessKeySecret": "wyLTSmsyPGP1ohvvw8xYgB29dlGI8KMiH2pK****",
+                "Expiration": "2015-04-09T11:52:19Z"
+            }
+        }"#;
+        let credentials = parse_assume_role_response(json).unwrap();
+        assert_eq!(credentials.access_key_id, "STS.L4aBSCSJVMuKg5U1****");
+        assert_eq!(credentials.security_token.as_deref(), Some("CAIS-token"));
+
+        let expiration = credentials.expiration.unwrap();
+        assert!(credentials.is_fresh(expiration - chrono::Duration::minutes(10)));
+        assert!(!credentials.is_fresh(expiration - chrono::Duration::minutes(1)));
+
+        assert!(parse_assume_role_response(r#"{"RequestId": "x"}"#).is_err());
+    }
+
+    #[test]
+    fn test_member_cost_summaries() {
+        let bill = |json: &str| -> Vec<AccountBillItem> {
+            let response: AccountBillResponse = serde_json::from_str(json).unwrap();
+            response.data.unwrap().items.unwrap().item.unwrap()
+        };
+        let current = bill(
+            r#"{"Code": "Success", "Data": {"TotalCount": 3, "Items": {"Item": [
+                {"OwnerID": "1001", "OwnerName": "shop", "ProductName": "ECS", "PretaxAmount": 80.0, "Currency": "CNY"},
+                {"OwnerID": "1001", "OwnerName": "shop", "ProductName": "OSS", "PretaxAmount": 20.0, "Currency": "CNY"},
+                {"OwnerID": "1002", "OwnerName": "data", "ProductName": "MaxCompute", "PretaxAmount": 300.0, "Currency": "CNY"}
+            ]}}}"#,
+        );
+        let last = bill(
+            r#"{"Data": {"TotalCount": 1, "Items": {"Item": [
+                {"OwnerID": "1001", "OwnerName": "shop", "ProductName": "ECS", "PretaxAmount": 50.0, "Currency": "CNY"}
+            ]}}}"#,
+        );
+
+        // Discovered members, largest first
+        let discovered = AliyunMemberBilling {
+            account: "Finance".to_string(),
+            members: Vec::new(),
+        };
+        let summaries =
+            member_cost_summaries("acc", "Finance", &discovered, &current, &last, "CNY");
+        assert_eq!(summaries.len(), 2);
+        assert_eq!(summaries[0].account_id, "acc#1002");
+        assert_eq!(summaries[0].account_name, "Finance / data");
+        assert_eq!(summaries[0].current_month_cost, 300.0);
+        assert_eq!(summaries[1].current_month_cost, 100.0);
+        assert_eq!(summaries[1].last_month_cost, 50.0);
+        assert_eq!(summaries[1].month_over_month_change, 100.0);
+        assert_eq!(summaries[1].current_month_details[0].service, "ECS");
+
+        // Configured members keep their order and names, also without costs
+        let configured = AliyunMemberBilling {
+            account: "Finance".to_string(),
+            members: vec![
+                crate::config::AliyunMember {
+                    id: "1001".to_string(),
+                    name: Some("Shop".to_string()),
+                },
+                crate::config::AliyunMember {
+                    id: "1003".to_string(),
+                    name: None,
+                },
+            ],
+        };
+        let summaries =
+            member_cost_summaries("acc", "Finance", &configured, &current, &last, "CNY");
+        assert_eq!(summaries.len(), 2);
+        assert_eq!(summaries[0].account_name, "Finance / Shop");
+        assert_eq!(summaries[1].account_id, "acc#1003");
+        assert_eq!(summaries[1].account_name, "Finance / 1003");
+        assert_eq!(summaries[1].current_month_cost, 0.0);
+    }
+
+    #[test]
+    fn test_site_from_region() {
+        assert_eq!(AliyunSite::from_region(None), AliyunSite::China);
+        assert_eq!(
+            AliyunSite::from_region(Some("cn-hangzhou")),
+            AliyunSite::China
+        );
+        assert_eq!(
+            AliyunSite::from_region(Some("ap-southeast-1")),
+            AliyunSite::International
+        );
+        assert_eq!(
+            AliyunSite::from_region(Some(" INTL ")),
+            AliyunSite::International
+        );
+        assert_eq!(
+            AliyunSite::International.bss_host(),
+            "business.ap-southeast-1.aliyuncs.com"
+        );
+        assert_eq!(AliyunSite::International.currency(), "USD");
+    }
+
+    #[test]
+    fn test_parse_bill_overview_currency() {
+        let response: BillOverviewResponse = serde_json::from_str(
+            r#"{"Code": "Success", "Data": {"Items": {"Item": [
+                {"ProductName": "ECS", "PretaxAmount": 12.5, "Currency": "USD"},
+                {"ProductName": "OSS", "PretaxAmount": 0.0, "Currency": "USD"}
+            ]}}}"#,
+        )
+        .unwrap();
+        let (total, details, currency) = parse_bill_overview(&response, "CNY");
+        assert_eq!(total, 12.5);
+        assert_eq!(details.len(), 1);
+        assert_eq!(details[0].currency, "USD");
+        assert_eq!(currency, "USD");
+
+        let empty: BillOverviewResponse = serde_json::from_str(r#"{"Data": {}}"#).unwrap();
+        assert_eq!(parse_bill_overview(&empty, "USD").2, "USD");
+    }
+
+    #[test]
+    fn test_parse_tag_string() {
+        let tags = parse_tag_string("key:team value:platform; key:env value:prod;key:empty");
+        assert_eq!(
+            tags,
+            vec![
+                ("team".to_string(), "platform".to_string()),
+                ("env".to_string(), "prod".to_string()),
+                ("empty".to_string(), String::new()),
+            ]
+        );
+        assert!(parse_tag_string("").is_empty());
+    }
+
+    #[test]
+    fn test_billing_cycles() {
+        let cycles = billing_cycles("2023-11-15", "2024-02-01").unwrap();
+        assert_eq!(cycles, vec!["2023-11", "2023-12", "2024-01"]);
+    }
+
+    #[test]
+    fn test_instance_bill_item_dimensions() {
+        let item: InstanceBillItem = serde_json::from_str(
+            r#"{
+                "ProductName": "ECS",
+                "PretaxAmount": 12.5,
+                "Region": "cn-hangzhou",
+                "InstanceSpec": "ecs.g6.large",
+                "ResourceGroup": "rg-default",
+                "Tag": "key:team value:platform"
+            }"#,
+        )
+        .unwrap();
+
+        let cost = instance_bill_item_to_cost("acc", "2024-05-01", "CNY", item).unwrap();
+        assert_eq!(cost.service, "ECS");
+        assert_eq!(cost.dimensions["REGION"], "cn-hangzhou");
+        assert_eq!(cost.dimensions["INSTANCE_TYPE"], "ecs.g6.large");
+        assert_eq!(cost.dimensions["RESOURCE_GROUP"], "rg-default");
+        assert_eq!(cost.dimensions["TAG:team"], "platform");
+        assert!(!cost.dimensions.contains_key("USAGE_TYPE"));
+    }
+
+    #[test]
+    fn test_instance_bill_item_to_focus() {
+        let item: InstanceBillItem = serde_json::from_str(
+            r#"{
+                "ProductCode": "oss",
+                "ProductName": "Object Storage Service",
+                "InstanceId": "my-bucket",
+                "PretaxAmount": 8.0,
+                "PretaxGrossAmount": 10.0,
+                "Currency": "CNY",
+                "Region": "cn-shanghai",
+                "Tag": "key:env value:prod; key:owner"
+            }"#,
+        )
+        .unwrap();
+
+        let focus =
+            instance_bill_item_to_focus("acc", "Main", "2024-12-01", "2025-01-01", "CNY", item)
+                .unwrap();
+        assert_eq!(focus.billed_cost, 8.0);
+        assert_eq!(focus.list_cost, 10.0);
+        assert_eq!(focus.service_category, "Storage");
+        assert_eq!(focus.provider_name, "Alibaba Cloud");
+        assert_eq!(focus.resource_id.as_deref(), Some("my-bucket"));
+        assert_eq!(focus.region_id.as_deref(), Some("cn-shanghai"));
+        assert_eq!(focus.tags.len(), 1);
+        assert_eq!(focus.tags["env"], "prod");
+    }
 }
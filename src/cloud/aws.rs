@@ -5,11 +5,93 @@ use chrono::{DateTime, Datelike, Utc};
 use hmac::{Hmac, Mac};
 use serde::Deserialize;
 use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
 
-use super::{CloudProvider, CloudService, CostData, CostSummary};
+use super::http::{self, Endpoint, HttpRequest};
+use super::{
+    focus, AnomalyFeedback, AnomalyRootCause, BudgetInfo, BudgetSource, CloudProvider,
+    CloudService, CommitmentSummary, CommitmentUtilization, CostAnomaly, CostData, CostDimension,
+    CostSummary, FocusCost, ProviderError, UNTAGGED_VALUE,
+};
+use crate::redact;
 
 type HmacSha256 = Hmac<Sha256>;
 
+/// (monitor ARN, monitor name) pairs plus the next page token
+type AnomalyMonitorPage = (Vec<(String, String)>, Option<String>);
+
+/// Maximum number of pages fetched per paginated Cost Explorer request (each page is
+/// a billed request)
+const MAX_COST_EXPLORER_PAGES: usize = 20;
+
+/// Maximum number of DescribeBudgets pages (100 budgets each) fetched per sync
+const MAX_BUDGET_PAGES: usize = 10;
+
+/// AWS partition, detected from the account's region
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AwsPartition {
+    /// Commercial regions (`aws`)
+    Standard,
+    /// China regions operated by Sinnet and NWCD (`aws-cn`)
+    China,
+    /// AWS GovCloud (US) regions (`aws-us-gov`)
+    GovCloud,
+}
+
+impl AwsPartition {
+    pub fn from_region(region: &str) -> Self {
+        if region.starts_with("cn-") {
+            AwsPartition::China
+        } else if region.starts_with("us-gov-") {
+            AwsPartition::GovCloud
+        } else {
+            AwsPartition::Standard
+        }
+    }
+
+    pub fn dns_suffix(&self) -> &'static str {
+        match self {
+            AwsPartition::China => "amazonaws.com.cn",
+            AwsPartition::Standard | AwsPartition::GovCloud => "amazonaws.com",
+        }
+    }
+
+    /// Region that Cost Explorer and Budgets are served from and signed for
+    pub fn billing_region(&self) -> &'static str {
+        match self {
+            AwsPartition::Standard => "us-east-1",
+            AwsPartition::China => "cn-northwest-1",
+            AwsPartition::GovCloud => "us-gov-west-1",
+        }
+    }
+
+    pub fn ce_host(&self) -> String {
+        format!("ce.{}.{}", self.billing_region(), self.dns_suffix())
+    }
+
+    /// AWS Budgets is a global endpoint in the commercial and China partitions and a
+    /// regional one in GovCloud
+    pub fn budgets_host(&self) -> String {
+        match self {
+            AwsPartition::Standard => "budgets.amazonaws.com".to_string(),
+            AwsPartition::China => "budgets.amazonaws.com.cn".to_string(),
+            AwsPartition::GovCloud => "budgets.us-gov-west-1.amazonaws.com".to_string(),
+        }
+    }
+
+    pub fn sts_host(&self, region: &str) -> String {
+        format!("sts.{}.{}", region, self.dns_suffix())
+    }
+
+    /// Billing currency, for results that do not carry a unit
+    pub fn currency(&self) -> &'static str {
+        match self {
+            AwsPartition::China => "CNY",
+            AwsPartition::Standard | AwsPartition::GovCloud => "USD",
+        }
+    }
+}
+
 /// AWS Cloud Service
 pub struct AwsCloudService {
     account_id: String,
@@ -17,6 +99,9 @@ pub struct AwsCloudService {
     access_key_id: String,
     secret_access_key: String,
     region: String,
+    partition: AwsPartition,
+    /// Endpoint overrides by service (`sts`, `ce`, `budgets`)
+    endpoints: BTreeMap<String, String>,
 }
 
 impl AwsCloudService {
@@ -27,12 +112,19 @@ impl AwsCloudService {
         secret_access_key: String,
         region: Option<String>,
     ) -> Self {
+        let endpoints = http::endpoint_overrides(&account_id, &account_name, CloudProvider::AWS);
+        let region = region
+            .map(|r| r.trim().to_string())
+            .filter(|r| !r.is_empty())
+            .unwrap_or_else(|| "us-east-1".to_string());
         Self {
             account_id,
             account_name,
             access_key_id,
             secret_access_key,
-            region: region.unwrap_or_else(|| "us-east-1".to_string()),
+            partition: AwsPartition::from_region(&region),
+            region,
+            endpoints,
         }
     }
 
@@ -76,7 +168,7 @@ impl AwsCloudService {
         all_headers.push(("x-amz-content-sha256".to_string(), payload_hash.clone()));
 
         // Sort by lowercase key
-        all_headers.sort_by(|a, b| a.0.to_lowercase().cmp(&b.0.to_lowercase()));
+        all_headers.sort_by_key(|a| a.0.to_lowercase());
 
         let canonical_headers: String = all_headers
             .iter()
@@ -124,49 +216,139 @@ impl AwsCloudService {
 
     /// Call STS GetCallerIdentity API
     fn call_sts_get_caller_identity(&self) -> Result<StsCallerIdentity> {
-        let timestamp = Utc::now();
         let service = "sts";
-        let host = format!("sts.{}.amazonaws.com", self.region);
+        let endpoint = Endpoint::resolve(
+            &self.endpoints,
+            "sts",
+            &self.partition.sts_host(&self.region),
+        )?;
+        let host = endpoint.host.clone();
         let uri = "/";
         let query_string = "Action=GetCallerIdentity&Version=2011-06-15";
-
-        let amz_date = timestamp.format("%Y%m%dT%H%M%SZ").to_string();
+        let url = endpoint.url(&format!("{}?{}", uri, query_string));
         let payload_hash = Self::sha256_hash(b"");
 
-        let authorization =
-            self.sign_request("GET", service, &host, uri, query_string, &[], "", timestamp)?;
+        // Signed again for every attempt
+        let response = http::send(CloudProvider::AWS, &self.account_id, "STS", || {
+            let timestamp = Utc::now();
+            let amz_date = timestamp.format("%Y%m%dT%H%M%SZ").to_string();
+            let authorization =
+                self.sign_request("GET", service, &host, uri, query_string, &[], "", timestamp)?;
+
+            Ok(HttpRequest::get(&url)
+                .header("Authorization", &authorization)
+                .header("X-Amz-Date", &amz_date)
+                .header("X-Amz-Content-Sha256", &payload_hash)
+                .header("Host", &host))
+        })?;
+
+        // 4xx bodies carry the error code (e.g. InvalidClientTokenId)
+        if response.status >= 400 {
+            return Err(classify_error(
+                "STS",
+                response.status,
+                &response.body,
+                None,
+                response.retry_after,
+            )
+            .into());
+        }
 
-        let url = format!("https://{}{}?{}", host, uri, query_string);
+        // Parse XML response
+        parse_sts_response(&response.body)
+    }
+
+    /// Send a signed JSON request to an AWS JSON-protocol API and return the response body
+    fn call_json_api(
+        &self,
+        api_name: &str,
+        service: &str,
+        region: &str,
+        endpoint: &Endpoint,
+        target: &str,
+        request_body: &serde_json::Value,
+    ) -> Result<String> {
+        let uri = "/";
+        let host = endpoint.host.as_str();
+        let url = endpoint.url(uri);
 
-        let response = ureq::get(&url)
-            .header("Authorization", &authorization)
-            .header("X-Amz-Date", &amz_date)
-            .header("X-Amz-Content-Sha256", &payload_hash)
-            .header("Host", &host)
-            .call()
-            .map_err(|e| anyhow!("STS request failed: {}", e))?;
+        let payload = serde_json::to_string(request_body)?;
+        let payload_hash = Self::sha256_hash(payload.as_bytes());
 
-        let body = response
-            .into_body()
-            .read_to_string()
-            .map_err(|e| anyhow!("Failed to read response: {}", e))?;
+        // Add required headers
+        let headers = vec![
+            (
+                "content-type".to_string(),
+                "application/x-amz-json-1.1".to_string(),
+            ),
+            ("x-amz-target".to_string(), target.to_string()),
+        ];
 
-        // Parse XML response
-        parse_sts_response(&body)
+        tracing::debug!("Sending {} request: {} ({})", api_name, url, target);
+
+        // Signed again for every attempt
+        // Reads can be sent again after a server error; writes such as CreateBudget cannot
+        let action = target.rsplit('.').next().unwrap_or(target);
+        let idempotent = ["Get", "Describe", "List"]
+            .iter()
+            .any(|prefix| action.starts_with(prefix));
+
+        let response = http::send(CloudProvider::AWS, &self.account_id, api_name, || {
+            let timestamp = Utc::now();
+            let amz_date = timestamp.format("%Y%m%dT%H%M%SZ").to_string();
+            let authorization = self.sign_request_with_region(
+                "POST", service, region, host, uri, "", &headers, &payload, timestamp,
+            )?;
+
+            Ok(HttpRequest::post(&url, payload.as_str())
+                .header("Authorization", &authorization)
+                .header("X-Amz-Date", &amz_date)
+                .header("X-Amz-Content-Sha256", &payload_hash)
+                .header("Host", host)
+                .header("Content-Type", "application/x-amz-json-1.1")
+                .header("X-Amz-Target", target)
+                .idempotent(idempotent))
+        })?;
+
+        if response.status >= 400 {
+            tracing::error!(
+                "{} error response (HTTP {}): {}",
+                api_name,
+                response.status,
+                redact::excerpt(&response.body)
+            );
+            let required = required_iam_action(service, action);
+            return Err(classify_error(
+                api_name,
+                response.status,
+                &response.body,
+                Some(&required),
+                response.retry_after,
+            )
+            .into());
+        }
+
+        Ok(response.body)
+    }
+
+    /// Call a Cost Explorer API action
+    /// Note: Cost Explorer has a single endpoint per partition (us-east-1, cn-northwest-1, us-gov-west-1)
+    fn call_ce_api(&self, action: &str, request_body: &serde_json::Value) -> Result<String> {
+        let endpoint = Endpoint::resolve(&self.endpoints, "ce", &self.partition.ce_host())?;
+        let target = format!("AWSInsightsIndexService.{}", action);
+
+        self.call_json_api(
+            "Cost Explorer",
+            "ce",
+            self.partition.billing_region(),
+            &endpoint,
+            &target,
+            request_body,
+        )
     }
 
     /// Call Cost Explorer API
-    /// Note: Cost Explorer API is only available in us-east-1 region
     fn call_cost_explorer(&self, start_date: &str, end_date: &str) -> Result<Vec<CostData>> {
-        let timestamp = Utc::now();
-        let service = "ce";
-        // Cost Explorer API is only available in us-east-1
-        let ce_region = "us-east-1";
-        let host = format!("ce.{}.amazonaws.com", ce_region);
-        let uri = "/";
-
-        let amz_date = timestamp.format("%Y%m%dT%H%M%SZ").to_string();
-
         // Build request body
         let request_body = serde_json::json!({
             "TimePeriod": {
@@ -180,160 +362,419 @@ impl AwsCloudService {
                 "Key": "SERVICE"
             }]
         });
-        let payload = serde_json::to_string(&request_body)?;
-        let payload_hash = Self::sha256_hash(payload.as_bytes());
 
-        // Add required headers
-        let headers = vec![
-            (
-                "content-type".to_string(),
-                "application/x-amz-json-1.1".to_string(),
-            ),
-            (
-                "x-amz-target".to_string(),
-                "AWSInsightsIndexService.GetCostAndUsage".to_string(),
-            ),
-        ];
+        let body = self.call_ce_api("GetCostAndUsage", &request_body)?;
+        parse_cost_explorer_response(&body, &self.account_id, &self.account_name)
+    }
 
-        // Sign with us-east-1 region
-        let authorization = self.sign_request_with_region(
-            "POST", service, ce_region, &host, uri, "", &headers, &payload, timestamp,
-        )?;
+    /// Call Cost Explorer API to get daily costs (not grouped by service, for trend charts)
+    fn call_cost_explorer_daily(&self, start_date: &str, end_date: &str) -> Result<Vec<CostData>> {
+        // Build request body - not grouped by service, get daily total cost directly
+        let request_body = serde_json::json!({
+            "TimePeriod": {
+                "Start": start_date,
+                "End": end_date
+            },
+            "Granularity": "DAILY",
+            "Metrics": ["UnblendedCost"]
+        });
 
-        let url = format!("https://{}{}", host, uri);
-
-        // Use Agent and disable status code as error, so we can read 4xx/5xx response body
-        let agent = ureq::Agent::config_builder()
-            .http_status_as_error(false)
-            .timeout_global(Some(std::time::Duration::from_secs(30)))
-            .build()
-            .new_agent();
-
-        tracing::debug!("Sending Cost Explorer request: {}", url);
-
-        let result = agent
-            .post(&url)
-            .header("Authorization", &authorization)
-            .header("X-Amz-Date", &amz_date)
-            .header("X-Amz-Content-Sha256", &payload_hash)
-            .header("Host", &host)
-            .header("Content-Type", "application/x-amz-json-1.1")
-            .header("X-Amz-Target", "AWSInsightsIndexService.GetCostAndUsage")
-            .send(&payload);
-
-        match result {
-            Ok(response) => {
-                let status = response.status().as_u16();
-                let body = response
-                    .into_body()
-                    .read_to_string()
-                    .map_err(|e| anyhow!("Failed to read response: {}", e))?;
-
-                if status >= 400 {
-                    tracing::error!("Cost Explorer error response (HTTP {}): {}", status, body);
-                    return Err(anyhow!(
-                        "Cost Explorer request failed: HTTP {} - {}",
-                        status,
-                        body
-                    ));
-                }
+        let body = self.call_ce_api("GetCostAndUsage", &request_body)?;
+        parse_daily_cost_response(&body, &self.account_id)
+    }
 
-                parse_cost_explorer_response(&body, &self.account_id, &self.account_name)
+    /// Call Cost Explorer API grouped by service and a cost allocation dimension
+    fn call_cost_explorer_grouped(
+        &self,
+        start_date: &str,
+        end_date: &str,
+        dimension: &CostDimension,
+    ) -> Result<Vec<CostData>> {
+        let group_by = match dimension {
+            CostDimension::Service => serde_json::json!({"Type": "DIMENSION", "Key": "SERVICE"}),
+            CostDimension::Region => serde_json::json!({"Type": "DIMENSION", "Key": "REGION"}),
+            CostDimension::UsageType => {
+                serde_json::json!({"Type": "DIMENSION", "Key": "USAGE_TYPE"})
             }
-            Err(e) => {
-                // Network or other errors
-                let error_msg = format!("{:?}", e);
-                tracing::error!("Cost Explorer request error details: {}", error_msg);
-                Err(anyhow!("Cost Explorer request failed: {}", e))
+            CostDimension::InstanceType => {
+                serde_json::json!({"Type": "DIMENSION", "Key": "INSTANCE_TYPE"})
+            }
+            CostDimension::Tag(key) => serde_json::json!({"Type": "TAG", "Key": key}),
+            CostDimension::ResourceGroup => {
+                return Err(anyhow!("AWS does not support grouping by resource group"))
             }
+        };
+
+        let mut group_bys = vec![serde_json::json!({"Type": "DIMENSION", "Key": "SERVICE"})];
+        if *dimension != CostDimension::Service {
+            group_bys.push(group_by);
         }
-    }
 
-    /// Call Cost Explorer API to get daily costs (not grouped by service, for trend charts)
-    fn call_cost_explorer_daily(&self, start_date: &str, end_date: &str) -> Result<Vec<CostData>> {
-        let timestamp = Utc::now();
-        let service = "ce";
-        let ce_region = "us-east-1";
-        let host = format!("ce.{}.amazonaws.com", ce_region);
-        let uri = "/";
+        let mut cost_data = Vec::new();
+        let mut next_page_token: Option<String> = None;
+
+        for _ in 0..MAX_COST_EXPLORER_PAGES {
+            let mut request_body = serde_json::json!({
+                "TimePeriod": {
+                    "Start": start_date,
+                    "End": end_date
+                },
+                "Granularity": "MONTHLY",
+                "Metrics": ["UnblendedCost"],
+                "GroupBy": group_bys
+            });
+            if let Some(token) = &next_page_token {
+                request_body["NextPageToken"] = serde_json::Value::String(token.clone());
+            }
 
-        let amz_date = timestamp.format("%Y%m%dT%H%M%SZ").to_string();
+            let body = self.call_ce_api("GetCostAndUsage", &request_body)?;
+            let (mut page, token) =
+                parse_grouped_cost_response(&body, &self.account_id, dimension)?;
+            cost_data.append(&mut page);
 
-        // Build request body - not grouped by service, get daily total cost directly
-        let request_body = serde_json::json!({
+            match token {
+                Some(token) if !token.is_empty() => next_page_token = Some(token),
+                _ => return Ok(cost_data),
+            }
+        }
+
+        tracing::warn!(
+            "Cost breakdown by {} for {} truncated after {} pages",
+            dimension.label(),
+            self.account_id,
+            MAX_COST_EXPLORER_PAGES
+        );
+        Ok(cost_data)
+    }
+
+    /// Call a Cost Explorer action that fails with DataUnavailableException when the
+    /// account has nothing to report (e.g. no Savings Plans), returning None in that case
+    fn call_ce_api_optional(
+        &self,
+        action: &str,
+        request_body: &serde_json::Value,
+    ) -> Result<Option<serde_json::Value>> {
+        match self.call_ce_api(action, request_body) {
+            Ok(body) => Ok(Some(serde_json::from_str(&body)?)),
+            Err(e)
+                if matches!(
+                    ProviderError::find(&e),
+                    Some(ProviderError::DataNotReady(_))
+                ) =>
+            {
+                tracing::debug!("{} has no data for account {}", action, self.account_id);
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Get Savings Plans and Reserved Instances utilization and coverage
+    pub fn get_commitment_summary(
+        &self,
+        start_date: &str,
+        end_date: &str,
+    ) -> Result<CommitmentSummary> {
+        let time_period = serde_json::json!({
+            "TimePeriod": {
+                "Start": start_date,
+                "End": end_date
+            }
+        });
+        let monthly = serde_json::json!({
             "TimePeriod": {
                 "Start": start_date,
                 "End": end_date
             },
-            "Granularity": "DAILY",
-            "Metrics": ["UnblendedCost"]
+            "Granularity": "MONTHLY"
         });
-        let payload = serde_json::to_string(&request_body)?;
-        let payload_hash = Self::sha256_hash(payload.as_bytes());
 
-        let headers = vec![
-            (
-                "content-type".to_string(),
-                "application/x-amz-json-1.1".to_string(),
-            ),
-            (
-                "x-amz-target".to_string(),
-                "AWSInsightsIndexService.GetCostAndUsage".to_string(),
-            ),
-        ];
+        let sp_utilization =
+            self.call_ce_api_optional("GetSavingsPlansUtilization", &time_period)?;
+        let sp_coverage = self.call_ce_api_optional("GetSavingsPlansCoverage", &monthly)?;
+        let ri_utilization =
+            self.call_ce_api_optional("GetReservationUtilization", &time_period)?;
+        let ri_coverage = self.call_ce_api_optional("GetReservationCoverage", &time_period)?;
 
-        let authorization = self.sign_request_with_region(
-            "POST", service, ce_region, &host, uri, "", &headers, &payload, timestamp,
-        )?;
+        Ok(CommitmentSummary {
+            account_id: self.account_id.clone(),
+            start_date: start_date.to_string(),
+            end_date: end_date.to_string(),
+            currency: self.partition.currency().to_string(),
+            savings_plans: parse_savings_plans(sp_utilization.as_ref(), sp_coverage.as_ref()),
+            reservations: parse_reservations(ri_utilization.as_ref(), ri_coverage.as_ref()),
+        })
+    }
 
-        let url = format!("https://{}{}", host, uri);
-
-        let agent = ureq::Agent::config_builder()
-            .http_status_as_error(false)
-            .timeout_global(Some(std::time::Duration::from_secs(30)))
-            .build()
-            .new_agent();
-
-        tracing::debug!("Sending Cost Explorer daily cost request: {}", url);
-
-        let result = agent
-            .post(&url)
-            .header("Authorization", &authorization)
-            .header("X-Amz-Date", &amz_date)
-            .header("X-Amz-Content-Sha256", &payload_hash)
-            .header("Host", &host)
-            .header("Content-Type", "application/x-amz-json-1.1")
-            .header("X-Amz-Target", "AWSInsightsIndexService.GetCostAndUsage")
-            .send(&payload);
-
-        match result {
-            Ok(response) => {
-                let status = response.status().as_u16();
-                let body = response
-                    .into_body()
-                    .read_to_string()
-                    .map_err(|e| anyhow!("Failed to read response: {}", e))?;
-
-                if status >= 400 {
-                    tracing::error!(
-                        "Cost Explorer daily cost request error (HTTP {}): {}",
-                        status,
-                        body
-                    );
-                    return Err(anyhow!(
-                        "Cost Explorer request failed: HTTP {} - {}",
-                        status,
-                        body
-                    ));
-                }
+    /// Get cost anomalies detected by the account's Cost Anomaly Detection monitors
+    pub fn get_anomalies(&self, start_date: &str, end_date: &str) -> Result<Vec<CostAnomaly>> {
+        // Monitor names, so anomalies can show which monitor fired
+        let mut monitors = HashMap::new();
+        let mut next_token: Option<String> = None;
+        for _ in 0..MAX_COST_EXPLORER_PAGES {
+            let mut request_body = serde_json::json!({ "MaxResults": 100 });
+            if let Some(token) = &next_token {
+                request_body["NextPageToken"] = serde_json::Value::String(token.clone());
+            }
 
-                parse_daily_cost_response(&body, &self.account_id)
+            let body = self.call_ce_api("GetAnomalyMonitors", &request_body)?;
+            let (page, token) = parse_anomaly_monitors_response(&body)?;
+            monitors.extend(page);
+
+            next_token = token.filter(|token| !token.is_empty());
+            if next_token.is_none() {
+                break;
             }
-            Err(e) => {
-                tracing::error!("Cost Explorer daily cost request error: {:?}", e);
-                Err(anyhow!("Cost Explorer request failed: {}", e))
+        }
+        if next_token.is_some() {
+            tracing::warn!(
+                "Anomaly monitors for {} truncated after {} pages",
+                self.account_id,
+                MAX_COST_EXPLORER_PAGES
+            );
+        }
+
+        let mut anomalies = Vec::new();
+        let mut next_token: Option<String> = None;
+        for _ in 0..MAX_COST_EXPLORER_PAGES {
+            let mut request_body = serde_json::json!({
+                "DateInterval": {
+                    "StartDate": start_date,
+                    "EndDate": end_date
+                },
+                "MaxResults": 100
+            });
+            if let Some(token) = &next_token {
+                request_body["NextPageToken"] = serde_json::Value::String(token.clone());
+            }
+
+            let body = self.call_ce_api("GetAnomalies", &request_body)?;
+            let (page, token) =
+                parse_anomalies_response(&body, &monitors, self.partition.currency())?;
+            anomalies.extend(page);
+
+            next_token = token.filter(|token| !token.is_empty());
+            if next_token.is_none() {
+                break;
             }
         }
+        if next_token.is_some() {
+            tracing::warn!(
+                "Anomalies for {} truncated after {} pages",
+                self.account_id,
+                MAX_COST_EXPLORER_PAGES
+            );
+        }
+
+        // Largest impact first
+        anomalies.sort_by(|a, b| {
+            b.total_impact
+                .partial_cmp(&a.total_impact)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        Ok(anomalies)
+    }
+
+    /// Acknowledge an anomaly (ProvideAnomalyFeedback)
+    pub fn provide_anomaly_feedback(
+        &self,
+        anomaly_id: &str,
+        feedback: AnomalyFeedback,
+    ) -> Result<()> {
+        let request_body = serde_json::json!({
+            "AnomalyId": anomaly_id,
+            "Feedback": feedback.key()
+        });
+
+        self.call_ce_api("ProvideAnomalyFeedback", &request_body)?;
+        tracing::info!(
+            "Sent anomaly feedback {} for {} ({})",
+            feedback.key(),
+            anomaly_id,
+            self.account_name
+        );
+        Ok(())
+    }
+
+    /// Call an AWS Budgets API action
+    /// Note: AWS Budgets is a global service signed in the partition's billing region
+    fn call_budgets_api(&self, action: &str, request_body: &serde_json::Value) -> Result<String> {
+        let target = format!("AWSBudgetServiceGateway.{}", action);
+        let endpoint =
+            Endpoint::resolve(&self.endpoints, "budgets", &self.partition.budgets_host())?;
+
+        self.call_json_api(
+            "AWS Budgets",
+            "budgets",
+            self.partition.billing_region(),
+            &endpoint,
+            &target,
+            request_body,
+        )
+    }
+
+    /// List the cost budgets defined in AWS Budgets, including their alert thresholds
+    pub fn describe_budgets(&self) -> Result<Vec<AwsBudget>> {
+        let aws_account_id = self.call_sts_get_caller_identity()?.account;
+        let mut budgets = Vec::new();
+        let mut next_token: Option<String> = None;
+
+        for _ in 0..MAX_BUDGET_PAGES {
+            let mut request_body = serde_json::json!({
+                "AccountId": aws_account_id,
+                "MaxResults": 100
+            });
+            if let Some(token) = &next_token {
+                request_body["NextToken"] = serde_json::Value::String(token.clone());
+            }
+
+            let body = self.call_budgets_api("DescribeBudgets", &request_body)?;
+            let (page, token) = parse_describe_budgets_response(&body, self.partition.currency())?;
+            budgets.extend(page);
+
+            next_token = token.filter(|token| !token.is_empty());
+            if next_token.is_none() {
+                break;
+            }
+        }
+        if next_token.is_some() {
+            tracing::warn!(
+                "AWS Budgets list for {} truncated after {} pages",
+                self.account_id,
+                MAX_BUDGET_PAGES
+            );
+        }
+
+        for budget in &mut budgets {
+            let request_body = serde_json::json!({
+                "AccountId": aws_account_id,
+                "BudgetName": budget.name
+            });
+            let body = self.call_budgets_api("DescribeNotificationsForBudget", &request_body)?;
+            budget.alert_threshold = parse_budget_alert_threshold(&body)?;
+        }
+
+        Ok(budgets)
+    }
+
+    /// Sync the account's budget with AWS Budgets.
+    ///
+    /// A monthly cost budget in AWS is imported and becomes the source of truth. When
+    /// AWS has none and `push_local` is set, a CloudBridge budget is created (or updated)
+    /// in AWS instead, and CloudBridge stays the source of truth.
+    pub fn sync_budget(
+        &self,
+        local: Option<&BudgetInfo>,
+        push_local: bool,
+        alert_email: Option<&str>,
+    ) -> Result<Option<BudgetInfo>> {
+        let remote = self.describe_budgets()?;
+
+        let pushed_name = local
+            .filter(|b| b.source == BudgetSource::Local)
+            .and_then(|b| b.external_name.as_deref());
+
+        if let Some(local) = local.filter(|b| b.source == BudgetSource::Local && push_local) {
+            let exists = pushed_name.is_some_and(|name| remote.iter().any(|b| b.name == name));
+            let name = self.push_budget(local, exists, alert_email)?;
+            return Ok(Some(BudgetInfo {
+                external_name: Some(name),
+                updated_at: Utc::now(),
+                ..local.clone()
+            }));
+        }
+
+        // Keep following the previously imported budget, otherwise take the first
+        // monthly cost budget that CloudBridge did not push itself
+        let linked_name = local
+            .filter(|b| b.source == BudgetSource::AwsBudgets)
+            .and_then(|b| b.external_name.as_deref());
+        let imported = linked_name
+            .and_then(|name| remote.iter().find(|b| b.name == name))
+            .or_else(|| {
+                remote
+                    .iter()
+                    .find(|b| b.is_monthly_cost() && Some(b.name.as_str()) != pushed_name)
+            });
+
+        match imported {
+            Some(budget) => {
+                let now = Utc::now();
+                Ok(Some(BudgetInfo {
+                    account_id: self.account_id.clone(),
+                    monthly_budget: budget.limit_amount,
+                    currency: budget.currency.clone(),
+                    alert_threshold: budget.alert_threshold.unwrap_or(80.0),
+                    source: BudgetSource::AwsBudgets,
+                    external_name: Some(budget.name.clone()),
+                    created_at: local.map(|b| b.created_at).unwrap_or(now),
+                    updated_at: now,
+                }))
+            }
+            // The linked AWS budget was deleted, keep the last imported values
+            None => Ok(local.cloned()),
+        }
+    }
+
+    /// Create or update a CloudBridge budget in AWS Budgets, returning the AWS budget name
+    fn push_budget(
+        &self,
+        budget: &BudgetInfo,
+        exists: bool,
+        alert_email: Option<&str>,
+    ) -> Result<String> {
+        let aws_account_id = self.call_sts_get_caller_identity()?.account;
+        let name = budget
+            .external_name
+            .clone()
+            .unwrap_or_else(|| format!("CloudBridge-{}", self.account_name));
+
+        let aws_budget = serde_json::json!({
+            "BudgetName": name,
+            "BudgetLimit": {
+                "Amount": format!("{:.2}", budget.monthly_budget),
+                "Unit": budget.currency
+            },
+            "TimeUnit": "MONTHLY",
+            "BudgetType": "COST"
+        });
+
+        if exists {
+            let request_body = serde_json::json!({
+                "AccountId": aws_account_id,
+                "NewBudget": aws_budget
+            });
+            self.call_budgets_api("UpdateBudget", &request_body)?;
+        } else {
+            let mut request_body = serde_json::json!({
+                "AccountId": aws_account_id,
+                "Budget": aws_budget
+            });
+            // AWS requires at least one subscriber per notification
+            if let Some(email) = alert_email {
+                request_body["NotificationsWithSubscribers"] = serde_json::json!([{
+                    "Notification": {
+                        "NotificationType": "ACTUAL",
+                        "ComparisonOperator": "GREATER_THAN",
+                        "Threshold": budget.alert_threshold,
+                        "ThresholdType": "PERCENTAGE"
+                    },
+                    "Subscribers": [{
+                        "SubscriptionType": "EMAIL",
+                        "Address": email
+                    }]
+                }]);
+            }
+            self.call_budgets_api("CreateBudget", &request_body)?;
+        }
+
+        tracing::info!(
+            "Pushed budget {} to AWS Budgets for account {}",
+            name,
+            self.account_name
+        );
+        Ok(name)
     }
 
     /// Sign with specified region (for services like Cost Explorer that are only available in specific regions)
@@ -363,7 +804,7 @@ impl AwsCloudService {
         all_headers.push(("x-amz-content-sha256".to_string(), payload_hash.clone()));
 
         // Sort by lowercase key
-        all_headers.sort_by(|a, b| a.0.to_lowercase().cmp(&b.0.to_lowercase()));
+        all_headers.sort_by_key(|a| a.0.to_lowercase());
 
         let canonical_headers: String = all_headers
             .iter()
@@ -419,22 +860,131 @@ struct StsCallerIdentity {
     user_id: String,
 }
 
+/// Text of the first `<tag>` element (simple XML parsing, avoids additional dependencies)
+fn xml_tag(xml: &str, tag: &str) -> Option<String> {
+    let start_tag = format!("<{}>", tag);
+    let end_tag = format!("</{}>", tag);
+    let start = xml.find(&start_tag)? + start_tag.len();
+    let end = start + xml[start..].find(&end_tag)?;
+    Some(xml[start..end].to_string())
+}
+
+/// IAM action an API call needs, e.g. `ce:GetCostAndUsage` (used when the error
+/// message does not name it)
+fn required_iam_action(service: &str, action: &str) -> String {
+    match service {
+        "budgets" if action.starts_with("Describe") => "budgets:ViewBudget".to_string(),
+        "budgets" => "budgets:ModifyBudget".to_string(),
+        _ => format!("{}:{}", service, action),
+    }
+}
+
+/// Classify an AWS error response: JSON APIs send `__type` and `message`, query APIs
+/// such as STS an XML `<Error>` with `Code` and `Message`
+fn classify_error(
+    api_name: &str,
+    status: u16,
+    body: &str,
+    required_action: Option<&str>,
+    retry_after: Option<std::time::Duration>,
+) -> ProviderError {
+    let (code, message) = match serde_json::from_str::<serde_json::Value>(body) {
+        Ok(json) => {
+            let field = |names: &[&str]| {
+                names
+                    .iter()
+                    .find_map(|name| json.get(*name).and_then(|v| v.as_str()))
+                    .unwrap_or_default()
+                    .to_string()
+            };
+            // e.g. "com.amazonaws.awsinsightsindexservice#AccessDeniedException"
+            let code = field(&["__type", "code", "Code"]);
+            let code = code.rsplit('#').next().unwrap_or_default();
+            let code = code.split(':').next().unwrap_or_default().to_string();
+            (code, field(&["message", "Message"]))
+        }
+        Err(_) => (
+            xml_tag(body, "Code").unwrap_or_default(),
+            xml_tag(body, "Message").unwrap_or_default(),
+        ),
+    };
+
+    let detail = if code.is_empty() {
+        format!(
+            "{} request failed: HTTP {} - {}",
+            api_name,
+            status,
+            redact::excerpt(body)
+        )
+    } else {
+        format!(
+            "{} request failed: {} - {}",
+            api_name,
+            code,
+            redact::redact(&message)
+        )
+    };
+
+    match code.as_str() {
+        "UnrecognizedClientException"
+        | "InvalidClientTokenId"
+        | "SignatureDoesNotMatch"
+        | "InvalidSignatureException"
+        | "IncompleteSignature"
+        | "MissingAuthenticationToken"
+        | "ExpiredToken"
+        | "ExpiredTokenException"
+        | "AuthFailure" => ProviderError::AuthFailed(detail),
+        // Cost Explorer answers this way until it is enabled for the account
+        "AccessDeniedException"
+            if message
+                .to_ascii_lowercase()
+                .contains("not enabled for cost explorer") =>
+        {
+            ProviderError::DataNotReady(detail)
+        }
+        "AccessDeniedException" | "AccessDenied" | "UnauthorizedOperation" => {
+            ProviderError::PermissionDenied {
+                action: denied_action(&message).or(required_action.map(str::to_string)),
+                message: detail,
+            }
+        }
+        "ThrottlingException"
+        | "Throttling"
+        | "TooManyRequestsException"
+        | "RequestLimitExceeded"
+        | "LimitExceededException" => ProviderError::Throttled(retry_after),
+        "DataUnavailableException" | "BillExpirationException" => {
+            ProviderError::DataNotReady(detail)
+        }
+        _ => match status {
+            401 => ProviderError::AuthFailed(detail),
+            403 => ProviderError::PermissionDenied {
+                action: required_action.map(str::to_string),
+                message: detail,
+            },
+            429 => ProviderError::Throttled(retry_after),
+            _ => ProviderError::Unexpected(detail),
+        },
+    }
+}
+
+/// Action named in an AWS access denied message
+/// ("... is not authorized to perform: ce:GetCostAndUsage on resource: ...")
+fn denied_action(message: &str) -> Option<String> {
+    let (_, rest) = message.split_once("perform: ")?;
+    let action = rest.split_whitespace().next()?;
+    let action = action.trim_end_matches(|c: char| !c.is_ascii_alphanumeric());
+    action.contains(':').then(|| action.to_string())
+}
+
 /// Parse STS GetCallerIdentity XML response
 fn parse_sts_response(xml: &str) -> Result<StsCallerIdentity> {
-    // Simple XML parsing (avoid additional dependencies)
-    let extract = |tag: &str| -> Option<String> {
-        let start_tag = format!("<{}>", tag);
-        let end_tag = format!("</{}>", tag);
-        let start = xml.find(&start_tag)? + start_tag.len();
-        let end = xml.find(&end_tag)?;
-        Some(xml[start..end].to_string())
-    };
+    let extract = |tag: &str| xml_tag(xml, tag);
 
     // Check for errors
     if xml.contains("<Error>") {
-        let code = extract("Code").unwrap_or_else(|| "Unknown".to_string());
-        let message = extract("Message").unwrap_or_else(|| "Unknown error".to_string());
-        return Err(anyhow!("AWS STS error: {} - {}", code, message));
+        return Err(classify_error("STS", 200, xml, None, None).into());
     }
 
     Ok(StsCallerIdentity {
@@ -515,6 +1065,7 @@ fn parse_cost_explorer_response(
                             service: service_name,
                             amount,
                             currency,
+                            dimensions: Default::default(),
                         });
                     }
                 }
@@ -581,6 +1132,7 @@ fn parse_daily_cost_response(json: &str, account_id: &str) -> Result<Vec<CostDat
                     service: "Total".to_string(),
                     amount,
                     currency,
+                    dimensions: Default::default(),
                 });
             }
         }
@@ -590,6 +1142,347 @@ fn parse_daily_cost_response(json: &str, account_id: &str) -> Result<Vec<CostDat
     Ok(cost_data)
 }
 
+/// Parse a Cost Explorer response grouped by SERVICE and an optional second dimension.
+/// Returns the cost records and the next page token.
+fn parse_grouped_cost_response(
+    json: &str,
+    account_id: &str,
+    dimension: &CostDimension,
+) -> Result<(Vec<CostData>, Option<String>)> {
+    #[derive(Deserialize)]
+    struct CeResponse {
+        #[serde(rename = "ResultsByTime")]
+        results_by_time: Option<Vec<TimeResult>>,
+        #[serde(rename = "NextPageToken")]
+        next_page_token: Option<String>,
+    }
+
+    #[derive(Deserialize)]
+    struct TimeResult {
+        #[serde(rename = "TimePeriod")]
+        time_period: TimePeriod,
+        #[serde(rename = "Groups")]
+        groups: Option<Vec<CostGroup>>,
+    }
+
+    #[derive(Deserialize)]
+    struct TimePeriod {
+        #[serde(rename = "Start")]
+        start: String,
+    }
+
+    #[derive(Deserialize)]
+    struct CostGroup {
+        #[serde(rename = "Keys")]
+        keys: Vec<String>,
+        #[serde(rename = "Metrics")]
+        metrics: CostMetrics,
+    }
+
+    #[derive(Deserialize)]
+    struct CostMetrics {
+        #[serde(rename = "UnblendedCost")]
+        unblended_cost: CostAmount,
+    }
+
+    #[derive(Deserialize)]
+    struct CostAmount {
+        #[serde(rename = "Amount")]
+        amount: String,
+        #[serde(rename = "Unit")]
+        unit: String,
+    }
+
+    let response: CeResponse = serde_json::from_str(json)?;
+    let dimension_key = dimension.key();
+
+    let mut cost_data = Vec::new();
+    for result in response.results_by_time.unwrap_or_default() {
+        for group in result.groups.unwrap_or_default() {
+            let amount: f64 = group.metrics.unblended_cost.amount.parse().unwrap_or(0.0);
+            if amount == 0.0 {
+                continue;
+            }
+
+            let service = group.keys.first().cloned().unwrap_or_default();
+            let mut dimensions = std::collections::BTreeMap::new();
+            dimensions.insert("SERVICE".to_string(), service.clone());
+
+            if let Some(value) = group.keys.get(1) {
+                // Tag group keys are returned as "key$value", with an empty value when untagged
+                let value = match dimension {
+                    CostDimension::Tag(_) => value
+                        .split_once('$')
+                        .map(|(_, v)| v.to_string())
+                        .unwrap_or_else(|| value.clone()),
+                    _ => value.clone(),
+                };
+                let value = if value.is_empty() || value == "NoRegion" {
+                    UNTAGGED_VALUE.to_string()
+                } else {
+                    value
+                };
+                dimensions.insert(dimension_key.clone(), value);
+            }
+
+            cost_data.push(CostData {
+                account_id: account_id.to_string(),
+                date: result.time_period.start.clone(),
+                service,
+                amount,
+                currency: group.metrics.unblended_cost.unit,
+                dimensions,
+            });
+        }
+    }
+
+    Ok((cost_data, response.next_page_token))
+}
+
+/// Read a Cost Explorer string amount at a JSON pointer (amounts are returned as strings)
+fn json_amount(value: &serde_json::Value, pointer: &str) -> f64 {
+    match value.pointer(pointer) {
+        Some(serde_json::Value::String(s)) => s.parse().unwrap_or(0.0),
+        Some(v) => v.as_f64().unwrap_or(0.0),
+        None => 0.0,
+    }
+}
+
+/// Parse GetSavingsPlansUtilization and GetSavingsPlansCoverage responses
+fn parse_savings_plans(
+    utilization: Option<&serde_json::Value>,
+    coverage: Option<&serde_json::Value>,
+) -> Option<CommitmentUtilization> {
+    let utilization = utilization?;
+    let total_commitment = json_amount(utilization, "/Total/Utilization/TotalCommitment");
+    if total_commitment <= 0.0 {
+        return None;
+    }
+
+    // Coverage is returned per period, so sum up and recompute the percentage
+    let (covered, on_demand) = coverage
+        .and_then(|c| c.pointer("/SavingsPlansCoverages"))
+        .and_then(|c| c.as_array())
+        .map(|periods| {
+            periods
+                .iter()
+                .fold((0.0, 0.0), |(covered, on_demand), period| {
+                    (
+                        covered + json_amount(period, "/Coverage/SpendCoveredBySavingsPlans"),
+                        on_demand + json_amount(period, "/Coverage/OnDemandCost"),
+                    )
+                })
+        })
+        .unwrap_or((0.0, 0.0));
+    let coverage_percentage = if covered + on_demand > 0.0 {
+        covered / (covered + on_demand) * 100.0
+    } else {
+        0.0
+    };
+
+    Some(CommitmentUtilization {
+        utilization_percentage: json_amount(
+            utilization,
+            "/Total/Utilization/UtilizationPercentage",
+        ),
+        coverage_percentage,
+        total_commitment,
+        unused_commitment: json_amount(utilization, "/Total/Utilization/UnusedCommitment"),
+        on_demand_not_covered: on_demand,
+        net_savings: json_amount(utilization, "/Total/Savings/NetSavings"),
+    })
+}
+
+/// Parse GetReservationUtilization and GetReservationCoverage responses
+fn parse_reservations(
+    utilization: Option<&serde_json::Value>,
+    coverage: Option<&serde_json::Value>,
+) -> Option<CommitmentUtilization> {
+    let utilization = utilization?;
+    let purchased_hours = json_amount(utilization, "/Total/PurchasedHours");
+    let total_commitment = json_amount(utilization, "/Total/TotalAmortizedFee");
+    if purchased_hours <= 0.0 && total_commitment <= 0.0 {
+        return None;
+    }
+
+    let (coverage_percentage, on_demand_not_covered) = coverage
+        .map(|c| {
+            (
+                json_amount(c, "/Total/CoverageHours/CoverageHoursPercentage"),
+                json_amount(c, "/Total/CoverageCost/OnDemandCost"),
+            )
+        })
+        .unwrap_or((0.0, 0.0));
+
+    Some(CommitmentUtilization {
+        utilization_percentage: json_amount(utilization, "/Total/UtilizationPercentage"),
+        coverage_percentage,
+        total_commitment,
+        unused_commitment: json_amount(utilization, "/Total/RICostForUnusedHours"),
+        on_demand_not_covered,
+        net_savings: json_amount(utilization, "/Total/NetRISavings"),
+    })
+}
+
+/// Parse a GetAnomalyMonitors response into (monitor ARN, monitor name) pairs
+fn parse_anomaly_monitors_response(json: &str) -> Result<AnomalyMonitorPage> {
+    let response: serde_json::Value = serde_json::from_str(json)
+        .map_err(|e| anyhow!("Failed to parse GetAnomalyMonitors response: {}", e))?;
+
+    let monitors = response["AnomalyMonitors"]
+        .as_array()
+        .map(|monitors| {
+            monitors
+                .iter()
+                .filter_map(|m| {
+                    Some((
+                        m["MonitorArn"].as_str()?.to_string(),
+                        m["MonitorName"].as_str().unwrap_or_default().to_string(),
+                    ))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let next_token = response["NextPageToken"].as_str().map(|s| s.to_string());
+    Ok((monitors, next_token))
+}
+
+/// Parse a GetAnomalies response
+fn parse_anomalies_response(
+    json: &str,
+    monitors: &HashMap<String, String>,
+    currency: &str,
+) -> Result<(Vec<CostAnomaly>, Option<String>)> {
+    let response: serde_json::Value = serde_json::from_str(json)
+        .map_err(|e| anyhow!("Failed to parse GetAnomalies response: {}", e))?;
+
+    let text = |v: &serde_json::Value, key: &str| v[key].as_str().map(|s| s.to_string());
+
+    let anomalies = response["Anomalies"]
+        .as_array()
+        .map(|anomalies| {
+            anomalies
+                .iter()
+                .map(|a| {
+                    let monitor_arn = text(a, "MonitorArn").unwrap_or_default();
+                    let root_causes = a["RootCauses"]
+                        .as_array()
+                        .map(|causes| {
+                            causes
+                                .iter()
+                                .map(|c| AnomalyRootCause {
+                                    service: text(c, "Service"),
+                                    region: text(c, "Region"),
+                                    usage_type: text(c, "UsageType"),
+                                    linked_account: text(c, "LinkedAccount"),
+                                })
+                                .collect()
+                        })
+                        .unwrap_or_default();
+
+                    CostAnomaly {
+                        anomaly_id: text(a, "AnomalyId").unwrap_or_default(),
+                        monitor_name: monitors.get(&monitor_arn).cloned(),
+                        monitor_arn,
+                        // Dates are returned as full timestamps, keep the day
+                        start_date: text(a, "AnomalyStartDate")
+                            .map(|d| d.chars().take(10).collect())
+                            .unwrap_or_default(),
+                        end_date: text(a, "AnomalyEndDate").map(|d| d.chars().take(10).collect()),
+                        total_impact: json_amount(a, "/Impact/TotalImpact"),
+                        max_impact: json_amount(a, "/Impact/MaxImpact"),
+                        actual_spend: json_amount(a, "/Impact/TotalActualSpend"),
+                        expected_spend: json_amount(a, "/Impact/TotalExpectedSpend"),
+                        currency: currency.to_string(),
+                        root_causes,
+                        feedback: a["Feedback"].as_str().and_then(AnomalyFeedback::from_key),
+                    }
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let next_token = response["NextPageToken"].as_str().map(|s| s.to_string());
+    Ok((anomalies, next_token))
+}
+
+/// A budget as defined in AWS Budgets
+#[derive(Debug, Clone)]
+pub struct AwsBudget {
+    pub name: String,
+    pub limit_amount: f64,
+    pub currency: String,
+    pub time_unit: String,
+    pub budget_type: String,
+    /// Percentage threshold of the first actual-spend notification, if any
+    pub alert_threshold: Option<f64>,
+}
+
+impl AwsBudget {
+    /// Only monthly cost budgets map onto CloudBridge budgets
+    fn is_monthly_cost(&self) -> bool {
+        self.time_unit == "MONTHLY" && self.budget_type == "COST"
+    }
+}
+
+/// Parse a DescribeBudgets response
+fn parse_describe_budgets_response(
+    json: &str,
+    default_currency: &str,
+) -> Result<(Vec<AwsBudget>, Option<String>)> {
+    let response: serde_json::Value = serde_json::from_str(json)
+        .map_err(|e| anyhow!("Failed to parse DescribeBudgets response: {}", e))?;
+
+    let budgets = response["Budgets"]
+        .as_array()
+        .map(|budgets| {
+            budgets
+                .iter()
+                .map(|b| AwsBudget {
+                    name: b["BudgetName"].as_str().unwrap_or_default().to_string(),
+                    limit_amount: json_amount(b, "/BudgetLimit/Amount"),
+                    currency: b
+                        .pointer("/BudgetLimit/Unit")
+                        .and_then(|u| u.as_str())
+                        .unwrap_or(default_currency)
+                        .to_string(),
+                    time_unit: b["TimeUnit"].as_str().unwrap_or_default().to_string(),
+                    budget_type: b["BudgetType"].as_str().unwrap_or_default().to_string(),
+                    alert_threshold: None,
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let next_token = response["NextToken"].as_str().map(|s| s.to_string());
+    Ok((budgets, next_token))
+}
+
+/// Parse a DescribeNotificationsForBudget response into a percentage alert threshold
+fn parse_budget_alert_threshold(json: &str) -> Result<Option<f64>> {
+    let response: serde_json::Value = serde_json::from_str(json).map_err(|e| {
+        anyhow!(
+            "Failed to parse DescribeNotificationsForBudget response: {}",
+            e
+        )
+    })?;
+
+    let threshold = response["Notifications"]
+        .as_array()
+        .and_then(|notifications| {
+            notifications
+                .iter()
+                .find(|n| {
+                    n["NotificationType"] == "ACTUAL"
+                        && n["ThresholdType"].as_str().unwrap_or("PERCENTAGE") == "PERCENTAGE"
+                })
+                .and_then(|n| n["Threshold"].as_f64())
+        });
+
+    Ok(threshold)
+}
+
 impl CloudService for AwsCloudService {
     fn validate_credentials(&self) -> Result<bool> {
         match self.call_sts_get_caller_identity() {
@@ -630,8 +1523,9 @@ impl CloudService for AwsCloudService {
         let current_costs = self.get_cost_data(&current_month_start, &current_month_end)?;
         let current_month_cost: f64 = current_costs.iter().map(|c| c.amount).sum();
         tracing::info!(
-            "Current month cost: {} USD ({} records)",
+            "Current month cost: {} {} ({} records)",
             current_month_cost,
+            self.partition.currency(),
             current_costs.len()
         );
 
@@ -639,8 +1533,9 @@ impl CloudService for AwsCloudService {
         let last_costs = self.get_cost_data(&last_month_start, &last_month_end)?;
         let last_month_cost: f64 = last_costs.iter().map(|c| c.amount).sum();
         tracing::info!(
-            "Last month cost: {} USD ({} records)",
+            "Last month cost: {} {} ({} records)",
             last_month_cost,
+            self.partition.currency(),
             last_costs.len()
         );
 
@@ -654,7 +1549,7 @@ impl CloudService for AwsCloudService {
         let currency = current_costs
             .first()
             .map(|c| c.currency.clone())
-            .unwrap_or_else(|| "USD".to_string());
+            .unwrap_or_else(|| self.partition.currency().to_string());
 
         // Aggregate current month costs by service
         let current_month_details = aggregate_costs_by_service(&current_costs);
@@ -681,7 +1576,7 @@ impl CloudService for AwsCloudService {
         let cost_data = self.call_cost_explorer_daily(start_date, end_date)?;
 
         // Aggregate daily costs
-        let (daily_costs, currency) = aggregate_daily_costs(&cost_data);
+        let (daily_costs, currency) = aggregate_daily_costs(&cost_data, self.partition.currency());
 
         Ok(super::CostTrend {
             account_id: self.account_id.clone(),
@@ -689,6 +1584,39 @@ impl CloudService for AwsCloudService {
             daily_costs,
         })
     }
+
+    fn get_cost_breakdown(
+        &self,
+        start_date: &str,
+        end_date: &str,
+        dimension: &CostDimension,
+    ) -> Result<Vec<CostData>> {
+        self.call_cost_explorer_grouped(start_date, end_date, dimension)
+    }
+
+    fn get_focus_costs(&self, start_date: &str, end_date: &str) -> Result<Vec<FocusCost>> {
+        // Service x region is the finest split Cost Explorer offers without resource-level data
+        let costs =
+            self.call_cost_explorer_grouped(start_date, end_date, &CostDimension::Region)?;
+        let aws_account_id = self
+            .call_sts_get_caller_identity()
+            .ok()
+            .map(|identity| identity.account);
+
+        Ok(costs
+            .iter()
+            .map(|cost| {
+                let period_end = focus::next_month_start(&cost.date).min(end_date.to_string());
+                focus::from_cost_data(
+                    CloudProvider::AWS,
+                    cost,
+                    &period_end,
+                    aws_account_id.clone(),
+                    Some(self.account_name.clone()),
+                )
+            })
+            .collect())
+    }
 }
 
 /// Aggregate cost data by service
@@ -723,11 +1651,14 @@ fn aggregate_costs_by_service(costs: &[CostData]) -> Vec<super::ServiceCost> {
 }
 
 /// Aggregate daily costs by date, returns (daily cost list, currency)
-fn aggregate_daily_costs(costs: &[CostData]) -> (Vec<super::DailyCost>, String) {
+fn aggregate_daily_costs(
+    costs: &[CostData],
+    default_currency: &str,
+) -> (Vec<super::DailyCost>, String) {
     use std::collections::HashMap;
 
     let mut date_map: HashMap<String, f64> = HashMap::new();
-    let mut currency = "USD".to_string();
+    let mut currency = default_currency.to_string();
 
     for cost in costs {
         *date_map.entry(cost.date.clone()).or_insert(0.0) += cost.amount;
@@ -749,10 +1680,270 @@ fn aggregate_daily_costs(costs: &[CostData]) -> (Vec<super::DailyCost>, String)
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_classify_error() {
+        let denied = r#"{"__type":"com.amazonaws.awsinsightsindexservice#AccessDeniedException","Message":"User: arn:aws:iam::123456789012:user/ci is not authorized to perform: ce:GetCostAndUsage on resource: arn:aws:ce:us-east-1:123456789012:/GetCostAndUsage"}"#;
+        match classify_error("Cost Explorer", 400, denied, Some("ce:Other"), None) {
+            ProviderError::PermissionDenied { action, message } => {
+                assert_eq!(action.as_deref(), Some("ce:GetCostAndUsage"));
+                assert!(message.starts_with("Cost Explorer request failed: AccessDeniedException"));
+                assert!(!message.contains("123456789012"));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        // The action falls back to the one the call needs
+        let denied = r#"{"__type":"AccessDeniedException","Message":"Access denied"}"#;
+        assert_eq!(
+            classify_error(
+                "AWS Budgets",
+                400,
+                denied,
+                Some(&required_iam_action("budgets", "DescribeBudgets")),
+                None
+            ),
+            ProviderError::PermissionDenied {
+                action: Some("budgets:ViewBudget".to_string()),
+                message: "AWS Budgets request failed: AccessDeniedException - Access denied"
+                    .to_string(),
+            }
+        );
+
+        let not_enabled = r#"{"__type":"AccessDeniedException","Message":"User not enabled for cost explorer access"}"#;
+        assert!(matches!(
+            classify_error("Cost Explorer", 400, not_enabled, None, None),
+            ProviderError::DataNotReady(_)
+        ));
+
+        let sts = "<ErrorResponse><Error><Type>Sender</Type><Code>InvalidClientTokenId</Code><Message>The security token included in the request is invalid.</Message></Error></ErrorResponse>";
+        assert!(matches!(
+            classify_error("STS", 403, sts, None, None),
+            ProviderError::AuthFailed(_)
+        ));
+
+        let throttled = r#"{"__type":"LimitExceededException","message":"Rate exceeded"}"#;
+        assert_eq!(
+            classify_error(
+                "Cost Explorer",
+                400,
+                throttled,
+                None,
+                Some(std::time::Duration::from_secs(5))
+            ),
+            ProviderError::Throttled(Some(std::time::Duration::from_secs(5)))
+        );
+
+        let no_data = r#"{"__type":"DataUnavailableException","Message":"Data is not available"}"#;
+        assert!(matches!(
+            classify_error("Cost Explorer", 400, no_data, None, None),
+            ProviderError::DataNotReady(_)
+        ));
+
+        assert!(matches!(
+            classify_error("Cost Explorer", 503, "Service Unavailable", None, None),
+            ProviderError::Unexpected(_)
+        ));
+    }
+
     #[test]
     fn test_sha256_hash() {
         let hash = AwsCloudService::sha256_hash(b"test");
         assert!(!hash.is_empty());
         assert_eq!(hash.len(), 64); // SHA256 produces 32 bytes = 64 hex characters
     }
+
+    #[test]
+    fn test_partition_from_region() {
+        let china = AwsPartition::from_region("cn-north-1");
+        assert_eq!(china, AwsPartition::China);
+        assert_eq!(china.ce_host(), "ce.cn-northwest-1.amazonaws.com.cn");
+        assert_eq!(china.billing_region(), "cn-northwest-1");
+        assert_eq!(
+            china.sts_host("cn-north-1"),
+            "sts.cn-north-1.amazonaws.com.cn"
+        );
+        assert_eq!(china.currency(), "CNY");
+
+        let gov = AwsPartition::from_region("us-gov-east-1");
+        assert_eq!(gov, AwsPartition::GovCloud);
+        assert_eq!(gov.ce_host(), "ce.us-gov-west-1.amazonaws.com");
+        assert_eq!(gov.billing_region(), "us-gov-west-1");
+        assert_eq!(gov.budgets_host(), "budgets.us-gov-west-1.amazonaws.com");
+        assert_eq!(
+            gov.sts_host("us-gov-east-1"),
+            "sts.us-gov-east-1.amazonaws.com"
+        );
+        assert_eq!(gov.currency(), "USD");
+
+        let standard = AwsPartition::from_region("eu-central-1");
+        assert_eq!(standard, AwsPartition::Standard);
+        assert_eq!(standard.ce_host(), "ce.us-east-1.amazonaws.com");
+        assert_eq!(standard.budgets_host(), "budgets.amazonaws.com");
+        // us-east-1 prefix must not be mistaken for GovCloud
+        assert_eq!(
+            AwsPartition::from_region("us-east-1"),
+            AwsPartition::Standard
+        );
+    }
+
+    #[test]
+    fn test_parse_grouped_cost_response_by_tag() {
+        let json = r#"{
+            "ResultsByTime": [{
+                "TimePeriod": {"Start": "2024-05-01", "End": "2024-06-01"},
+                "Groups": [
+                    {"Keys": ["Amazon EC2", "team$platform"],
+                     "Metrics": {"UnblendedCost": {"Amount": "12.5", "Unit": "USD"}}},
+                    {"Keys": ["Amazon S3", "team$"],
+                     "Metrics": {"UnblendedCost": {"Amount": "2.5", "Unit": "USD"}}},
+                    {"Keys": ["Tax", "team$"],
+                     "Metrics": {"UnblendedCost": {"Amount": "0", "Unit": "USD"}}}
+                ]
+            }],
+            "NextPageToken": "abc"
+        }"#;
+
+        let dimension = CostDimension::Tag("team".to_string());
+        let (costs, token) = parse_grouped_cost_response(json, "acc", &dimension).unwrap();
+
+        assert_eq!(token.as_deref(), Some("abc"));
+        assert_eq!(costs.len(), 2);
+        assert_eq!(costs[0].service, "Amazon EC2");
+        assert_eq!(costs[0].dimensions["TAG:team"], "platform");
+        assert_eq!(costs[1].dimensions["TAG:team"], UNTAGGED_VALUE);
+        assert_eq!(costs[1].dimensions["SERVICE"], "Amazon S3");
+    }
+
+    #[test]
+    fn test_parse_savings_plans() {
+        let utilization: serde_json::Value = serde_json::from_str(
+            r#"{"Total": {
+                "Utilization": {"TotalCommitment": "100.0", "UsedCommitment": "90.0",
+                                "UnusedCommitment": "10.0", "UtilizationPercentage": "90"},
+                "Savings": {"NetSavings": "25.5", "OnDemandCostEquivalent": "125.5"}
+            }}"#,
+        )
+        .unwrap();
+        let coverage: serde_json::Value = serde_json::from_str(
+            r#"{"SavingsPlansCoverages": [
+                {"Coverage": {"SpendCoveredBySavingsPlans": "60", "OnDemandCost": "20"}},
+                {"Coverage": {"SpendCoveredBySavingsPlans": "30", "OnDemandCost": "10"}}
+            ]}"#,
+        )
+        .unwrap();
+
+        let sp = parse_savings_plans(Some(&utilization), Some(&coverage)).unwrap();
+        assert_eq!(sp.utilization_percentage, 90.0);
+        assert_eq!(sp.unused_commitment, 10.0);
+        assert_eq!(sp.on_demand_not_covered, 30.0);
+        assert_eq!(sp.coverage_percentage, 75.0);
+        assert_eq!(sp.net_savings, 25.5);
+
+        assert!(parse_savings_plans(None, Some(&coverage)).is_none());
+    }
+
+    #[test]
+    fn test_parse_reservations() {
+        let utilization: serde_json::Value = serde_json::from_str(
+            r#"{"Total": {"UtilizationPercentage": "80", "PurchasedHours": "720",
+                          "TotalAmortizedFee": "50", "RICostForUnusedHours": "10",
+                          "NetRISavings": "15"}}"#,
+        )
+        .unwrap();
+        let coverage: serde_json::Value = serde_json::from_str(
+            r#"{"Total": {"CoverageHours": {"CoverageHoursPercentage": "70"},
+                          "CoverageCost": {"OnDemandCost": "42.5"}}}"#,
+        )
+        .unwrap();
+
+        let ri = parse_reservations(Some(&utilization), Some(&coverage)).unwrap();
+        assert_eq!(ri.utilization_percentage, 80.0);
+        assert_eq!(ri.coverage_percentage, 70.0);
+        assert_eq!(ri.unused_commitment, 10.0);
+        assert_eq!(ri.on_demand_not_covered, 42.5);
+
+        let empty: serde_json::Value =
+            serde_json::from_str(r#"{"Total": {"PurchasedHours": "0"}}"#).unwrap();
+        assert!(parse_reservations(Some(&empty), None).is_none());
+    }
+
+    #[test]
+    fn test_parse_describe_budgets_response() {
+        let json = r#"{
+            "Budgets": [
+                {"BudgetName": "monthly-total",
+                 "BudgetLimit": {"Amount": "1500.0", "Unit": "USD"},
+                 "TimeUnit": "MONTHLY", "BudgetType": "COST"},
+                {"BudgetName": "ri-utilization",
+                 "TimeUnit": "MONTHLY", "BudgetType": "RI_UTILIZATION"}
+            ],
+            "NextToken": "next"
+        }"#;
+
+        let (budgets, token) = parse_describe_budgets_response(json, "USD").unwrap();
+
+        assert_eq!(token.as_deref(), Some("next"));
+        assert_eq!(budgets.len(), 2);
+        assert_eq!(budgets[0].name, "monthly-total");
+        assert_eq!(budgets[0].limit_amount, 1500.0);
+        assert_eq!(budgets[0].currency, "USD");
+        assert!(budgets[0].is_monthly_cost());
+        assert!(!budgets[1].is_monthly_cost());
+    }
+
+    #[test]
+    fn test_parse_budget_alert_threshold() {
+        let json = r#"{"Notifications": [
+            {"NotificationType": "FORECASTED", "ComparisonOperator": "GREATER_THAN",
+             "Threshold": 100.0, "ThresholdType": "PERCENTAGE"},
+            {"NotificationType": "ACTUAL", "ComparisonOperator": "GREATER_THAN",
+             "Threshold": 85.0, "ThresholdType": "PERCENTAGE"}
+        ]}"#;
+        assert_eq!(parse_budget_alert_threshold(json).unwrap(), Some(85.0));
+        assert_eq!(
+            parse_budget_alert_threshold(r#"{"Notifications": []}"#).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_anomalies_response() {
+        let json = r#"{
+            "Anomalies": [{
+                "AnomalyId": "anomaly-1",
+                "AnomalyStartDate": "2024-05-03T00:00:00Z",
+                "AnomalyEndDate": "2024-05-05T00:00:00Z",
+                "MonitorArn": "arn:aws:ce::123456789012:anomalymonitor/abc",
+                "RootCauses": [{
+                    "Service": "Amazon Elastic Compute Cloud - Compute",
+                    "Region": "us-east-1",
+                    "UsageType": "BoxUsage:m5.large",
+                    "LinkedAccount": "123456789012"
+                }],
+                "Impact": {"MaxImpact": 40.5, "TotalImpact": 95.25,
+                           "TotalActualSpend": 120.0, "TotalExpectedSpend": 24.75},
+                "Feedback": "PLANNED_ACTIVITY"
+            }]
+        }"#;
+        let monitors = HashMap::from([(
+            "arn:aws:ce::123456789012:anomalymonitor/abc".to_string(),
+            "Services".to_string(),
+        )]);
+
+        let (anomalies, token) = parse_anomalies_response(json, &monitors, "USD").unwrap();
+
+        assert!(token.is_none());
+        assert_eq!(anomalies.len(), 1);
+        let anomaly = &anomalies[0];
+        assert_eq!(anomaly.start_date, "2024-05-03");
+        assert_eq!(anomaly.end_date.as_deref(), Some("2024-05-05"));
+        assert_eq!(anomaly.monitor_name.as_deref(), Some("Services"));
+        assert_eq!(anomaly.total_impact, 95.25);
+        assert_eq!(anomaly.max_impact, 40.5);
+        assert_eq!(anomaly.feedback, Some(AnomalyFeedback::PlannedActivity));
+        assert_eq!(
+            anomaly.root_causes[0].describe(),
+            "Amazon Elastic Compute Cloud - Compute / us-east-1 / BoxUsage:m5.large"
+        );
+    }
 }
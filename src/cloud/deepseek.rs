@@ -2,8 +2,14 @@
 
 use anyhow::{anyhow, Result};
 use serde::Deserialize;
+use std::collections::BTreeMap;
 
-use super::{CloudProvider, CloudService, CostData, CostSummary, CostTrend, ServiceCost};
+use super::http::{self, Endpoint, HttpRequest};
+use super::{
+    focus, CloudProvider, CloudService, CostData, CostDimension, CostSummary, CostTrend, FocusCost,
+    ProviderError, ServiceCost,
+};
+use crate::redact;
 
 /// DeepSeek balance info
 #[derive(Debug, Deserialize)]
@@ -33,6 +39,8 @@ pub struct DeepSeekService {
     account_id: String,
     account_name: String,
     api_key: String,
+    /// Endpoint overrides by service (`api`)
+    endpoints: BTreeMap<String, String>,
 }
 
 impl DeepSeekService {
@@ -43,41 +51,80 @@ impl DeepSeekService {
         _secret: String, // Not used for DeepSeek, but kept for interface consistency
         _region: Option<String>, // Not used for DeepSeek
     ) -> Self {
+        let endpoints =
+            http::endpoint_overrides(&account_id, &account_name, CloudProvider::DeepSeek);
         Self {
             account_id,
             account_name,
             api_key,
+            endpoints,
         }
     }
 
     /// Get user balance from DeepSeek API
     pub fn get_balance(&self) -> Result<BalanceResponse> {
-        let response = ureq::get("https://api.deepseek.com/user/balance")
-            .header("Accept", "application/json")
-            .header("Authorization", &format!("Bearer {}", self.api_key))
-            .call()
-            .map_err(|e| anyhow!("Failed to call DeepSeek API: {}", e))?;
-
-        let body = response
-            .into_body()
-            .read_to_string()
-            .map_err(|e| anyhow!("Failed to read response: {}", e))?;
-
-        let balance: BalanceResponse = serde_json::from_str(&body)
+        let endpoint = Endpoint::resolve(&self.endpoints, "api", "api.deepseek.com")?;
+        let response = http::send(
+            CloudProvider::DeepSeek,
+            &self.account_id,
+            "DeepSeek API",
+            || {
+                Ok(HttpRequest::get(endpoint.url("/user/balance"))
+                    .header("Accept", "application/json")
+                    .header("Authorization", &format!("Bearer {}", self.api_key)))
+            },
+        )?;
+
+        if response.status >= 400 {
+            return Err(
+                classify_error(response.status, &response.body, response.retry_after).into(),
+            );
+        }
+
+        let balance: BalanceResponse = serde_json::from_str(&response.body)
             .map_err(|e| anyhow!("Failed to parse DeepSeek response: {}", e))?;
 
         Ok(balance)
     }
 }
 
+/// Classify a DeepSeek error response (`{"error":{"message":...,"type":...}}`) by
+/// HTTP status
+fn classify_error(
+    status: u16,
+    body: &str,
+    retry_after: Option<std::time::Duration>,
+) -> ProviderError {
+    let message = serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|json| {
+            json.pointer("/error/message")
+                .and_then(|m| m.as_str())
+                .map(|m| m.to_string())
+        })
+        .unwrap_or_else(|| redact::excerpt(body));
+    let detail = format!("DeepSeek API error: HTTP {} - {}", status, message);
+
+    match status {
+        401 => ProviderError::AuthFailed(detail),
+        403 => ProviderError::PermissionDenied {
+            action: None,
+            message: detail,
+        },
+        429 => ProviderError::Throttled(retry_after),
+        _ => ProviderError::Unexpected(detail),
+    }
+}
+
 impl CloudService for DeepSeekService {
     fn validate_credentials(&self) -> Result<bool> {
         match self.get_balance() {
             Ok(_) => Ok(true),
-            Err(e) => {
+            Err(e) if matches!(ProviderError::find(&e), Some(ProviderError::AuthFailed(_))) => {
                 tracing::warn!("DeepSeek credential validation failed: {}", e);
                 Ok(false)
             }
+            Err(e) => Err(e),
         }
     }
 
@@ -144,4 +191,56 @@ impl CloudService for DeepSeekService {
             daily_costs: vec![],
         })
     }
+
+    fn get_cost_breakdown(
+        &self,
+        _start_date: &str,
+        _end_date: &str,
+        _dimension: &CostDimension,
+    ) -> Result<Vec<CostData>> {
+        // DeepSeek only reports balances, there is nothing to break down
+        Ok(vec![])
+    }
+
+    fn get_focus_costs(&self, start_date: &str, end_date: &str) -> Result<Vec<FocusCost>> {
+        // The balance API has no spend history, so this yields rows only once
+        // get_cost_data returns usage; DeepSeek API usage is AI and Machine Learning
+        Ok(self
+            .get_cost_data(start_date, end_date)?
+            .iter()
+            .map(|cost| {
+                focus::from_cost_data(
+                    CloudProvider::DeepSeek,
+                    cost,
+                    &focus::next_day(&cost.date),
+                    None,
+                    Some(self.account_name.clone()),
+                )
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_error() {
+        let body = r#"{"error":{"message":"Authentication Fails (no such user)","type":"authentication_error","param":null,"code":"invalid_request_error"}}"#;
+        assert_eq!(
+            classify_error(401, body, None),
+            ProviderError::AuthFailed(
+                "DeepSeek API error: HTTP 401 - Authentication Fails (no such user)".to_string()
+            )
+        );
+        assert_eq!(
+            classify_error(429, "", Some(std::time::Duration::from_secs(3))),
+            ProviderError::Throttled(Some(std::time::Duration::from_secs(3)))
+        );
+        assert!(matches!(
+            classify_error(503, "upstream unavailable", None),
+            ProviderError::Unexpected(_)
+        ));
+    }
 }
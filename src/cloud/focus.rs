@@ -0,0 +1,402 @@
+//! FOCUS-aligned cost records
+//!
+//! Provider-neutral cost rows following the FinOps Open Cost and Usage Specification
+//! (FOCUS 1.0) column set. Providers map their billing data into [`FocusCost`]; the rows
+//! are stored in the `focus_costs` table and can be exported as a FOCUS dataset.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::{CloudProvider, CostData, CostDimension};
+
+/// FOCUS cost record
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct FocusCost {
+    /// CloudBridge account ID (not a FOCUS column)
+    #[serde(skip)]
+    pub account_id: String,
+    /// Amount charged on the invoice
+    pub billed_cost: f64,
+    /// Amortized cost after discounts and commitments
+    pub effective_cost: f64,
+    /// Cost at public list prices
+    pub list_cost: f64,
+    pub billing_currency: String,
+    pub charge_category: ChargeCategory,
+    /// Inclusive start date (YYYY-MM-DD)
+    pub charge_period_start: String,
+    /// Exclusive end date (YYYY-MM-DD)
+    pub charge_period_end: String,
+    pub provider_name: String,
+    pub service_name: String,
+    pub service_category: String,
+    pub region_id: Option<String>,
+    pub resource_id: Option<String>,
+    pub sub_account_id: Option<String>,
+    pub sub_account_name: Option<String>,
+    pub tags: BTreeMap<String, String>,
+}
+
+/// FOCUS ChargeCategory
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChargeCategory {
+    Usage,
+    Purchase,
+    Tax,
+    Credit,
+    Adjustment,
+}
+
+impl ChargeCategory {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChargeCategory::Usage => "Usage",
+            ChargeCategory::Purchase => "Purchase",
+            ChargeCategory::Tax => "Tax",
+            ChargeCategory::Credit => "Credit",
+            ChargeCategory::Adjustment => "Adjustment",
+        }
+    }
+
+    /// Infer the category from the service name and amount
+    pub fn infer(service: &str, amount: f64) -> Self {
+        let service = service.to_lowercase();
+        if service
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .any(|word| word == "tax")
+        {
+            ChargeCategory::Tax
+        } else if amount < 0.0 {
+            ChargeCategory::Credit
+        } else if service.contains("refund") {
+            ChargeCategory::Adjustment
+        } else if service.contains("upfront") || service.contains("subscription fee") {
+            ChargeCategory::Purchase
+        } else {
+            ChargeCategory::Usage
+        }
+    }
+}
+
+/// Keywords mapped to FOCUS ServiceCategory values, checked in order.
+/// Keywords of four characters or fewer must match a whole word of the service name
+/// (after stripping an "amazon"/"aws" prefix), longer ones may match anywhere.
+const SERVICE_CATEGORIES: &[(&str, &[&str])] = &[
+    (
+        "AI and Machine Learning",
+        &[
+            "sagemaker",
+            "bedrock",
+            "deepseek",
+            "machine learning",
+            "rekognition",
+            "comprehend",
+            "textract",
+            "transcribe",
+            "translate",
+            "polly",
+            "model studio",
+            "bailian",
+            "pai",
+        ],
+    ),
+    (
+        "Databases",
+        &[
+            "database",
+            "dynamodb",
+            "aurora",
+            "elasticache",
+            "redis",
+            "polardb",
+            "mongodb",
+            "documentdb",
+            "rds",
+        ],
+    ),
+    (
+        "Analytics",
+        &[
+            "athena",
+            "glue",
+            "kinesis",
+            "redshift",
+            "quicksight",
+            "maxcompute",
+            "opensearch",
+            "elasticsearch",
+            "log service",
+            "emr",
+            "sls",
+        ],
+    ),
+    (
+        "Storage",
+        &[
+            "simple storage",
+            "object storage",
+            "block store",
+            "file system",
+            "glacier",
+            "backup",
+            "s3",
+            "oss",
+            "ebs",
+            "efs",
+            "nas",
+        ],
+    ),
+    (
+        "Networking",
+        &[
+            "cloudfront",
+            "virtual private cloud",
+            "load balanc",
+            "route 53",
+            "data transfer",
+            "nat gateway",
+            "elastic ip",
+            "direct connect",
+            "cdn",
+            "vpc",
+            "slb",
+            "elb",
+            "eip",
+        ],
+    ),
+    (
+        "Integration",
+        &[
+            "simple queue",
+            "simple notification",
+            "eventbridge",
+            "step functions",
+            "api gateway",
+            "message",
+            "sqs",
+            "sns",
+        ],
+    ),
+    (
+        "Security",
+        &[
+            "guardduty",
+            "key management",
+            "secrets manager",
+            "security",
+            "certificate",
+            "inspector",
+            "shield",
+            "macie",
+            "waf",
+            "kms",
+        ],
+    ),
+    (
+        "Management and Governance",
+        &[
+            "cloudwatch",
+            "cloudtrail",
+            "systems manager",
+            "cost explorer",
+            "cloudmonitor",
+            "config",
+            "support",
+        ],
+    ),
+    ("Identity", &["cognito", "directory service", "identity"]),
+    (
+        "Developer Tools",
+        &["codebuild", "codepipeline", "codecommit", "x-ray"],
+    ),
+    (
+        "Compute",
+        &[
+            "elastic compute",
+            "function compute",
+            "lambda",
+            "fargate",
+            "lightsail",
+            "container",
+            "kubernetes",
+            "batch",
+            "compute",
+            "ec2",
+            "ecs",
+            "eks",
+            "ack",
+        ],
+    ),
+];
+
+/// Map a provider service name or code to a FOCUS ServiceCategory
+pub fn service_category(service: &str) -> &'static str {
+    let name = service.to_lowercase();
+    let words: Vec<&str> = name
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| {
+            w.strip_prefix("amazon")
+                .or_else(|| w.strip_prefix("aws"))
+                .filter(|rest| !rest.is_empty())
+                .unwrap_or(w)
+        })
+        .collect();
+
+    SERVICE_CATEGORIES
+        .iter()
+        .find(|(_, keywords)| {
+            keywords.iter().any(|keyword| {
+                if keyword.len() <= 4 {
+                    words.contains(keyword)
+                } else {
+                    name.contains(keyword)
+                }
+            })
+        })
+        .map(|(category, _)| *category)
+        .unwrap_or("Other")
+}
+
+/// Map a cost row carrying dimensions (service, region, tags) into a FOCUS record.
+///
+/// Cost Explorer, Aliyun bill and CUR rows only carry one amount, so it is used for
+/// billed, effective and list cost unless the provider mapping overrides them.
+pub fn from_cost_data(
+    provider: CloudProvider,
+    cost: &CostData,
+    period_end: &str,
+    sub_account_id: Option<String>,
+    sub_account_name: Option<String>,
+) -> FocusCost {
+    let tags = cost
+        .dimensions
+        .iter()
+        .filter_map(|(key, value)| {
+            key.strip_prefix("TAG:")
+                .filter(|_| value != super::UNTAGGED_VALUE)
+                .map(|tag| (tag.to_string(), value.clone()))
+        })
+        .collect();
+    let region_id = cost
+        .dimensions
+        .get(&CostDimension::Region.key())
+        .filter(|r| r.as_str() != super::UNTAGGED_VALUE)
+        .cloned();
+
+    FocusCost {
+        account_id: cost.account_id.clone(),
+        billed_cost: cost.amount,
+        effective_cost: cost.amount,
+        list_cost: cost.amount,
+        billing_currency: cost.currency.clone(),
+        charge_category: ChargeCategory::infer(&cost.service, cost.amount),
+        charge_period_start: cost.date.clone(),
+        charge_period_end: period_end.to_string(),
+        provider_name: provider_name(provider).to_string(),
+        service_name: cost.service.clone(),
+        service_category: service_category(&cost.service).to_string(),
+        region_id,
+        resource_id: None,
+        sub_account_id,
+        sub_account_name,
+        tags,
+    }
+}
+
+/// FOCUS ProviderName
+pub fn provider_name(provider: CloudProvider) -> &'static str {
+    match provider {
+        CloudProvider::AWS => "AWS",
+        CloudProvider::Aliyun => "Alibaba Cloud",
+        CloudProvider::DeepSeek => "DeepSeek",
+        other => other.display_name(),
+    }
+}
+
+/// First day of the month after `date` (YYYY-MM-DD), used as the end of monthly periods
+pub fn next_month_start(date: &str) -> String {
+    let year: i32 = date.get(0..4).and_then(|y| y.parse().ok()).unwrap_or(1970);
+    let month: u32 = date.get(5..7).and_then(|m| m.parse().ok()).unwrap_or(1);
+    if month >= 12 {
+        format!("{}-01-01", year + 1)
+    } else {
+        format!("{}-{:02}-01", year, month + 1)
+    }
+}
+
+/// Day after `date` (YYYY-MM-DD), used as the end of daily periods
+pub fn next_day(date: &str) -> String {
+    chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .ok()
+        .and_then(|d| d.succ_opt())
+        .map(|d| d.format("%Y-%m-%d").to_string())
+        .unwrap_or_else(|| date.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_service_category() {
+        assert_eq!(
+            service_category("Amazon Elastic Compute Cloud - Compute"),
+            "Compute"
+        );
+        assert_eq!(service_category("AmazonEC2"), "Compute");
+        assert_eq!(service_category("Amazon Simple Storage Service"), "Storage");
+        assert_eq!(service_category("AmazonS3"), "Storage");
+        assert_eq!(
+            service_category("Amazon Relational Database Service"),
+            "Databases"
+        );
+        assert_eq!(service_category("Object Storage Service"), "Storage");
+        assert_eq!(service_category("Elastic Compute Service"), "Compute");
+        assert_eq!(service_category("DeepSeek API"), "AI and Machine Learning");
+        // Short codes only match whole words
+        assert_eq!(service_category("Programmatic Records"), "Other");
+    }
+
+    #[test]
+    fn test_from_cost_data() {
+        let mut dimensions = BTreeMap::new();
+        dimensions.insert("SERVICE".to_string(), "Amazon S3".to_string());
+        dimensions.insert("REGION".to_string(), "us-west-2".to_string());
+        dimensions.insert("TAG:team".to_string(), "platform".to_string());
+        dimensions.insert(
+            "TAG:env".to_string(),
+            super::super::UNTAGGED_VALUE.to_string(),
+        );
+        let cost = CostData {
+            account_id: "acc".to_string(),
+            date: "2024-12-01".to_string(),
+            service: "Amazon S3".to_string(),
+            amount: 12.5,
+            currency: "USD".to_string(),
+            dimensions,
+        };
+
+        let focus = from_cost_data(
+            CloudProvider::AWS,
+            &cost,
+            &next_month_start(&cost.date),
+            Some("111122223333".to_string()),
+            None,
+        );
+
+        assert_eq!(focus.charge_period_end, "2025-01-01");
+        assert_eq!(focus.provider_name, "AWS");
+        assert_eq!(focus.service_category, "Storage");
+        assert_eq!(focus.charge_category, ChargeCategory::Usage);
+        assert_eq!(focus.region_id.as_deref(), Some("us-west-2"));
+        assert_eq!(focus.tags.len(), 1);
+        assert_eq!(focus.tags["team"], "platform");
+        assert_eq!(ChargeCategory::infer("Tax", 3.0), ChargeCategory::Tax);
+        assert_eq!(
+            ChargeCategory::infer("Amazon EC2", -5.0),
+            ChargeCategory::Credit
+        );
+    }
+}
@@ -0,0 +1,888 @@
+//! Shared HTTP client for provider APIs
+//!
+//! All provider calls go through one `ureq::Agent`, so connections are reused across
+//! calls. Requests wait for the account's token bucket before they are sent. Throttled
+//! requests are retried with jittered exponential backoff, honoring `Retry-After`;
+//! server errors (5xx) are retried only for idempotent requests, as a failed write may
+//! still have been applied. Requests are rebuilt for every attempt because signatures
+//! carry a timestamp and, for Alibaba Cloud, a single-use nonce.
+//!
+//! The agent uses the proxy and CA bundle from the `network` settings in config.json
+//! and is rebuilt when they change; provider hosts can be replaced per provider or
+//! account through `endpoints`.
+
+use anyhow::{anyhow, Context, Result};
+use rand::Rng;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::CertificateDer;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::{CloudProvider, ProviderError};
+use crate::config::NetworkConfig;
+use crate::redact;
+
+/// Timeout for a whole request, including reading the response
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Error codes in 4xx bodies that mean the request was rate limited. Alibaba Cloud
+/// qualifies them, e.g. `Throttling.User`.
+const THROTTLING_CODES: &[&str] = &[
+    "Throttling",
+    "ThrottlingException",
+    "TooManyRequests",
+    "TooManyRequestsException",
+    "LimitExceededException",
+    "RequestLimitExceeded",
+];
+
+/// Where a provider API is reached: the base URL and the `Host` requests are signed for
+#[derive(Debug, Clone, PartialEq)]
+pub struct Endpoint {
+    pub base_url: String,
+    pub host: String,
+}
+
+impl Endpoint {
+    /// Default HTTPS endpoint of a host
+    pub fn https(host: &str) -> Self {
+        Self {
+            base_url: format!("https://{}", host),
+            host: host.to_string(),
+        }
+    }
+
+    /// Endpoint from an override such as `https://ce.cn-northwest-1.amazonaws.com.cn`
+    /// or `http://127.0.0.1:4566`
+    pub fn parse(url: &str) -> Result<Self> {
+        let uri: ureq::http::Uri = url
+            .parse()
+            .map_err(|e| anyhow!("Invalid endpoint '{}': {}", url, e))?;
+        if !matches!(uri.scheme_str(), Some("http") | Some("https")) {
+            anyhow::bail!("Endpoint '{}' must start with http:// or https://", url);
+        }
+        if !matches!(uri.path(), "" | "/") || uri.query().is_some() {
+            anyhow::bail!("Endpoint '{}' must not have a path", url);
+        }
+        let host = uri
+            .authority()
+            .map(|authority| authority.as_str().rsplit('@').next().unwrap_or_default())
+            .filter(|host| !host.is_empty())
+            .ok_or_else(|| anyhow!("Endpoint '{}' has no host", url))?;
+        Ok(Self {
+            base_url: format!("{}://{}", uri.scheme_str().unwrap_or("https"), host),
+            host: host.to_string(),
+        })
+    }
+
+    /// The configured override for `service`, or the default host
+    pub fn resolve(
+        overrides: &BTreeMap<String, String>,
+        service: &str,
+        default_host: &str,
+    ) -> Result<Self> {
+        match overrides.get(service) {
+            Some(url) => Self::parse(url),
+            None => Ok(Self::https(default_host)),
+        }
+    }
+
+    /// URL of a path (with query) on this endpoint, e.g. `/?Action=...`
+    pub fn url(&self, path_and_query: &str) -> String {
+        format!("{}{}", self.base_url, path_and_query)
+    }
+}
+
+/// Endpoint overrides configured for an account, by service
+pub fn endpoint_overrides(
+    account_id: &str,
+    account_name: &str,
+    provider: CloudProvider,
+) -> BTreeMap<String, String> {
+    crate::config::current_config()
+        .map(|config| config.endpoints_for(account_id, account_name, provider))
+        .unwrap_or_default()
+}
+
+/// Certificates of a PEM bundle
+pub fn load_ca_bundle(path: &Path) -> Result<Vec<CertificateDer<'static>>> {
+    let pem = std::fs::read(path)
+        .with_context(|| format!("Failed to read CA bundle {}", path.display()))?;
+    let certificates = CertificateDer::pem_slice_iter(&pem)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| anyhow!("Invalid CA bundle {}: {:?}", path.display(), e))?;
+    if certificates.is_empty() {
+        anyhow::bail!("No certificates in CA bundle {}", path.display());
+    }
+    Ok(certificates)
+}
+
+/// A request to send; built again for every attempt
+pub struct HttpRequest {
+    url: String,
+    headers: Vec<(String, String)>,
+    /// Sent as POST when set, GET otherwise
+    body: Option<String>,
+    /// Whether sending it twice has the same effect as once, so server errors are retried
+    idempotent: bool,
+}
+
+impl HttpRequest {
+    pub fn get(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            headers: Vec::new(),
+            body: None,
+            idempotent: true,
+        }
+    }
+
+    /// POST request; not idempotent unless marked with `idempotent(true)`
+    pub fn post(url: impl Into<String>, body: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            headers: Vec::new(),
+            body: Some(body.into()),
+            idempotent: false,
+        }
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Mark the request as safe to repeat, e.g. a read sent as POST
+    pub fn idempotent(mut self, idempotent: bool) -> Self {
+        self.idempotent = idempotent;
+        self
+    }
+}
+
+/// Response of the last attempt; 4xx and 5xx statuses are returned, not errors
+#[derive(Debug)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub retry_after: Option<Duration>,
+    pub body: String,
+}
+
+/// When and how often to retry
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Attempts including the first one
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled for every further retry
+    pub base_delay: Duration,
+    /// Upper bound for a single delay
+    pub max_delay: Duration,
+    /// Longer `Retry-After` values are not waited for; the response is returned
+    pub max_retry_after: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(20),
+            max_retry_after: Duration::from_secs(60),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before retry number `retry` (0-based): exponential with "equal jitter",
+    /// i.e. between half and all of the exponential delay
+    fn backoff(&self, retry: u32) -> Duration {
+        let exponential = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_delay);
+        exponential.mul_f64(rand::thread_rng().gen_range(0.5..=1.0))
+    }
+}
+
+/// Token bucket: `burst` requests at once, refilled at `per_second`
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimit {
+    pub burst: f64,
+    pub per_second: f64,
+}
+
+impl RateLimit {
+    /// Request rate each provider's billing APIs tolerate (Cost Explorer and the
+    /// Alibaba Cloud BSS API are both limited to a few requests per second per account)
+    pub fn for_provider(provider: CloudProvider) -> Self {
+        match provider {
+            CloudProvider::AWS => Self {
+                burst: 5.0,
+                per_second: 5.0,
+            },
+            CloudProvider::Aliyun => Self {
+                burst: 5.0,
+                per_second: 5.0,
+            },
+            _ => Self {
+                burst: 2.0,
+                per_second: 2.0,
+            },
+        }
+    }
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    limit: RateLimit,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            tokens: limit.burst,
+            refilled_at: Instant::now(),
+        }
+    }
+
+    /// Take a token, or return how long to wait for the next one
+    fn try_take(&mut self) -> Result<(), Duration> {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.limit.per_second).min(self.limit.burst);
+        self.refilled_at = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64(
+                (1.0 - self.tokens) / self.limit.per_second,
+            ))
+        }
+    }
+}
+
+/// HTTP client with connection reuse, rate limiting and retries
+pub struct HttpClient {
+    agent: ureq::Agent,
+    policy: RetryPolicy,
+    limits: fn(CloudProvider) -> RateLimit,
+    /// Token buckets by provider and account
+    buckets: Mutex<HashMap<(CloudProvider, String), TokenBucket>>,
+}
+
+impl HttpClient {
+    pub fn new(
+        policy: RetryPolicy,
+        limits: fn(CloudProvider) -> RateLimit,
+        network: &NetworkConfig,
+    ) -> Result<Self> {
+        let mut builder = ureq::Agent::config_builder()
+            // 4xx/5xx bodies carry the provider's error code
+            .http_status_as_error(false)
+            .timeout_global(Some(REQUEST_TIMEOUT));
+
+        if let Some(proxy) = network.proxy.as_deref().filter(|p| !p.trim().is_empty()) {
+            let proxy = ureq::Proxy::new(proxy.trim())
+                .map_err(|e| anyhow!("Invalid proxy '{}': {}", redact::redact(proxy), e))?;
+            builder = builder.proxy(Some(proxy));
+        }
+
+        if let Some(path) = &network.ca_bundle {
+            let roots: Vec<ureq::tls::Certificate<'static>> = load_ca_bundle(path)?
+                .iter()
+                .map(|der| ureq::tls::Certificate::from_der(der.as_ref()).to_owned())
+                .collect();
+            builder = builder.tls_config(
+                ureq::tls::TlsConfig::builder()
+                    .root_certs(ureq::tls::RootCerts::new_with_certs(&roots))
+                    .build(),
+            );
+        }
+
+        Ok(Self {
+            agent: builder.build().new_agent(),
+            policy,
+            limits,
+            buckets: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Wait until the account's rate limit allows another request
+    fn acquire(&self, provider: CloudProvider, account: &str) {
+        loop {
+            let wait = {
+                let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+                let bucket = buckets
+                    .entry((provider, account.to_string()))
+                    .or_insert_with(|| TokenBucket::new((self.limits)(provider)));
+                match bucket.try_take() {
+                    Ok(()) => return,
+                    Err(wait) => wait,
+                }
+            };
+            std::thread::sleep(wait);
+        }
+    }
+
+    fn execute(&self, request: &HttpRequest) -> Result<HttpResponse, ureq::Error> {
+        let response = match &request.body {
+            Some(body) => {
+                let mut builder = self.agent.post(&request.url);
+                for (name, value) in &request.headers {
+                    builder = builder.header(name.as_str(), value.as_str());
+                }
+                builder.send(body.as_str())?
+            }
+            None => {
+                let mut builder = self.agent.get(&request.url);
+                for (name, value) in &request.headers {
+                    builder = builder.header(name.as_str(), value.as_str());
+                }
+                builder.call()?
+            }
+        };
+
+        let status = response.status().as_u16();
+        let retry_after = retry_after(response.headers());
+        let body = response.into_body().read_to_string()?;
+        Ok(HttpResponse {
+            status,
+            retry_after,
+            body,
+        })
+    }
+
+    /// Send a request once, without rate limiting or retries, for services other than
+    /// the cloud providers (e.g. a secret manager)
+    pub fn request(&self, request: &HttpRequest) -> Result<HttpResponse> {
+        self.execute(request)
+            .map_err(|e| anyhow!("{}", redact::redact(&e.to_string())))
+    }
+
+    /// Send a request built by `build` for `account`, retrying throttled responses and,
+    /// for idempotent requests, 5xx responses.
+    ///
+    /// `api_name` labels errors and logs. Transport failures are returned as
+    /// `ProviderError::Network`; error statuses are returned as responses for the
+    /// provider to classify.
+    pub fn send(
+        &self,
+        provider: CloudProvider,
+        account: &str,
+        api_name: &str,
+        build: impl Fn() -> Result<HttpRequest>,
+    ) -> Result<HttpResponse> {
+        let mut attempt = 0;
+        loop {
+            self.acquire(provider, account);
+            let request = build()?;
+            let response = self.execute(&request).map_err(|e| {
+                // ureq errors may include the (signed) URL
+                ProviderError::Network(format!(
+                    "{} request failed: {}",
+                    api_name,
+                    redact::redact(&e.to_string())
+                ))
+            })?;
+
+            attempt += 1;
+            if !is_retryable(&response, request.idempotent) || attempt >= self.policy.max_attempts {
+                return Ok(response);
+            }
+
+            let delay = match response.retry_after {
+                Some(delay) if delay > self.policy.max_retry_after => return Ok(response),
+                Some(delay) => delay,
+                None => self.policy.backoff(attempt - 1),
+            };
+            tracing::warn!(
+                "{} request returned HTTP {}, retrying in {:.1}s (attempt {} of {})",
+                api_name,
+                response.status,
+                delay.as_secs_f64(),
+                attempt + 1,
+                self.policy.max_attempts
+            );
+            std::thread::sleep(delay);
+        }
+    }
+}
+
+/// Delay from a `Retry-After` header
+fn retry_after(headers: &ureq::http::HeaderMap) -> Option<Duration> {
+    headers
+        .get("retry-after")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| parse_retry_after(value, chrono::Utc::now()))
+}
+
+/// `Retry-After` value in seconds or as an HTTP date (RFC 9110: IMF-fixdate, or the
+/// obsolete RFC 850 and asctime forms); dates in the past mean no delay
+fn parse_retry_after(value: &str, now: chrono::DateTime<chrono::Utc>) -> Option<Duration> {
+    use chrono::NaiveDateTime;
+
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = chrono::DateTime::parse_from_rfc2822(value)
+        .map(|date| date.naive_utc())
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%A, %d-%b-%y %H:%M:%S GMT"))
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%a %b %e %H:%M:%S %Y"))
+        .ok()?
+        .and_utc();
+    Some((date - now).to_std().unwrap_or(Duration::ZERO))
+}
+
+/// Whether a response is worth retrying: rate limited, or a server-side failure of a
+/// request that may be sent again
+fn is_retryable(response: &HttpResponse, idempotent: bool) -> bool {
+    match response.status {
+        429 => true,
+        s if s >= 500 => idempotent,
+        s if s >= 400 => error_code(&response.body).is_some_and(|code| is_throttling(&code)),
+        _ => false,
+    }
+}
+
+/// Error code of an error body: `__type`/`Code`/`code` (or `error.code`) in JSON,
+/// or the `<Code>` element in XML. AWS namespaces are removed.
+fn error_code(body: &str) -> Option<String> {
+    let code = match serde_json::from_str::<serde_json::Value>(body) {
+        Ok(json) => ["/__type", "/Code", "/code", "/error/code", "/error/type"]
+            .iter()
+            .find_map(|pointer| json.pointer(pointer).and_then(|v| v.as_str()))
+            .map(|code| code.to_string()),
+        Err(_) => {
+            let start = body.find("<Code>")? + "<Code>".len();
+            let end = start + body[start..].find("</Code>")?;
+            Some(body[start..end].trim().to_string())
+        }
+    }?;
+    Some(code.rsplit('#').next().unwrap_or_default().to_string())
+}
+
+fn is_throttling(code: &str) -> bool {
+    let base = code.split('.').next().unwrap_or_default();
+    THROTTLING_CODES.contains(&code) || THROTTLING_CODES.contains(&base)
+}
+
+/// The shared client and the network settings it was built with
+static CLIENT: Mutex<Option<(NetworkConfig, Arc<HttpClient>)>> = Mutex::new(None);
+
+/// The client shared by all providers, rebuilt when the network settings change.
+/// Invalid settings are reported on every call until they are fixed.
+pub fn client() -> Result<Arc<HttpClient>> {
+    let network = crate::config::current_config()
+        .map(|config| config.network.clone())
+        .unwrap_or_default();
+
+    let mut shared = CLIENT.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some((built_with, client)) = shared.as_ref() {
+        if *built_with == network {
+            return Ok(client.clone());
+        }
+    }
+
+    let client = Arc::new(
+        HttpClient::new(RetryPolicy::default(), RateLimit::for_provider, &network)
+            .context("Invalid network settings")?,
+    );
+    *shared = Some((network, client.clone()));
+    Ok(client)
+}
+
+/// Send a request for `account` with the shared client
+pub fn send(
+    provider: CloudProvider,
+    account: &str,
+    api_name: &str,
+    build: impl Fn() -> Result<HttpRequest>,
+) -> Result<HttpResponse> {
+    client()?.send(provider, account, api_name, build)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Local server answering requests with the given (status, extra headers, body)
+    /// responses in turn, repeating the last one; returns its URL and a request counter
+    fn throttling_server(
+        responses: Vec<(&'static str, &'static str, &'static str)>,
+    ) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let count = Arc::new(AtomicUsize::new(0));
+        let counter = count.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { continue };
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                loop {
+                    let mut line = String::new();
+                    if reader.read_line(&mut line).unwrap_or(0) == 0 || line.trim().is_empty() {
+                        break;
+                    }
+                }
+
+                let n = counter.fetch_add(1, Ordering::SeqCst);
+                let (status, headers, body) = responses[n.min(responses.len() - 1)];
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 {}\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    headers,
+                    body.len(),
+                    body
+                );
+            }
+        });
+        (url, count)
+    }
+
+    fn fast_policy() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 4,
+            base_delay: Duration::from_millis(10),
+            max_delay: Duration::from_millis(50),
+            max_retry_after: Duration::from_secs(5),
+        }
+    }
+
+    fn unlimited(_: CloudProvider) -> RateLimit {
+        RateLimit {
+            burst: 1000.0,
+            per_second: 1000.0,
+        }
+    }
+
+    #[test]
+    fn test_retries_throttled_and_failed_requests() {
+        let (url, count) = throttling_server(vec![
+            ("503 Service Unavailable", "", "busy"),
+            (
+                "400 Bad Request",
+                "",
+                r#"{"__type":"ThrottlingException","message":"Rate exceeded"}"#,
+            ),
+            ("200 OK", "", "ok"),
+        ]);
+        let client = HttpClient::new(fast_policy(), unlimited, &NetworkConfig::default()).unwrap();
+
+        let response = client
+            .send(CloudProvider::AWS, "acc", "Test", || {
+                Ok(HttpRequest::get(&url))
+            })
+            .unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, "ok");
+        assert_eq!(count.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_honors_retry_after() {
+        let (url, count) = throttling_server(vec![
+            ("429 Too Many Requests", "Retry-After: 1\r\n", ""),
+            ("200 OK", "", "ok"),
+        ]);
+        let client = HttpClient::new(fast_policy(), unlimited, &NetworkConfig::default()).unwrap();
+
+        let started = Instant::now();
+        let response = client
+            .send(CloudProvider::Aliyun, "acc", "Test", || {
+                Ok(HttpRequest::get(&url))
+            })
+            .unwrap();
+        assert_eq!(response.status, 200);
+        assert!(started.elapsed() >= Duration::from_secs(1));
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        let now = chrono::DateTime::parse_from_rfc3339("2015-10-21T07:28:00Z")
+            .unwrap()
+            .to_utc();
+        assert_eq!(
+            parse_retry_after(" 120 ", now),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:30 GMT", now),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            parse_retry_after("Wednesday, 21-Oct-15 07:29:00 GMT", now),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(
+            parse_retry_after("Wed Oct 21 07:28:05 2015", now),
+            Some(Duration::from_secs(5))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:27:00 GMT", now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+
+    #[test]
+    fn test_gives_up_after_max_attempts() {
+        let (url, count) = throttling_server(vec![("429 Too Many Requests", "", "slow down")]);
+        let client = HttpClient::new(fast_policy(), unlimited, &NetworkConfig::default()).unwrap();
+
+        let response = client
+            .send(CloudProvider::DeepSeek, "acc", "Test", || {
+                Ok(HttpRequest::get(&url))
+            })
+            .unwrap();
+        assert_eq!(response.status, 429);
+        assert_eq!(count.load(Ordering::SeqCst), 4);
+
+        // Client errors other than throttling are not retried
+        let (url, count) = throttling_server(vec![("403 Forbidden", "", "AccessDenied")]);
+        let response = client
+            .send(CloudProvider::AWS, "acc", "Test", || {
+                Ok(HttpRequest::get(&url))
+            })
+            .unwrap();
+        assert_eq!(response.status, 403);
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+
+        // A Retry-After beyond the policy's limit is left to the caller
+        let (url, count) =
+            throttling_server(vec![("429 Too Many Requests", "Retry-After: 3600\r\n", "")]);
+        let response = client
+            .send(CloudProvider::AWS, "acc", "Test", || {
+                Ok(HttpRequest::get(&url))
+            })
+            .unwrap();
+        assert_eq!(response.retry_after, Some(Duration::from_secs(3600)));
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_rate_limit_per_account() {
+        fn limits(provider: CloudProvider) -> RateLimit {
+            match provider {
+                CloudProvider::AWS => RateLimit {
+                    burst: 2.0,
+                    per_second: 10.0,
+                },
+                _ => RateLimit {
+                    burst: 1000.0,
+                    per_second: 1000.0,
+                },
+            }
+        }
+        let (url, count) = throttling_server(vec![("200 OK", "", "ok")]);
+        let client = HttpClient::new(fast_policy(), limits, &NetworkConfig::default()).unwrap();
+
+        // 2 requests from the burst, 4 more at 10 per second
+        let started = Instant::now();
+        for _ in 0..6 {
+            client
+                .send(CloudProvider::AWS, "acc", "Test", || {
+                    Ok(HttpRequest::get(&url))
+                })
+                .unwrap();
+        }
+        assert!(started.elapsed() >= Duration::from_millis(350));
+
+        // Other accounts of the provider have their own bucket
+        let started = Instant::now();
+        for _ in 0..2 {
+            client
+                .send(CloudProvider::AWS, "other", "Test", || {
+                    Ok(HttpRequest::get(&url))
+                })
+                .unwrap();
+        }
+        assert!(started.elapsed() < Duration::from_millis(90));
+
+        // Other providers have their own limits
+        let started = Instant::now();
+        for _ in 0..6 {
+            client
+                .send(CloudProvider::Aliyun, "acc", "Test", || {
+                    Ok(HttpRequest::get(&url))
+                })
+                .unwrap();
+        }
+        assert!(started.elapsed() < Duration::from_millis(350));
+        assert_eq!(count.load(Ordering::SeqCst), 14);
+    }
+
+    #[test]
+    fn test_writes_are_not_retried_after_server_errors() {
+        let client = HttpClient::new(fast_policy(), unlimited, &NetworkConfig::default()).unwrap();
+
+        let (url, count) = throttling_server(vec![("503 Service Unavailable", "", "busy")]);
+        let response = client
+            .send(CloudProvider::AWS, "acc", "Test", || {
+                Ok(HttpRequest::post(&url, "{}"))
+            })
+            .unwrap();
+        assert_eq!(response.status, 503);
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+
+        // Throttled requests were not processed, so writes are retried too
+        let (url, count) = throttling_server(vec![
+            ("429 Too Many Requests", "", ""),
+            ("200 OK", "", "ok"),
+        ]);
+        let response = client
+            .send(CloudProvider::AWS, "acc", "Test", || {
+                Ok(HttpRequest::post(&url, "{}"))
+            })
+            .unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+
+        // Reads sent as POST can opt in
+        let (url, count) = throttling_server(vec![
+            ("500 Internal Server Error", "", ""),
+            ("200 OK", "", "ok"),
+        ]);
+        let response = client
+            .send(CloudProvider::AWS, "acc", "Test", || {
+                Ok(HttpRequest::post(&url, "{}").idempotent(true))
+            })
+            .unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_throttling_error_codes() {
+        for body in [
+            r#"{"__type":"com.amazonaws.awsinsightsindexservice#LimitExceededException"}"#,
+            r#"{"Code":"Throttling.User","Message":"Request was denied due to user flow control."}"#,
+            "<ErrorResponse><Error><Code>Throttling</Code></Error></ErrorResponse>",
+        ] {
+            assert!(
+                error_code(body).is_some_and(|code| is_throttling(&code)),
+                "{}",
+                body
+            );
+        }
+
+        // A message mentioning throttling is not a throttling error
+        let denied = r#"{"__type":"AccessDeniedException","Message":"Not authorized to change Throttling settings"}"#;
+        assert_eq!(error_code(denied).as_deref(), Some("AccessDeniedException"));
+        assert!(!is_throttling("AccessDeniedException"));
+        assert!(error_code("Service Unavailable").is_none());
+    }
+
+    #[test]
+    fn test_transport_errors_are_network_errors() {
+        // Nothing listens on the port once the listener is dropped
+        let url = {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            format!("http://{}/", listener.local_addr().unwrap())
+        };
+        let client = HttpClient::new(fast_policy(), unlimited, &NetworkConfig::default()).unwrap();
+        let error = client
+            .send(CloudProvider::AWS, "acc", "Test", || {
+                Ok(HttpRequest::get(&url))
+            })
+            .unwrap_err();
+        assert!(matches!(
+            ProviderError::find(&error),
+            Some(ProviderError::Network(_))
+        ));
+    }
+
+    #[test]
+    fn test_requests_go_through_proxy() {
+        // Records the first request line the proxy receives and refuses it
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let proxy = format!("http://{}", listener.local_addr().unwrap());
+        let (tx, rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            if let Some(Ok(mut stream)) = listener.incoming().next() {
+                let mut line = String::new();
+                BufReader::new(stream.try_clone().unwrap())
+                    .read_line(&mut line)
+                    .unwrap();
+                let _ = tx.send(line);
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 502 Bad Gateway\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                );
+            }
+        });
+
+        let network = NetworkConfig {
+            proxy: Some(proxy),
+            ..Default::default()
+        };
+        let client = HttpClient::new(fast_policy(), unlimited, &network).unwrap();
+        let _ = client.send(CloudProvider::AWS, "acc", "Test", || {
+            Ok(HttpRequest::get("http://ce.example.invalid/"))
+        });
+        let line = rx.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert!(line.contains("ce.example.invalid"), "{}", line);
+
+        let network = NetworkConfig {
+            proxy: Some("not a proxy url".to_string()),
+            ..Default::default()
+        };
+        assert!(HttpClient::new(fast_policy(), unlimited, &network).is_err());
+    }
+
+    #[test]
+    fn test_ca_bundle_errors() {
+        let dir = crate::config::scratch_dir("ca").unwrap();
+        let empty = dir.join("empty.pem");
+        std::fs::write(&empty, "no certificates here\n").unwrap();
+
+        let network = NetworkConfig {
+            ca_bundle: Some(empty.clone()),
+            ..Default::default()
+        };
+        let error = HttpClient::new(fast_policy(), unlimited, &network)
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("No certificates"));
+        assert!(load_ca_bundle(&dir.join("missing.pem")).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_endpoint_overrides() {
+        let overrides: BTreeMap<String, String> = [
+            ("ce", "https://ce.cn-northwest-1.amazonaws.com.cn/"),
+            ("sts", "http://127.0.0.1:4566"),
+            ("bss", "http://127.0.0.1:4566/bss"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+
+        let ce = Endpoint::resolve(&overrides, "ce", "ce.us-east-1.amazonaws.com").unwrap();
+        assert_eq!(ce.host, "ce.cn-northwest-1.amazonaws.com.cn");
+        assert_eq!(ce.url("/"), "https://ce.cn-northwest-1.amazonaws.com.cn/");
+
+        let sts = Endpoint::resolve(&overrides, "sts", "sts.amazonaws.com").unwrap();
+        assert_eq!(sts.host, "127.0.0.1:4566");
+        assert_eq!(sts.url("/?Action=X"), "http://127.0.0.1:4566/?Action=X");
+
+        let budgets = Endpoint::resolve(&overrides, "budgets", "budgets.amazonaws.com").unwrap();
+        assert_eq!(budgets, Endpoint::https("budgets.amazonaws.com"));
+
+        assert!(Endpoint::resolve(&overrides, "bss", "business.aliyuncs.com").is_err());
+        assert!(Endpoint::parse("ftp://example.com").is_err());
+    }
+}
@@ -3,13 +3,20 @@
 pub mod aliyun;
 pub mod aws;
 pub mod deepseek;
+pub mod focus;
+pub mod http;
 
-use anyhow::Result;
+pub use focus::FocusCost;
+
+use anyhow::{anyhow, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Duration;
 
 /// Cloud provider type
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
 #[allow(clippy::upper_case_acronyms)]
 pub enum CloudProvider {
     #[default]
@@ -81,6 +88,112 @@ pub struct CostData {
     pub amount: f64,
     /// Currency
     pub currency: String,
+    /// Cost allocation dimensions of this record (dimension key -> value),
+    /// e.g. `REGION -> us-east-1` or `TAG:team -> platform`
+    #[serde(default)]
+    pub dimensions: BTreeMap<String, String>,
+}
+
+/// Value used when a cost record has no value for the requested dimension
+pub const UNTAGGED_VALUE: &str = "(untagged)";
+
+/// Cost allocation dimension used to group a cost breakdown
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+pub enum CostDimension {
+    #[default]
+    Service,
+    Region,
+    UsageType,
+    InstanceType,
+    /// Aliyun resource group
+    ResourceGroup,
+    /// Cost allocation tag key (e.g. `team`, `env`)
+    Tag(String),
+}
+
+impl CostDimension {
+    /// Stable key used for storage and in `CostData::dimensions`
+    pub fn key(&self) -> String {
+        match self {
+            CostDimension::Service => "SERVICE".to_string(),
+            CostDimension::Region => "REGION".to_string(),
+            CostDimension::UsageType => "USAGE_TYPE".to_string(),
+            CostDimension::InstanceType => "INSTANCE_TYPE".to_string(),
+            CostDimension::ResourceGroup => "RESOURCE_GROUP".to_string(),
+            CostDimension::Tag(key) => format!("TAG:{}", key),
+        }
+    }
+
+    /// Label for display
+    pub fn label(&self) -> String {
+        match self {
+            CostDimension::Service => "Service".to_string(),
+            CostDimension::Region => "Region".to_string(),
+            CostDimension::UsageType => "Usage Type".to_string(),
+            CostDimension::InstanceType => "Instance Type".to_string(),
+            CostDimension::ResourceGroup => "Resource Group".to_string(),
+            CostDimension::Tag(key) => format!("Tag: {}", key),
+        }
+    }
+
+    /// Dimensions a provider can group by, with the configured tag keys appended
+    pub fn available_for(provider: CloudProvider, tag_keys: &[String]) -> Vec<CostDimension> {
+        let mut dimensions = match provider {
+            CloudProvider::AWS => vec![
+                CostDimension::Service,
+                CostDimension::Region,
+                CostDimension::UsageType,
+                CostDimension::InstanceType,
+            ],
+            CloudProvider::Aliyun => vec![
+                CostDimension::Service,
+                CostDimension::Region,
+                CostDimension::UsageType,
+                CostDimension::InstanceType,
+                CostDimension::ResourceGroup,
+            ],
+            _ => return vec![CostDimension::Service],
+        };
+        dimensions.extend(tag_keys.iter().map(|k| CostDimension::Tag(k.clone())));
+        dimensions
+    }
+}
+
+/// Aggregate cost records by the value of a dimension, sorted by amount descending.
+/// Records in different currencies are kept in separate groups rather than summed.
+pub fn group_costs_by_dimension(costs: &[CostData], dimension: &CostDimension) -> Vec<ServiceCost> {
+    let key = dimension.key();
+    let mut groups: BTreeMap<(String, String), f64> = BTreeMap::new();
+
+    for cost in costs {
+        let value = match dimension {
+            CostDimension::Service => cost.service.clone(),
+            _ => cost
+                .dimensions
+                .get(&key)
+                .filter(|v| !v.is_empty())
+                .cloned()
+                .unwrap_or_else(|| UNTAGGED_VALUE.to_string()),
+        };
+        *groups.entry((value, cost.currency.clone())).or_insert(0.0) += cost.amount;
+    }
+
+    let mut result: Vec<ServiceCost> = groups
+        .into_iter()
+        .map(|((service, currency), amount)| ServiceCost {
+            service,
+            amount,
+            currency,
+        })
+        .collect();
+
+    result.sort_by(|a, b| {
+        b.amount
+            .partial_cmp(&a.amount)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    result
 }
 
 /// Cost summary
@@ -137,6 +250,132 @@ pub struct CostTrend {
     pub daily_costs: Vec<DailyCost>,
 }
 
+/// Commitment (Savings Plans / Reserved Instances) utilization and coverage
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CommitmentUtilization {
+    /// Share of the purchased commitment that was used (percentage)
+    pub utilization_percentage: f64,
+    /// Share of eligible usage covered by the commitment (percentage)
+    pub coverage_percentage: f64,
+    /// Total commitment cost in the period
+    pub total_commitment: f64,
+    /// Commitment cost that went unused
+    pub unused_commitment: f64,
+    /// Eligible on-demand spend not covered by the commitment
+    pub on_demand_not_covered: f64,
+    /// Net savings compared to on-demand pricing
+    pub net_savings: f64,
+}
+
+/// Commitment summary for an account
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommitmentSummary {
+    /// Account ID
+    pub account_id: String,
+    /// Period start (YYYY-MM-DD)
+    pub start_date: String,
+    /// Period end (YYYY-MM-DD, exclusive)
+    pub end_date: String,
+    /// Currency
+    pub currency: String,
+    /// Savings Plans (None if the account has no Savings Plans)
+    pub savings_plans: Option<CommitmentUtilization>,
+    /// Reserved Instances (None if the account has no reservations)
+    pub reservations: Option<CommitmentUtilization>,
+}
+
+/// Cost anomaly detected by a provider-side anomaly monitor (AWS Cost Anomaly Detection)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CostAnomaly {
+    /// Provider anomaly ID
+    pub anomaly_id: String,
+    /// Monitor that detected the anomaly
+    pub monitor_arn: String,
+    /// Monitor display name (if known)
+    pub monitor_name: Option<String>,
+    /// Anomaly start date (YYYY-MM-DD)
+    pub start_date: String,
+    /// Anomaly end date (None while the anomaly is still ongoing)
+    pub end_date: Option<String>,
+    /// Total cost impact (actual minus expected spend)
+    pub total_impact: f64,
+    /// Maximum daily cost impact
+    pub max_impact: f64,
+    /// Total actual spend during the anomaly
+    pub actual_spend: f64,
+    /// Total expected spend during the anomaly
+    pub expected_spend: f64,
+    /// Currency
+    pub currency: String,
+    /// Root causes ranked by the provider
+    pub root_causes: Vec<AnomalyRootCause>,
+    /// Feedback already given for this anomaly
+    pub feedback: Option<AnomalyFeedback>,
+}
+
+/// Root cause of a cost anomaly
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AnomalyRootCause {
+    pub service: Option<String>,
+    pub region: Option<String>,
+    pub usage_type: Option<String>,
+    pub linked_account: Option<String>,
+}
+
+impl AnomalyRootCause {
+    /// Human-readable description, e.g. "Amazon EC2 / us-east-1 / BoxUsage:m5.large"
+    pub fn describe(&self) -> String {
+        let parts: Vec<&str> = [&self.service, &self.region, &self.usage_type]
+            .into_iter()
+            .filter_map(|p| p.as_deref())
+            .collect();
+        if parts.is_empty() {
+            "Unknown".to_string()
+        } else {
+            parts.join(" / ")
+        }
+    }
+}
+
+/// Feedback on whether a detected anomaly is a real issue
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AnomalyFeedback {
+    /// Confirmed unexpected spend
+    Yes,
+    /// Not an anomaly
+    No,
+    /// Expected spend from planned activity
+    PlannedActivity,
+}
+
+impl AnomalyFeedback {
+    /// Value used by the AWS API
+    pub fn key(&self) -> &'static str {
+        match self {
+            AnomalyFeedback::Yes => "YES",
+            AnomalyFeedback::No => "NO",
+            AnomalyFeedback::PlannedActivity => "PLANNED_ACTIVITY",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "YES" => Some(AnomalyFeedback::Yes),
+            "NO" => Some(AnomalyFeedback::No),
+            "PLANNED_ACTIVITY" => Some(AnomalyFeedback::PlannedActivity),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            AnomalyFeedback::Yes => "Confirmed",
+            AnomalyFeedback::No => "Not an issue",
+            AnomalyFeedback::PlannedActivity => "Planned",
+        }
+    }
+}
+
 /// Budget information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BudgetInfo {
@@ -148,12 +387,52 @@ pub struct BudgetInfo {
     pub currency: String,
     /// Alert threshold (percentage, e.g., 80.0 for 80%)
     pub alert_threshold: f64,
+    /// Where the budget is maintained
+    #[serde(default)]
+    pub source: BudgetSource,
+    /// Name of the linked budget in the provider (e.g. the AWS Budgets budget name)
+    #[serde(default)]
+    pub external_name: Option<String>,
     /// Created time
     pub created_at: DateTime<Utc>,
     /// Updated time
     pub updated_at: DateTime<Utc>,
 }
 
+/// Source of truth for a budget
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum BudgetSource {
+    /// Maintained in CloudBridge (optionally pushed to the provider)
+    #[default]
+    Local,
+    /// Imported from AWS Budgets, edit it in the AWS console
+    AwsBudgets,
+}
+
+impl BudgetSource {
+    /// Stable key stored in the database
+    pub fn key(&self) -> &'static str {
+        match self {
+            BudgetSource::Local => "local",
+            BudgetSource::AwsBudgets => "aws_budgets",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Self {
+        match key {
+            "aws_budgets" => BudgetSource::AwsBudgets,
+            _ => BudgetSource::Local,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            BudgetSource::Local => "CloudBridge",
+            BudgetSource::AwsBudgets => "AWS Budgets",
+        }
+    }
+}
+
 /// Budget status (comparison of budget vs actual)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BudgetStatus {
@@ -175,6 +454,133 @@ pub struct BudgetStatus {
     pub alert_triggered: bool,
 }
 
+impl BudgetStatus {
+    /// Compare a budget against the cost of its month
+    pub fn from_budget(budget: &BudgetInfo, account_name: &str, current_cost: f64) -> Self {
+        let percentage_used = if budget.monthly_budget > 0.0 {
+            (current_cost / budget.monthly_budget) * 100.0
+        } else {
+            0.0
+        };
+
+        BudgetStatus {
+            account_id: budget.account_id.clone(),
+            account_name: account_name.to_string(),
+            monthly_budget: budget.monthly_budget,
+            current_cost,
+            currency: budget.currency.clone(),
+            percentage_used,
+            remaining: budget.monthly_budget - current_cost,
+            alert_triggered: percentage_used >= budget.alert_threshold,
+        }
+    }
+}
+
+/// Permission policies for each provider, referenced by permission errors
+pub const POLICIES_DOC_PATH: &str = "docs/policies.md";
+pub const POLICIES_DOC_URL: &str =
+    "https://github.com/JetSquirrel/cloudbridge/blob/main/docs/policies.md";
+
+/// Classified provider failure
+///
+/// Provider clients return these (inside `anyhow::Error`) for failed API calls so the
+/// UI can tell a missing permission from a bad key or a rate limit and suggest a fix.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProviderError {
+    /// Credentials rejected: unknown or inactive key, bad signature, expired token
+    AuthFailed(String),
+    /// Credentials are valid but lack a permission, e.g. `ce:GetCostAndUsage`
+    PermissionDenied {
+        action: Option<String>,
+        message: String,
+    },
+    /// Rate limited; the delay is set when the provider sends one
+    Throttled(Option<Duration>),
+    /// The provider has no data for the request yet (billing data is still being
+    /// prepared, or the feature has nothing to report)
+    DataNotReady(String),
+    /// The request got no response (DNS, TLS, timeout, connection reset)
+    Network(String),
+    /// Any other failure
+    Unexpected(String),
+}
+
+impl ProviderError {
+    /// The provider error in an error's cause chain, if any
+    pub fn find(error: &anyhow::Error) -> Option<&ProviderError> {
+        error
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<ProviderError>())
+    }
+
+    /// Suggested fix to show next to the error
+    pub fn guidance(&self) -> Option<String> {
+        match self {
+            ProviderError::AuthFailed(_) => Some(
+                "Check the access key and secret; rotate them if they were deactivated".to_string(),
+            ),
+            ProviderError::PermissionDenied {
+                action: Some(action),
+                ..
+            } => Some(format!(
+                "Grant {} to these credentials (see {})",
+                action, POLICIES_DOC_PATH
+            )),
+            ProviderError::PermissionDenied { action: None, .. } => Some(format!(
+                "Grant the permissions listed in {}",
+                POLICIES_DOC_PATH
+            )),
+            ProviderError::Throttled(Some(delay)) => Some(format!(
+                "The provider is rate limiting requests; try again in {}s",
+                delay.as_secs().max(1)
+            )),
+            ProviderError::Throttled(None) => {
+                Some("The provider is rate limiting requests; try again shortly".to_string())
+            }
+            ProviderError::DataNotReady(_) => {
+                Some("Billing data can take up to 24 hours to appear; try again later".to_string())
+            }
+            ProviderError::Network(_) => {
+                Some("Check the network connection and proxy settings".to_string())
+            }
+            ProviderError::Unexpected(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for ProviderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProviderError::AuthFailed(message) => write!(f, "Authentication failed: {}", message),
+            ProviderError::PermissionDenied { message, .. } => {
+                write!(f, "Permission denied: {}", message)
+            }
+            ProviderError::Throttled(Some(delay)) => write!(
+                f,
+                "Request throttled (retry after {}s)",
+                delay.as_secs().max(1)
+            ),
+            ProviderError::Throttled(None) => write!(f, "Request throttled"),
+            ProviderError::DataNotReady(message) => {
+                write!(f, "Data not available yet: {}", message)
+            }
+            ProviderError::Network(message) => write!(f, "Network error: {}", message),
+            ProviderError::Unexpected(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for ProviderError {}
+
+/// Error message for display, followed by the suggested fix for provider errors
+pub fn describe_error(error: &anyhow::Error) -> String {
+    let message = crate::redact::error_message(error);
+    match ProviderError::find(error).and_then(|e| e.guidance()) {
+        Some(guidance) => format!("{}. {}", message.trim_end_matches('.'), guidance),
+        None => message,
+    }
+}
+
 /// Cloud service provider trait (sync version, using ureq)
 pub trait CloudService: Send + Sync {
     /// Validate credentials
@@ -188,4 +594,251 @@ pub trait CloudService: Send + Sync {
 
     /// Get cost trend (daily costs)
     fn get_cost_trend(&self, start_date: &str, end_date: &str) -> Result<CostTrend>;
+
+    /// Get cost data grouped by a cost allocation dimension (region, tag, ...)
+    fn get_cost_breakdown(
+        &self,
+        start_date: &str,
+        end_date: &str,
+        dimension: &CostDimension,
+    ) -> Result<Vec<CostData>>;
+
+    /// Get costs as FOCUS-aligned records
+    fn get_focus_costs(&self, start_date: &str, end_date: &str) -> Result<Vec<FocusCost>>;
+}
+
+/// Create the provider service for an account
+pub fn service_for_account(account: &CloudAccount) -> Result<Box<dyn CloudService>> {
+    let account = &crate::secret_store::resolve_credentials(account)?;
+    let id = account.id.clone();
+    let name = account.name.clone();
+    let ak = account.access_key_id.clone();
+    let sk = account.secret_access_key.clone();
+    let region = account.region.clone();
+
+    match account.provider {
+        CloudProvider::AWS => Ok(Box::new(aws::AwsCloudService::new(
+            id, name, ak, sk, region,
+        ))),
+        CloudProvider::Aliyun => Ok(Box::new(aliyun::AliyunCloudService::for_account(account))),
+        CloudProvider::DeepSeek => Ok(Box::new(deepseek::DeepSeekService::new(
+            id, name, ak, sk, region,
+        ))),
+        other => Err(anyhow!("{} is not supported yet", other.display_name())),
+    }
+}
+
+/// Separates the payer account ID from the member UID in sub-account IDs
+const MEMBER_ID_SEPARATOR: char = '#';
+
+/// ID of the virtual sub-account of a member billed to `parent_id`
+pub fn member_account_id(parent_id: &str, member_id: &str) -> String {
+    format!("{}{}{}", parent_id, MEMBER_ID_SEPARATOR, member_id)
+}
+
+/// Display name of a member sub-account
+pub fn member_account_name(parent_name: &str, member_name: &str) -> String {
+    format!("{} / {}", parent_name, member_name)
+}
+
+/// Split an account ID into the stored account's ID and the member UID of a sub-account
+pub fn split_member_account_id(id: &str) -> (&str, Option<&str>) {
+    match id.split_once(MEMBER_ID_SEPARATOR) {
+        Some((parent_id, member_id)) => (parent_id, Some(member_id)),
+        None => (id, None),
+    }
+}
+
+/// Account by ID; member sub-accounts resolve to their payer account with the sub-account ID
+pub fn find_account(accounts: Vec<CloudAccount>, id: &str) -> Option<CloudAccount> {
+    let (parent_id, member_id) = split_member_account_id(id);
+    let mut account = accounts.into_iter().find(|a| a.id == parent_id)?;
+    if member_id.is_some() {
+        account.id = id.to_string();
+    }
+    Some(account)
+}
+
+/// Member billing of an Alibaba Cloud payer account (`aliyun_member_billing`)
+pub fn member_billing_for(
+    account: &CloudAccount,
+    config: &crate::config::AppConfig,
+) -> Option<crate::config::AliyunMemberBilling> {
+    if account.provider != CloudProvider::Aliyun {
+        return None;
+    }
+    config.aliyun_member_billing_for(&account.id, &account.name)
+}
+
+/// Cached summaries of the members of a payer account, if all are cached. Without a
+/// configured member list, the members found by the last bill query are used.
+pub fn cached_member_summaries(
+    account: &CloudAccount,
+    billing: &crate::config::AliyunMemberBilling,
+) -> Option<Vec<CostSummary>> {
+    let members = if billing.members.is_empty() {
+        crate::db::get_discovered_members(&account.id, &account.provider)
+            .ok()
+            .flatten()?
+    } else {
+        billing
+            .members
+            .iter()
+            .map(|member| {
+                (
+                    member_account_id(&account.id, &member.id),
+                    member_account_name(&account.name, member.label()),
+                )
+            })
+            .collect()
+    };
+    members
+        .iter()
+        .map(|(id, name)| {
+            crate::db::get_cached_cost_summary_with_account(id, name, &account.provider)
+                .ok()
+                .flatten()
+        })
+        .collect()
+}
+
+/// Cost summaries shown for an account: one per member for payer accounts with member
+/// billing, otherwise the account's own
+pub fn fetch_cost_summaries(
+    account: &CloudAccount,
+    config: &crate::config::AppConfig,
+) -> Result<Vec<CostSummary>> {
+    match member_billing_for(account, config) {
+        Some(billing) => {
+            let account = crate::secret_store::resolve_credentials(account)?;
+            let summaries = aliyun::AliyunCloudService::for_account(&account)
+                .get_member_cost_summaries(&billing)?;
+            if billing.members.is_empty() {
+                let members: Vec<(String, String)> = summaries
+                    .iter()
+                    .map(|s| (s.account_id.clone(), s.account_name.clone()))
+                    .collect();
+                if let Err(e) = crate::db::save_discovered_members(&account.id, &members) {
+                    tracing::warn!("Failed to cache members of {}: {}", account.name, e);
+                }
+            }
+            Ok(summaries)
+        }
+        None => Ok(vec![service_for_account(account)?.get_cost_summary()?]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cost(service: &str, dims: &[(&str, &str)], amount: f64) -> CostData {
+        CostData {
+            account_id: "acc".to_string(),
+            date: "2024-05-01".to_string(),
+            service: service.to_string(),
+            amount,
+            currency: "USD".to_string(),
+            dimensions: dims
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_provider_error_guidance() {
+        let denied = ProviderError::PermissionDenied {
+            action: Some("ce:GetCostAndUsage".to_string()),
+            message: "not authorized".to_string(),
+        };
+        assert!(denied
+            .guidance()
+            .unwrap()
+            .starts_with("Grant ce:GetCostAndUsage"));
+
+        // Found behind added context, with the guidance appended
+        let error = anyhow::Error::new(denied.clone()).context("Failed to get cost data");
+        assert_eq!(ProviderError::find(&error), Some(&denied));
+        let message = describe_error(&error);
+        assert!(message.starts_with("Failed to get cost data: Permission denied: not authorized"));
+        assert!(message
+            .ends_with("Grant ce:GetCostAndUsage to these credentials (see docs/policies.md)"));
+
+        let throttled = ProviderError::Throttled(Some(Duration::from_secs(20)));
+        assert_eq!(throttled.to_string(), "Request throttled (retry after 20s)");
+        assert!(throttled.guidance().unwrap().ends_with("try again in 20s"));
+
+        assert_eq!(describe_error(&anyhow!("plain failure")), "plain failure");
+    }
+
+    #[test]
+    fn test_member_accounts() {
+        let id = member_account_id("acc-1", "1234567890123457");
+        assert_eq!(id, "acc-1#1234567890123457");
+        assert_eq!(
+            split_member_account_id(&id),
+            ("acc-1", Some("1234567890123457"))
+        );
+        assert_eq!(split_member_account_id("acc-1"), ("acc-1", None));
+
+        let account = CloudAccount {
+            id: "acc-1".to_string(),
+            name: "Finance".to_string(),
+            provider: CloudProvider::Aliyun,
+            access_key_id: String::new(),
+            secret_access_key: String::new(),
+            region: None,
+            created_at: Utc::now(),
+            last_synced_at: None,
+            enabled: true,
+        };
+        let member = find_account(vec![account.clone()], &id).unwrap();
+        assert_eq!(member.id, id);
+        assert_eq!(member.name, "Finance");
+        assert_eq!(
+            find_account(vec![account.clone()], "acc-1").unwrap().id,
+            "acc-1"
+        );
+        assert!(find_account(vec![account], "acc-2#1").is_none());
+    }
+
+    #[test]
+    fn test_group_costs_by_tag() {
+        let costs = vec![
+            cost("EC2", &[("TAG:team", "platform")], 10.0),
+            cost("S3", &[("TAG:team", "platform")], 5.0),
+            cost("EC2", &[("TAG:team", "data")], 20.0),
+            cost("RDS", &[], 1.0),
+        ];
+
+        let groups = group_costs_by_dimension(&costs, &CostDimension::Tag("team".to_string()));
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups[0].service, "data");
+        assert_eq!(groups[1].service, "platform");
+        assert_eq!(groups[1].amount, 15.0);
+        assert_eq!(groups[2].service, UNTAGGED_VALUE);
+
+        let by_service = group_costs_by_dimension(&costs, &CostDimension::Service);
+        assert_eq!(by_service[0].service, "EC2");
+        assert_eq!(by_service[0].amount, 30.0);
+    }
+
+    #[test]
+    fn test_group_costs_keeps_currencies_apart() {
+        let mut cny = cost("ECS", &[], 70.0);
+        cny.currency = "CNY".to_string();
+        let costs = vec![cost("ECS", &[], 10.0), cny];
+
+        let groups = group_costs_by_dimension(&costs, &CostDimension::Service);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(
+            (groups[0].currency.as_str(), groups[0].amount),
+            ("CNY", 70.0)
+        );
+        assert_eq!(
+            (groups[1].currency.as_str(), groups[1].amount),
+            ("USD", 10.0)
+        );
+    }
 }
@@ -5,11 +5,127 @@ use aes_gcm::{
     Aes256Gcm, Nonce,
 };
 use anyhow::{anyhow, Result};
+use argon2::{Algorithm, Argon2, Params, Version};
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+use crate::config::{load_config, save_config, MasterKeyConfig};
 
 const NONCE_SIZE: usize = 12;
 const KEY_SIZE: usize = 32;
+const SALT_SIZE: usize = 16;
+
+/// Shortest accepted passphrase
+pub const MIN_PASSPHRASE_LEN: usize = 8;
+
+/// Known plaintext encrypted with the master key, to check a passphrase on unlock
+const VERIFIER_TEXT: &str = "cloudbridge-master-key";
+
+lazy_static::lazy_static! {
+    /// Key derived from the master passphrase while the app is unlocked
+    static ref SESSION_KEY: Mutex<Option<[u8; KEY_SIZE]>> = Mutex::new(None);
+    /// Master passphrase mode as saved in config.json; None until first read
+    static ref MASTER_STATE: Mutex<Option<MasterState>> = Mutex::new(None);
+}
+
+/// Whether master passphrase mode is on, kept in memory for the lock checks
+#[derive(Debug, Clone, Copy)]
+struct MasterState {
+    enabled: bool,
+    auto_lock_minutes: u32,
+}
+
+impl MasterState {
+    fn from_config(master: Option<&MasterKeyConfig>) -> Self {
+        Self {
+            enabled: master.is_some(),
+            auto_lock_minutes: master.map_or(0, |m| m.auto_lock_minutes),
+        }
+    }
+}
+
+/// Master passphrase mode, read from config.json on first use
+fn master_state() -> MasterState {
+    let Ok(mut state) = MASTER_STATE.lock() else {
+        return MasterState::from_config(None);
+    };
+    if let Some(state) = *state {
+        return state;
+    }
+    match load_config() {
+        Ok(config) => *state.insert(MasterState::from_config(config.master_key.as_ref())),
+        // Not cached, so the next check reads the config again
+        Err(_) => MasterState::from_config(None),
+    }
+}
+
+/// Record the master passphrase settings just saved to config.json
+pub(crate) fn set_master_state(master: Option<&MasterKeyConfig>) {
+    if let Ok(mut state) = MASTER_STATE.lock() {
+        *state = Some(MasterState::from_config(master));
+    }
+}
+
+/// Idle minutes after which the app locks; 0 without master passphrase mode or auto-lock
+pub fn auto_lock_minutes() -> u32 {
+    master_state().auto_lock_minutes
+}
+
+/// Reject passphrases that are too short
+pub fn check_passphrase(passphrase: &str) -> Result<()> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+        return Err(anyhow!(
+            "Passphrase must be at least {} characters",
+            MIN_PASSPHRASE_LEN
+        ));
+    }
+    Ok(())
+}
+
+/// Argon2id parameters and salt for deriving a key from a passphrase.
+/// Stored next to the data it protects; none of it is secret.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfParams {
+    /// Base64 salt
+    pub salt: String,
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl KdfParams {
+    /// Fresh random salt with the OWASP recommended Argon2id cost (19 MiB, 2 passes)
+    pub fn generate() -> Self {
+        let mut salt = [0u8; SALT_SIZE];
+        OsRng.fill_bytes(&mut salt);
+        Self {
+            salt: BASE64.encode(salt),
+            memory_kib: 19 * 1024,
+            iterations: 2,
+            parallelism: 1,
+        }
+    }
+
+    /// Derive the encryption key for `passphrase`
+    pub fn derive_key(&self, passphrase: &str) -> Result<[u8; KEY_SIZE]> {
+        let salt = BASE64.decode(&self.salt)?;
+        let params = Params::new(
+            self.memory_kib,
+            self.iterations,
+            self.parallelism,
+            Some(KEY_SIZE),
+        )
+        .map_err(|e| anyhow!("Invalid key derivation parameters: {}", e))?;
+
+        let mut key = [0u8; KEY_SIZE];
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+            .map_err(|e| anyhow!("Key derivation failed: {}", e))?;
+        Ok(key)
+    }
+}
 
 /// Encryption manager
 pub struct CryptoManager {
@@ -23,6 +139,11 @@ impl CryptoManager {
         Self { cipher }
     }
 
+    /// Create encryption manager with a key derived from a passphrase
+    pub fn from_passphrase(passphrase: &str, kdf: &KdfParams) -> Result<Self> {
+        Ok(Self::new(&kdf.derive_key(passphrase)?))
+    }
+
     /// Generate new encryption key
     pub fn generate_key() -> [u8; KEY_SIZE] {
         let mut key = [0u8; KEY_SIZE];
@@ -84,12 +205,76 @@ impl CryptoManager {
     }
 }
 
-/// Get or create encryption manager
-pub fn get_crypto_manager() -> Result<CryptoManager> {
-    use crate::config::{load_config, save_config};
+/// Master key settings and the derived key for a new master passphrase
+pub fn new_master_key(passphrase: &str) -> Result<(MasterKeyConfig, [u8; KEY_SIZE])> {
+    check_passphrase(passphrase)?;
+    let kdf = KdfParams::generate();
+    let key = kdf.derive_key(passphrase)?;
+    let master = MasterKeyConfig {
+        verifier: CryptoManager::new(&key).encrypt(VERIFIER_TEXT)?,
+        kdf,
+        auto_lock_minutes: MasterKeyConfig::DEFAULT_AUTO_LOCK_MINUTES,
+    };
+    Ok((master, key))
+}
+
+/// Derive the master key, failing if `passphrase` is not the master passphrase
+pub fn verify_master_key(master: &MasterKeyConfig, passphrase: &str) -> Result<[u8; KEY_SIZE]> {
+    let key = master.kdf.derive_key(passphrase)?;
+    match CryptoManager::new(&key).decrypt(&master.verifier) {
+        Ok(text) if text == VERIFIER_TEXT => Ok(key),
+        _ => Err(anyhow!("Wrong passphrase")),
+    }
+}
+
+/// Keep (or forget) the master key for this session
+pub(crate) fn set_session_key(key: Option<[u8; KEY_SIZE]>) {
+    if let Ok(mut session) = SESSION_KEY.lock() {
+        *session = key;
+    }
+}
+
+/// Unlock with the master passphrase; a no-op without master passphrase mode
+pub fn unlock(passphrase: &str) -> Result<()> {
+    if let Some(master) = load_config()?.master_key {
+        set_session_key(Some(verify_master_key(&master, passphrase)?));
+        tracing::info!("Unlocked with the master passphrase");
+    }
+    Ok(())
+}
+
+/// Forget the master key until the next unlock
+pub fn lock() {
+    set_session_key(None);
+    crate::secret_store::clear_resolved_references();
+    tracing::info!("Locked");
+}
 
+/// Whether master passphrase mode is on and the key is not in memory
+pub fn is_locked() -> bool {
+    let unlocked = SESSION_KEY.lock().map(|k| k.is_some()).unwrap_or(false);
+    !unlocked && master_state().enabled
+}
+
+/// Get or create encryption manager.
+///
+/// In master passphrase mode this is the key derived from the passphrase, available
+/// only while unlocked; otherwise the key stored in config.json.
+pub fn get_crypto_manager() -> Result<CryptoManager> {
     let mut config = load_config()?;
 
+    if config.master_key.is_some() {
+        let session = SESSION_KEY
+            .lock()
+            .map_err(|e| anyhow!("Failed to read the master key: {}", e))?;
+        return match *session {
+            Some(key) => Ok(CryptoManager::new(&key)),
+            None => Err(anyhow!(
+                "CloudBridge is locked; unlock it with the master passphrase (CLOUDBRIDGE_MASTER_PASSPHRASE for commands)"
+            )),
+        };
+    }
+
     let key = if let Some(ref key_str) = config.encryption_key {
         CryptoManager::key_from_string(key_str)?
     } else {
@@ -119,6 +304,56 @@ mod tests {
         assert_eq!(plaintext, decrypted);
     }
 
+    #[test]
+    fn test_passphrase_key_derivation() {
+        let kdf = KdfParams {
+            memory_kib: 1024,
+            iterations: 1,
+            ..KdfParams::generate()
+        };
+
+        let encrypted = CryptoManager::from_passphrase("correct horse", &kdf)
+            .unwrap()
+            .encrypt("secret")
+            .unwrap();
+        let same = CryptoManager::from_passphrase("correct horse", &kdf).unwrap();
+        assert_eq!(same.decrypt(&encrypted).unwrap(), "secret");
+
+        let wrong = CryptoManager::from_passphrase("battery staple", &kdf).unwrap();
+        assert!(wrong.decrypt(&encrypted).is_err());
+
+        let other_salt =
+            CryptoManager::from_passphrase("correct horse", &KdfParams::generate()).unwrap();
+        assert!(other_salt.decrypt(&encrypted).is_err());
+    }
+
+    #[test]
+    fn test_master_key_verification() {
+        let (mut master, key) = new_master_key("correct horse battery").unwrap();
+        assert!(!master.verifier.contains(VERIFIER_TEXT));
+
+        // Use cheap parameters for the remaining derivations
+        master.kdf.memory_kib = 1024;
+        master.kdf.iterations = 1;
+        let cheap_key = master.kdf.derive_key("correct horse battery").unwrap();
+        master.verifier = CryptoManager::new(&cheap_key)
+            .encrypt(VERIFIER_TEXT)
+            .unwrap();
+        assert_ne!(cheap_key, key);
+
+        assert_eq!(
+            verify_master_key(&master, "correct horse battery").unwrap(),
+            cheap_key
+        );
+        assert_eq!(
+            verify_master_key(&master, "wrong passphrase")
+                .unwrap_err()
+                .to_string(),
+            "Wrong passphrase"
+        );
+        assert!(new_master_key("short").is_err());
+    }
+
     #[test]
     fn test_key_serialization() {
         let key = CryptoManager::generate_key();
@@ -0,0 +1,590 @@
+//! AWS Cost and Usage Report ingestion
+//!
+//! Reads CUR 2.0 (Data Exports) and legacy CUR files from a local directory using
+//! DuckDB's CSV (including gzip) and Parquet readers. Line items are stored in
+//! `cost_line_items` with their resource IDs and aggregated into `cost_data`, so the
+//! dashboard can work from report data without Cost Explorer requests.
+//!
+//! Legacy CUR writes each report version to its own assembly folder next to a
+//! `<report>-Manifest.json` listing the current files; only the listed files are read.
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use duckdb::{params, Connection};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::cloud::{focus, CloudAccount, CloudProvider, CostData, CostDimension, FocusCost};
+use crate::config::AppConfig;
+use crate::db;
+
+/// Candidate column names per field: CUR 2.0 names first, then legacy CUR names.
+/// Where several columns are listed, the first non-empty value wins.
+const USAGE_DATE_COLUMNS: &[&str] = &["line_item_usage_start_date", "lineItem/UsageStartDate"];
+const SERVICE_COLUMNS: &[&str] = &[
+    "product_product_name",
+    "product/ProductName",
+    "line_item_product_code",
+    "lineItem/ProductCode",
+];
+const REGION_COLUMNS: &[&str] = &[
+    "product_region_code",
+    "product/regionCode",
+    "product/region",
+];
+const USAGE_TYPE_COLUMNS: &[&str] = &["line_item_usage_type", "lineItem/UsageType"];
+const RESOURCE_ID_COLUMNS: &[&str] = &["line_item_resource_id", "lineItem/ResourceId"];
+const AMOUNT_COLUMNS: &[&str] = &["line_item_unblended_cost", "lineItem/UnblendedCost"];
+const CURRENCY_COLUMNS: &[&str] = &["line_item_currency_code", "lineItem/CurrencyCode"];
+const LINKED_ACCOUNT_COLUMNS: &[&str] = &["line_item_usage_account_id", "lineItem/UsageAccountId"];
+
+/// Result of ingesting a CUR directory
+#[derive(Debug, Default, Clone)]
+pub struct CurIngestReport {
+    /// Files read in this run
+    pub files_ingested: usize,
+    /// Files skipped because they were unchanged since the last run
+    pub files_skipped: usize,
+    /// Line items loaded in this run
+    pub rows_ingested: usize,
+}
+
+/// Ingest the account's CUR directory, if one is configured (`cur_directories`).
+/// Failures are logged as well as returned; None when the account has no CUR directory.
+pub fn ingest_configured(
+    account: &CloudAccount,
+    config: &AppConfig,
+) -> Option<Result<CurIngestReport>> {
+    let dir = config.cur_directory_for(&account.id, &account.name)?;
+    let result = ingest_directory(&account.id, &dir);
+    if let Err(e) = &result {
+        tracing::warn!("CUR ingestion failed for {}: {}", account.name, e);
+    }
+    Some(result)
+}
+
+/// Ingest all new or changed CUR files under `dir` for a CloudBridge account
+pub fn ingest_directory(account_id: &str, dir: &Path) -> Result<CurIngestReport> {
+    let files = find_cur_files(dir)?;
+    db::with_connection(|conn| ingest_files(conn, account_id, &files))
+}
+
+/// Ingest the given files, skipping ones that were already ingested unchanged.
+///
+/// Files are recorded and the account's totals rebuilt in one transaction, so a file
+/// that fails to load leaves nothing marked as ingested.
+pub(crate) fn ingest_files(
+    conn: &Connection,
+    account_id: &str,
+    files: &[PathBuf],
+) -> Result<CurIngestReport> {
+    db::in_transaction(conn, |conn| load_files(conn, account_id, files))
+}
+
+fn load_files(conn: &Connection, account_id: &str, files: &[PathBuf]) -> Result<CurIngestReport> {
+    let mut report = CurIngestReport::default();
+
+    for path in files {
+        let metadata = std::fs::metadata(path)?;
+        let modified_at = metadata
+            .modified()
+            .map(|t| DateTime::<Utc>::from(t).to_rfc3339())
+            .unwrap_or_default();
+        let size = metadata.len() as i64;
+        let path_str = path.to_string_lossy().to_string();
+
+        let unchanged = conn
+            .query_row(
+                "SELECT COUNT(*) FROM cur_files WHERE path = ? AND modified_at = ? AND size = ?",
+                params![path_str, modified_at, size],
+                |row| row.get::<_, i64>(0),
+            )
+            .map(|count| count > 0)?;
+        if unchanged {
+            report.files_skipped += 1;
+            continue;
+        }
+
+        let rows = ingest_file(conn, account_id, path)
+            .map_err(|e| anyhow!("Failed to ingest {}: {}", path.display(), e))?;
+
+        conn.execute(
+            "INSERT OR REPLACE INTO cur_files (path, account_id, modified_at, size, row_count, ingested_at)
+             VALUES (?, ?, ?, ?, ?, ?)",
+            params![
+                path_str,
+                account_id,
+                modified_at,
+                size,
+                rows as i64,
+                Utc::now().to_rfc3339()
+            ],
+        )?;
+
+        tracing::info!("Ingested {} CUR line items from {}", rows, path.display());
+        report.files_ingested += 1;
+        report.rows_ingested += rows;
+    }
+
+    if report.files_ingested > 0 {
+        rebuild_cost_data(conn, account_id)?;
+        rebuild_focus_costs(conn, account_id)?;
+    }
+
+    Ok(report)
+}
+
+/// Load one CUR file into cost_line_items, replacing rows from an earlier version of it
+fn ingest_file(conn: &Connection, account_id: &str, path: &Path) -> Result<usize> {
+    let reader = reader_expression(path)?;
+
+    let mut stmt = conn.prepare(&format!("DESCRIBE SELECT * FROM {}", reader))?;
+    let columns = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let usage_date = first_value(&columns, USAGE_DATE_COLUMNS)
+        .ok_or_else(|| anyhow!("not a CUR file (no usage start date column)"))?;
+    let amount = first_value(&columns, AMOUNT_COLUMNS)
+        .ok_or_else(|| anyhow!("not a CUR file (no unblended cost column)"))?;
+    let service = first_value(&columns, SERVICE_COLUMNS).unwrap_or_else(|| "NULL".to_string());
+    let optional =
+        |candidates: &[&str]| first_value(&columns, candidates).unwrap_or_else(|| "NULL".into());
+
+    let path_str = path.to_string_lossy().to_string();
+    conn.execute(
+        "DELETE FROM cost_line_items WHERE source_file = ?",
+        params![path_str],
+    )?;
+
+    let sql = format!(
+        r#"
+        INSERT INTO cost_line_items
+        (account_id, linked_account, date, service, region, usage_type, resource_id, amount,
+         currency, source_file)
+        SELECT ?, {linked_account}, left({usage_date}, 10), COALESCE({service}, 'Unknown'),
+               {region}, {usage_type}, {resource_id}, TRY_CAST({amount} AS DOUBLE),
+               COALESCE({currency}, 'USD'), ?
+        FROM {reader}
+        WHERE TRY_CAST({amount} AS DOUBLE) IS NOT NULL AND {usage_date} IS NOT NULL
+        "#,
+        linked_account = optional(LINKED_ACCOUNT_COLUMNS),
+        region = optional(REGION_COLUMNS),
+        usage_type = optional(USAGE_TYPE_COLUMNS),
+        resource_id = optional(RESOURCE_ID_COLUMNS),
+        currency = optional(CURRENCY_COLUMNS),
+    );
+
+    let rows = conn.execute(&sql, params![account_id, path_str])?;
+    Ok(rows)
+}
+
+/// Replace the account's cost_data rows with daily per-service totals of its line items
+fn rebuild_cost_data(conn: &Connection, account_id: &str) -> Result<()> {
+    conn.execute(
+        "DELETE FROM cost_data WHERE account_id = ? AND date IN
+         (SELECT DISTINCT date FROM cost_line_items WHERE account_id = ?)",
+        params![account_id, account_id],
+    )?;
+    conn.execute(
+        r#"
+        INSERT INTO cost_data (id, account_id, date, service, amount, currency, created_at)
+        SELECT nextval('cost_data_id_seq'), account_id, date, service, amount, currency, ?
+        FROM (
+            SELECT account_id, date, service, SUM(amount) AS amount, currency
+            FROM cost_line_items
+            WHERE account_id = ?
+            GROUP BY account_id, date, service, currency
+        )
+        "#,
+        params![Utc::now().to_rfc3339(), account_id],
+    )?;
+    Ok(())
+}
+
+/// Replace the account's FOCUS rows with daily per-resource rows of its line items
+fn rebuild_focus_costs(conn: &Connection, account_id: &str) -> Result<()> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT date, service, region, resource_id, linked_account, currency, SUM(amount)
+        FROM cost_line_items
+        WHERE account_id = ?
+        GROUP BY date, service, region, resource_id, linked_account, currency
+        ORDER BY date
+        "#,
+    )?;
+    let rows = stmt
+        .query_map(params![account_id], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?,
+                row.get::<_, Option<String>>(3)?,
+                row.get::<_, Option<String>>(4)?,
+                row.get::<_, String>(5)?,
+                row.get::<_, f64>(6)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let (Some(first), Some(last)) = (rows.first(), rows.last()) else {
+        return Ok(());
+    };
+    let start_date = first.0.clone();
+    let end_date = focus::next_day(&last.0);
+
+    let costs: Vec<FocusCost> = rows
+        .into_iter()
+        .map(
+            |(date, service, region, resource_id, linked_account, currency, amount)| {
+                let mut dimensions = BTreeMap::new();
+                if let Some(region) = region {
+                    dimensions.insert(CostDimension::Region.key(), region);
+                }
+                let cost = CostData {
+                    account_id: account_id.to_string(),
+                    date,
+                    service,
+                    amount,
+                    currency,
+                    dimensions,
+                };
+                FocusCost {
+                    resource_id,
+                    ..focus::from_cost_data(
+                        CloudProvider::AWS,
+                        &cost,
+                        &focus::next_day(&cost.date),
+                        linked_account,
+                        None,
+                    )
+                }
+            },
+        )
+        .collect();
+
+    db::replace_focus_costs(conn, account_id, &start_date, &end_date, &costs)
+}
+
+/// Find CUR files (.csv, .csv.gz, .parquet) under a directory, recursively. Below a
+/// legacy CUR manifest, only the files it lists are returned.
+pub fn find_cur_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    if !dir.is_dir() {
+        return Err(anyhow!("CUR directory not found: {}", dir.display()));
+    }
+
+    let mut files = Vec::new();
+    let mut manifests = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        for entry in std::fs::read_dir(&current)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else if is_cur_file(&path) {
+                files.push(path);
+            } else if let Some(report_keys) = read_manifest(&path) {
+                manifests.push((current.clone(), report_keys));
+            }
+        }
+    }
+
+    // The manifest of a billing period lists the current assembly; assembly folders
+    // carry their own copy, so the outermost manifest above a file applies
+    manifests.sort_by_key(|(manifest_dir, _)| manifest_dir.components().count());
+    files.retain(|file| {
+        let Some((manifest_dir, report_keys)) = manifests
+            .iter()
+            .find(|(manifest_dir, _)| file.starts_with(manifest_dir))
+        else {
+            return true;
+        };
+        let relative = file
+            .strip_prefix(manifest_dir)
+            .map(|p| p.to_string_lossy().replace('\\', "/"))
+            .unwrap_or_default();
+        report_keys
+            .iter()
+            .any(|key| key.ends_with(&format!("/{}", relative)))
+    });
+
+    files.sort();
+    Ok(files)
+}
+
+/// `reportKeys` of a legacy CUR manifest (`<report>-Manifest.json`)
+fn read_manifest(path: &Path) -> Option<Vec<String>> {
+    let name = path.file_name()?.to_string_lossy().to_lowercase();
+    if !name.ends_with("-manifest.json") {
+        return None;
+    }
+    let manifest: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(path).ok()?).ok()?;
+    let keys = manifest
+        .get("reportKeys")?
+        .as_array()?
+        .iter()
+        .filter_map(|key| key.as_str().map(|key| key.to_string()))
+        .collect();
+    Some(keys)
+}
+
+fn is_cur_file(path: &Path) -> bool {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    name.ends_with(".csv") || name.ends_with(".csv.gz") || name.ends_with(".parquet")
+}
+
+/// DuckDB table function reading the file (gzip is detected from the extension)
+fn reader_expression(path: &Path) -> Result<String> {
+    let path_str = path
+        .to_str()
+        .ok_or_else(|| anyhow!("Unsupported file path: {}", path.display()))?
+        .replace('\'', "''");
+
+    if path_str.to_lowercase().ends_with(".parquet") {
+        Ok(format!("read_parquet('{}')", path_str))
+    } else {
+        Ok(format!(
+            "read_csv('{}', header = true, all_varchar = true)",
+            path_str
+        ))
+    }
+}
+
+/// SQL expression yielding the first non-empty value among the candidate columns present
+fn first_value(columns: &[String], candidates: &[&str]) -> Option<String> {
+    let present: Vec<String> = candidates
+        .iter()
+        .filter_map(|candidate| {
+            columns
+                .iter()
+                .find(|c| c.eq_ignore_ascii_case(candidate))
+                .map(|c| {
+                    format!(
+                        "NULLIF(CAST(\"{}\" AS VARCHAR), '')",
+                        c.replace('"', "\"\"")
+                    )
+                })
+        })
+        .collect();
+
+    match present.len() {
+        0 => None,
+        1 => Some(present[0].clone()),
+        _ => Some(format!("COALESCE({})", present.join(", "))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_connection() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::migrations::migrate(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO cloud_accounts (id, name, provider, created_at) VALUES ('acc', 'Test', 'AWS', '')",
+            [],
+        )
+        .unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_ingest_legacy_cur_csv() {
+        let dir = crate::config::scratch_dir("cur-legacy").unwrap();
+        std::fs::write(
+            dir.join("report-00001.csv"),
+            "identity/LineItemId,lineItem/UsageAccountId,lineItem/UsageStartDate,lineItem/ProductCode,product/ProductName,product/region,lineItem/UsageType,lineItem/ResourceId,lineItem/UnblendedCost,lineItem/CurrencyCode\n\
+             a,111122223333,2024-05-01T00:00:00Z,AmazonEC2,Amazon Elastic Compute Cloud,us-east-1,BoxUsage:m5.large,i-0abc,1.5,USD\n\
+             b,111122223333,2024-05-01T01:00:00Z,AmazonEC2,Amazon Elastic Compute Cloud,us-east-1,BoxUsage:m5.large,i-0abc,2.5,USD\n\
+             c,111122223333,2024-05-02T00:00:00Z,AmazonS3,,us-west-2,TimedStorage-ByteHrs,my-bucket,0.25,USD\n",
+        )
+        .unwrap();
+
+        let conn = test_connection();
+        let files = find_cur_files(&dir).unwrap();
+        let report = ingest_files(&conn, "acc", &files).unwrap();
+        assert_eq!(report.files_ingested, 1);
+        assert_eq!(report.rows_ingested, 3);
+
+        let (service, resource_id): (String, String) = conn
+            .query_row(
+                "SELECT service, resource_id FROM cost_line_items WHERE usage_type = 'TimedStorage-ByteHrs'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        // Empty product name falls back to the product code
+        assert_eq!(service, "AmazonS3");
+        assert_eq!(resource_id, "my-bucket");
+
+        let (date, amount): (String, f64) = conn
+            .query_row(
+                "SELECT date, amount FROM cost_data WHERE service = 'Amazon Elastic Compute Cloud'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(date, "2024-05-01");
+        assert_eq!(amount, 4.0);
+
+        // Unchanged files are skipped on the next run
+        let report = ingest_files(&conn, "acc", &files).unwrap();
+        assert_eq!(report.files_skipped, 1);
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM cost_line_items", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 3);
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    const LEGACY_HEADER: &str =
+        "lineItem/UsageStartDate,lineItem/ProductCode,lineItem/UnblendedCost,lineItem/CurrencyCode";
+
+    #[test]
+    fn test_failed_file_is_not_marked_ingested() {
+        let dir = crate::config::scratch_dir("cur-failed").unwrap();
+        let good = dir.join("a.csv");
+        let bad = dir.join("b.csv");
+        std::fs::write(
+            &good,
+            format!(
+                "{}\n2024-05-01T00:00:00Z,AmazonEC2,1.5,USD\n",
+                LEGACY_HEADER
+            ),
+        )
+        .unwrap();
+        std::fs::write(&bad, "not,a,report\n1,2,3\n").unwrap();
+
+        let conn = test_connection();
+        let files = vec![good.clone(), bad.clone()];
+        assert!(ingest_files(&conn, "acc", &files).is_err());
+        let count = |sql: &str| conn.query_row(sql, [], |row| row.get::<_, i64>(0)).unwrap();
+        assert_eq!(count("SELECT COUNT(*) FROM cur_files"), 0);
+        assert_eq!(count("SELECT COUNT(*) FROM cost_line_items"), 0);
+
+        // Once the broken file is gone, the earlier file is loaded and totals are rebuilt
+        std::fs::remove_file(&bad).unwrap();
+        let report = ingest_files(&conn, "acc", &[good]).unwrap();
+        assert_eq!(report.files_ingested, 1);
+        assert_eq!(count("SELECT COUNT(*) FROM cost_data"), 1);
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_manifest_selects_current_assembly() {
+        let dir = crate::config::scratch_dir("cur-manifest").unwrap();
+        let period = dir.join("report").join("20240501-20240601");
+        let manifest = |assembly: &str| {
+            format!(
+                r#"{{"assemblyId": "{0}", "reportKeys": ["cur/report/20240501-20240601/{0}/report-1.csv.gz"]}}"#,
+                assembly
+            )
+        };
+        for assembly in ["old-assembly", "new-assembly"] {
+            std::fs::create_dir_all(period.join(assembly)).unwrap();
+            std::fs::write(period.join(assembly).join("report-1.csv.gz"), "").unwrap();
+            std::fs::write(
+                period.join(assembly).join("report-Manifest.json"),
+                manifest(assembly),
+            )
+            .unwrap();
+        }
+        std::fs::write(
+            period.join("report-Manifest.json"),
+            manifest("new-assembly"),
+        )
+        .unwrap();
+        // Files outside any manifest's folder are all read
+        std::fs::write(dir.join("extra.csv"), "").unwrap();
+
+        let files = find_cur_files(&dir).unwrap();
+        assert_eq!(
+            files,
+            vec![
+                dir.join("extra.csv"),
+                period.join("new-assembly").join("report-1.csv.gz"),
+            ]
+        );
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_ingest_cur2_parquet() {
+        let dir = crate::config::scratch_dir("cur2-parquet").unwrap();
+        let file = dir.join("data").join("part-0.snappy.parquet");
+        std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+
+        let conn = test_connection();
+        conn.execute_batch(&format!(
+            "COPY (SELECT * FROM (VALUES
+                ('111122223333', TIMESTAMP '2024-05-03 00:00:00', 'AmazonRDS', 'eu-west-1',
+                 'InstanceUsage:db.t3.micro', 'arn:aws:rds:eu-west-1:111122223333:db:main', 3.25::DOUBLE, 'USD'))
+              t(line_item_usage_account_id, line_item_usage_start_date, line_item_product_code,
+                product_region_code, line_item_usage_type, line_item_resource_id,
+                line_item_unblended_cost, line_item_currency_code))
+             TO '{}' (FORMAT PARQUET)",
+            file.display()
+        ))
+        .unwrap();
+
+        let report = ingest_files(&conn, "acc", &find_cur_files(&dir).unwrap()).unwrap();
+        assert_eq!(report.rows_ingested, 1);
+
+        let (date, service, region, resource_id, linked): (String, String, String, String, String) =
+            conn.query_row(
+                "SELECT date, service, region, resource_id, linked_account FROM cost_line_items",
+                [],
+                |row| {
+                    Ok((
+                        row.get(0)?,
+                        row.get(1)?,
+                        row.get(2)?,
+                        row.get(3)?,
+                        row.get(4)?,
+                    ))
+                },
+            )
+            .unwrap();
+        assert_eq!(date, "2024-05-03");
+        assert_eq!(service, "AmazonRDS");
+        assert_eq!(region, "eu-west-1");
+        assert_eq!(resource_id, "arn:aws:rds:eu-west-1:111122223333:db:main");
+        assert_eq!(linked, "111122223333");
+
+        // Line items are mapped into FOCUS rows and exported with FOCUS column names
+        let export = dir.join("focus.csv");
+        let exported = db::copy_focus_dataset(
+            &conn,
+            &["acc".to_string()],
+            "2024-05-01",
+            "2024-06-01",
+            &export,
+        )
+        .unwrap();
+        assert_eq!(exported, 1);
+        let csv = std::fs::read_to_string(&export).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(
+            lines.next().unwrap(),
+            "BilledCost,EffectiveCost,ListCost,BillingCurrency,ChargeCategory,ChargePeriodStart,\
+             ChargePeriodEnd,ProviderName,PublisherName,InvoiceIssuerName,ServiceName,\
+             ServiceCategory,RegionId,ResourceId,SubAccountId,SubAccountName,Tags"
+        );
+        let row = lines.next().unwrap();
+        assert!(row
+            .starts_with("3.25,3.25,3.25,USD,Usage,2024-05-03T00:00:00Z,2024-05-04T00:00:00Z,AWS"));
+        assert!(row.contains(
+            "Databases,eu-west-1,arn:aws:rds:eu-west-1:111122223333:db:main,111122223333"
+        ));
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
@@ -1,30 +1,83 @@
 //! Database module - Using DuckDB for data storage
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use chrono::{DateTime, Duration, Utc};
 use duckdb::{params, Connection};
+use std::path::Path;
 use std::sync::{Arc, Mutex};
 
+use crate::backup;
+use crate::cache::{self, CacheDataset, CacheEntry};
 use crate::cloud::{
-    BudgetInfo, BudgetStatus, CloudAccount, CloudProvider, CostData, CostSummary, CostTrend,
-    DailyCost, ServiceCost,
+    AnomalyFeedback, BudgetInfo, BudgetSource, BudgetStatus, CloudAccount, CloudProvider,
+    CommitmentSummary, CostAnomaly, CostData, CostDimension, CostSummary, CostTrend, DailyCost,
+    FocusCost, ServiceCost,
 };
-use crate::config::get_database_path;
+use crate::config::{get_database_path, load_config};
 use crate::crypto::get_crypto_manager;
+use crate::migrations;
 use crate::secret_store;
 
 lazy_static::lazy_static! {
     static ref DB_CONNECTION: Arc<Mutex<Option<Connection>>> = Arc::new(Mutex::new(None));
 }
 
-/// Cache time-to-live (hours)
-const CACHE_TTL_HOURS: i64 = 6;
-
 /// Initialize database
 pub fn init_database() -> Result<()> {
     let db_path = get_database_path()?;
+    let existed = db_path.exists();
     let conn = Connection::open(&db_path)?;
 
+    // Keep a copy of the data before its schema changes
+    let keep = load_config().map(|c| c.auto_backups).unwrap_or(0);
+    if existed && keep > 0 && !migrations::pending_migrations(&conn)?.is_empty() {
+        backup::create_auto_backup(&conn, "pre-migration", keep)
+            .context("Backup before database migration failed")?;
+    }
+
+    let applied = migrations::migrate(&conn)?;
+    if !applied.is_empty() {
+        tracing::info!("Database migrated to schema version {:?}", applied.last());
+    }
+
+    let mut db = DB_CONNECTION.lock().unwrap();
+    *db = Some(conn);
+
+    tracing::info!("Database initialized: {:?}", db_path);
+    Ok(())
+}
+
+/// Replace the database file with `source` and reopen it
+pub(crate) fn replace_database(source: &Path) -> Result<()> {
+    let db_path = get_database_path()?;
+    let replaced = {
+        let mut db = DB_CONNECTION
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Failed to get database connection: {}", e))?;
+        // Closing the connection checkpoints and releases the file
+        *db = None;
+
+        let wal = db_path.with_extension("duckdb.wal");
+        std::fs::copy(source, &db_path)
+            .and_then(|_| {
+                if wal.exists() {
+                    std::fs::remove_file(&wal)
+                } else {
+                    Ok(())
+                }
+            })
+            .with_context(|| format!("Failed to replace the database at {}", db_path.display()))
+    };
+
+    // Reopen either way, so a failed copy leaves the previous database usable
+    init_database()?;
+    replaced
+}
+
+/// Create the version 1 schema, the layout of the last release before schema versioning.
+///
+/// Frozen: schema changes go into a new migration in `migrations`.
+pub(crate) fn create_schema_v1(conn: &Connection) -> Result<()> {
     // Create cloud accounts table
     conn.execute(
         r#"
@@ -114,13 +167,33 @@ pub fn init_database() -> Result<()> {
         [],
     )?;
 
-    let mut db = DB_CONNECTION.lock().unwrap();
-    *db = Some(conn);
-
-    tracing::info!("Database initialized: {:?}", db_path);
     Ok(())
 }
 
+/// Run a closure with the database connection
+pub(crate) fn with_connection<T>(f: impl FnOnce(&Connection) -> Result<T>) -> Result<T> {
+    let db = get_connection()?;
+    f(db.as_ref().unwrap())
+}
+
+/// Run `f` in a transaction, rolled back when it fails
+pub(crate) fn in_transaction<T>(
+    conn: &Connection,
+    f: impl FnOnce(&Connection) -> Result<T>,
+) -> Result<T> {
+    conn.execute_batch("BEGIN TRANSACTION")?;
+    match f(conn) {
+        Ok(value) => {
+            conn.execute_batch("COMMIT")?;
+            Ok(value)
+        }
+        Err(e) => {
+            let _ = conn.execute_batch("ROLLBACK");
+            Err(e)
+        }
+    }
+}
+
 /// Get database connection
 fn get_connection() -> Result<std::sync::MutexGuard<'static, Option<Connection>>> {
     let db = DB_CONNECTION
@@ -132,32 +205,39 @@ fn get_connection() -> Result<std::sync::MutexGuard<'static, Option<Connection>>
     Ok(db)
 }
 
+/// IDs of all accounts, without reading their credentials
+pub fn get_account_ids() -> Result<Vec<String>> {
+    let db = get_connection()?;
+    let conn = db.as_ref().unwrap();
+    let mut stmt = conn.prepare("SELECT id FROM cloud_accounts ORDER BY created_at")?;
+    let ids = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(ids)
+}
+
 /// Save cloud account
 pub fn save_account(account: &CloudAccount) -> Result<()> {
-    // Store secrets in OS keyring for better protection
+    // Store secrets in the secret backend (OS keyring by default)
     secret_store::store_account_secrets(
         &account.id,
         &account.access_key_id,
         &account.secret_access_key,
     )?;
 
-    // Still keep an immutable record in DB, but do not store plaintext or encrypted secrets in DB anymore.
-    // Use empty strings as placeholders for access_key_id/secret_access_key to avoid leaking secrets.
     let db = get_connection()?;
     let conn = db.as_ref().unwrap();
 
     conn.execute(
         r#"
         INSERT OR REPLACE INTO cloud_accounts 
-        (id, name, provider, access_key_id, secret_access_key, region, created_at, last_synced_at, enabled)
-        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+        (id, name, provider, region, created_at, last_synced_at, enabled)
+        VALUES (?, ?, ?, ?, ?, ?, ?)
         "#,
         params![
             account.id,
             account.name,
             format!("{:?}", account.provider),
-            "",
-            "",
             account.region,
             account.created_at.to_rfc3339(),
             account.last_synced_at.map(|dt| dt.to_rfc3339()),
@@ -168,85 +248,93 @@ pub fn save_account(account: &CloudAccount) -> Result<()> {
     Ok(())
 }
 
+/// Move credentials left in `legacy_credentials` by schema version 9 into the secret
+/// backend. Runs once the app is unlocked, as decrypting them needs the encryption key.
+/// Returns the number of accounts moved.
+pub fn migrate_legacy_credentials() -> Result<usize> {
+    let db = get_connection()?;
+    let conn = db.as_ref().unwrap();
+    move_legacy_credentials(conn)
+}
+
+fn move_legacy_credentials(conn: &Connection) -> Result<usize> {
+    let mut stmt = conn
+        .prepare("SELECT account_id, access_key_id, secret_access_key FROM legacy_credentials")?;
+    let legacy = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    if legacy.is_empty() {
+        return Ok(0);
+    }
+
+    let crypto = get_crypto_manager()?;
+    let mut moved = 0;
+    for (id, encrypted_ak, encrypted_sk) in legacy {
+        if secret_store::get_account_secrets(&id)?.is_none() {
+            // Keep values that do not decrypt rather than replacing them with nothing
+            let (Ok(access_key_id), Ok(secret_access_key)) =
+                (crypto.decrypt(&encrypted_ak), crypto.decrypt(&encrypted_sk))
+            else {
+                tracing::warn!("Cannot decrypt the stored credentials of {}; kept", id);
+                continue;
+            };
+            secret_store::store_account_secrets(&id, &access_key_id, &secret_access_key)
+                .with_context(|| format!("Failed to move credentials of {}", id))?;
+            moved += 1;
+        }
+        conn.execute(
+            "DELETE FROM legacy_credentials WHERE account_id = ?",
+            params![id],
+        )?;
+    }
+    Ok(moved)
+}
+
 /// Get all cloud accounts
 pub fn get_all_accounts() -> Result<Vec<CloudAccount>> {
-    let crypto = get_crypto_manager()?;
     let db = get_connection()?;
     let conn = db.as_ref().unwrap();
 
+    // Credentials from before the secret backend are moved there on first use
+    if let Err(e) = move_legacy_credentials(conn) {
+        tracing::warn!("Failed to move stored credentials: {}", e);
+    }
+
     let mut stmt = conn.prepare(
-        "SELECT id, name, provider, access_key_id, secret_access_key, region, created_at, last_synced_at, enabled FROM cloud_accounts"
+        "SELECT id, name, provider, region, created_at, last_synced_at, enabled FROM cloud_accounts",
     )?;
 
     let accounts = stmt
         .query_map([], |row| {
             let provider_str: String = row.get(2)?;
-            let provider = match provider_str.as_str() {
-                "AWS" => CloudProvider::AWS,
-                "Aliyun" => CloudProvider::Aliyun,
-                "Azure" => CloudProvider::Azure,
-                "GCP" => CloudProvider::GCP,
-                "DeepSeek" => CloudProvider::DeepSeek,
-                _ => CloudProvider::AWS,
-            };
-
-            let encrypted_ak: String = row.get(3)?;
-            let encrypted_sk: String = row.get(4)?;
+            let provider = parse_provider(&provider_str);
 
-            let created_at_str: String = row.get(6)?;
-            let last_synced_str: Option<String> = row.get(7)?;
+            let created_at_str: String = row.get(4)?;
+            let last_synced_str: Option<String> = row.get(5)?;
 
             Ok((
                 row.get::<_, String>(0)?,
                 row.get::<_, String>(1)?,
                 provider,
-                encrypted_ak,
-                encrypted_sk,
-                row.get::<_, Option<String>>(5)?,
+                row.get::<_, Option<String>>(3)?,
                 created_at_str,
                 last_synced_str,
-                row.get::<_, bool>(8)?,
+                row.get::<_, bool>(6)?,
             ))
         })?
         .collect::<Result<Vec<_>, _>>()?;
 
     let mut result = Vec::new();
-    for (
-        id,
-        name,
-        provider,
-        encrypted_ak,
-        encrypted_sk,
-        region,
-        created_at_str,
-        last_synced_str,
-        enabled,
-    ) in accounts
-    {
-        // Try to load secrets from OS keyring first (migration path). If not present, fall back to
-        // decrypting existing values from DB and migrate them into keyring.
-        let (access_key_id, secret_access_key) = match secret_store::get_account_secrets(&id)? {
-            Some((ak, sk)) => (ak, sk),
-            None => {
-                // Attempt to decrypt stored values (may be legacy). If successful, migrate to keyring.
-                let ak_plain = crypto.decrypt(&encrypted_ak).unwrap_or_default();
-                let sk_plain = crypto.decrypt(&encrypted_sk).unwrap_or_default();
-
-                if !ak_plain.is_empty() || !sk_plain.is_empty() {
-                    if let Err(e) = secret_store::store_account_secrets(&id, &ak_plain, &sk_plain) {
-                        tracing::warn!("Failed to migrate secrets into keyring for {}: {}", id, e);
-                    } else {
-                        // Clear secrets in DB to avoid retention of encrypted blobs
-                        let _ = conn.execute(
-                            "UPDATE cloud_accounts SET access_key_id = ?, secret_access_key = ? WHERE id = ?",
-                            params!["", "", id],
-                        );
-                    }
-                }
-
-                (ak_plain, sk_plain)
-            }
-        };
+    for (id, name, provider, region, created_at_str, last_synced_str, enabled) in accounts {
+        // Credentials live in the secret backend
+        let (access_key_id, secret_access_key) =
+            secret_store::get_account_secrets(&id)?.unwrap_or_default();
         let created_at = DateTime::parse_from_rfc3339(&created_at_str)
             .map(|dt| dt.with_timezone(&Utc))
             .unwrap_or_else(|_| Utc::now());
@@ -275,22 +363,56 @@ pub fn delete_account(account_id: &str) -> Result<()> {
     let db = get_connection()?;
     let conn = db.as_ref().unwrap();
 
-    // First delete associated cost data
-    conn.execute(
-        "DELETE FROM cost_data WHERE account_id = ?",
-        params![account_id],
-    )?;
-    // Then delete the account
+    delete_account_rows(conn, account_id)?;
+
+    // Remove secrets from the secret backend as well
+    if let Err(e) = secret_store::delete_account_secrets(account_id) {
+        tracing::warn!("Failed to delete account secrets: {}", e);
+    }
+
+    Ok(())
+}
+
+/// Delete an account with its data, budgets and caches, including the rows of its
+/// member sub-accounts (`<account>#<member>`).
+///
+/// The dependent rows are deleted in one transaction, so a failure keeps all of them.
+/// DuckDB cannot delete a row in the transaction that deletes the rows referencing it,
+/// so the account itself is deleted afterwards.
+fn delete_account_rows(conn: &Connection, account_id: &str) -> Result<()> {
+    in_transaction(conn, |conn| delete_account_data(conn, account_id))?;
     conn.execute(
         "DELETE FROM cloud_accounts WHERE id = ?",
         params![account_id],
     )?;
+    Ok(())
+}
 
-    // Remove secrets from OS keyring as well
-    if let Err(e) = secret_store::delete_account_secrets(account_id) {
-        tracing::warn!("Failed to delete account secrets from keyring: {}", e);
+fn delete_account_data(conn: &Connection, account_id: &str) -> Result<()> {
+    let member_prefix = format!("{}#", account_id);
+    for table in [
+        "cost_data",
+        "cost_line_items",
+        "cur_files",
+        "focus_costs",
+        "cost_summary_cache",
+        "cost_trend_cache",
+        "cost_breakdown_cache",
+        "commitment_cache",
+        "anomaly_cache",
+        "member_discovery_cache",
+        "legacy_credentials",
+        "budget_sources",
+        "budgets",
+    ] {
+        conn.execute(
+            &format!(
+                "DELETE FROM {} WHERE account_id = ? OR starts_with(account_id, ?)",
+                table
+            ),
+            params![account_id, member_prefix],
+        )?;
     }
-
     Ok(())
 }
 
@@ -303,8 +425,8 @@ pub fn save_cost_data(costs: &[CostData]) -> Result<()> {
     for cost in costs {
         conn.execute(
             r#"
-            INSERT INTO cost_data (account_id, date, service, amount, currency)
-            VALUES (?, ?, ?, ?, ?)
+            INSERT INTO cost_data (id, account_id, date, service, amount, currency, created_at)
+            VALUES (nextval('cost_data_id_seq'), ?, ?, ?, ?, ?, ?)
             "#,
             params![
                 cost.account_id,
@@ -312,6 +434,7 @@ pub fn save_cost_data(costs: &[CostData]) -> Result<()> {
                 cost.service,
                 cost.amount,
                 cost.currency,
+                Utc::now().to_rfc3339(),
             ],
         )?;
     }
@@ -337,6 +460,7 @@ pub fn get_cost_data(account_id: &str, start_date: &str, end_date: &str) -> Resu
                 service: row.get(2)?,
                 amount: row.get(3)?,
                 currency: row.get(4)?,
+                dimensions: Default::default(),
             })
         })?
         .collect::<Result<Vec<_>, _>>()?;
@@ -344,6 +468,141 @@ pub fn get_cost_data(account_id: &str, start_date: &str, end_date: &str) -> Resu
     Ok(costs)
 }
 
+/// Build a cost summary from locally stored cost data (e.g. ingested CUR files)
+///
+/// Returns None when there is no stored data for the current or last month.
+pub fn get_cost_summary_from_cost_data(
+    account_id: &str,
+    account_name: &str,
+    provider: &CloudProvider,
+) -> Result<Option<CostSummary>> {
+    use chrono::Datelike;
+
+    let db = get_connection()?;
+    let conn = db.as_ref().unwrap();
+
+    let now = Utc::now();
+    let current_month_start = format!("{}-{:02}-01", now.year(), now.month());
+    let last_month = now.with_day(1).unwrap() - Duration::days(1);
+    let last_month_start = format!("{}-{:02}-01", last_month.year(), last_month.month());
+
+    let mut stmt = conn.prepare(
+        "SELECT service, SUM(amount) AS total, MIN(currency) FROM cost_data
+         WHERE account_id = ? AND date >= ? AND date < ?
+         GROUP BY service ORDER BY total DESC",
+    )?;
+    let mut details_for = |start: &str, end: &str| -> Result<Vec<ServiceCost>> {
+        Ok(stmt
+            .query_map(params![account_id, start, end], |row| {
+                Ok(ServiceCost {
+                    service: row.get(0)?,
+                    amount: row.get(1)?,
+                    currency: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?)
+    };
+
+    let current_month_details = details_for(&current_month_start, "9999-12-31")?;
+    let last_month_details = details_for(&last_month_start, &current_month_start)?;
+
+    if current_month_details.is_empty() && last_month_details.is_empty() {
+        return Ok(None);
+    }
+
+    let current_month_cost: f64 = current_month_details.iter().map(|s| s.amount).sum();
+    let last_month_cost: f64 = last_month_details.iter().map(|s| s.amount).sum();
+    let month_over_month_change = if last_month_cost > 0.0 {
+        ((current_month_cost - last_month_cost) / last_month_cost) * 100.0
+    } else {
+        0.0
+    };
+    let currency = current_month_details
+        .first()
+        .or(last_month_details.first())
+        .map(|s| s.currency.clone())
+        .unwrap_or_else(|| "USD".to_string());
+
+    Ok(Some(CostSummary {
+        account_id: account_id.to_string(),
+        account_name: account_name.to_string(),
+        provider: *provider,
+        current_month_cost,
+        last_month_cost,
+        currency,
+        month_over_month_change,
+        current_month_details,
+        last_month_details,
+    }))
+}
+
+/// Per-service totals from locally stored cost data (end date exclusive), largest first
+pub fn get_service_costs_from_cost_data(
+    account_id: &str,
+    start_date: &str,
+    end_date: &str,
+) -> Result<Vec<ServiceCost>> {
+    let db = get_connection()?;
+    let conn = db.as_ref().unwrap();
+
+    let mut stmt = conn.prepare(
+        "SELECT service, SUM(amount) AS total, MIN(currency) FROM cost_data
+         WHERE account_id = ? AND date >= ? AND date < ?
+         GROUP BY service ORDER BY total DESC",
+    )?;
+    let services = stmt
+        .query_map(params![account_id, start_date, end_date], |row| {
+            Ok(ServiceCost {
+                service: row.get(0)?,
+                amount: row.get(1)?,
+                currency: row.get(2)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(services)
+}
+
+/// Build a daily cost trend from locally stored cost data (end date exclusive)
+///
+/// Returns None when there is no stored data in the range.
+pub fn get_cost_trend_from_cost_data(
+    account_id: &str,
+    start_date: &str,
+    end_date: &str,
+) -> Result<Option<CostTrend>> {
+    let db = get_connection()?;
+    let conn = db.as_ref().unwrap();
+
+    let mut stmt = conn.prepare(
+        "SELECT date, SUM(amount), MIN(currency) FROM cost_data
+         WHERE account_id = ? AND date >= ? AND date < ?
+         GROUP BY date ORDER BY date",
+    )?;
+    let rows = stmt
+        .query_map(params![account_id, start_date, end_date], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, f64>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let Some(currency) = rows.first().map(|(_, _, currency)| currency.clone()) else {
+        return Ok(None);
+    };
+
+    Ok(Some(CostTrend {
+        account_id: account_id.to_string(),
+        currency,
+        daily_costs: rows
+            .into_iter()
+            .map(|(date, amount, _)| DailyCost { date, amount })
+            .collect(),
+    }))
+}
+
 /// Get cost summaries for all accounts (reserved interface)
 #[allow(dead_code)]
 pub fn get_all_cost_summaries() -> Result<Vec<CostSummary>> {
@@ -374,6 +633,64 @@ pub fn get_all_cost_summaries() -> Result<Vec<CostSummary>> {
 
 // ==================== Cache Functions ====================
 
+/// Provider as stored in `cloud_accounts.provider`
+fn parse_provider(value: &str) -> CloudProvider {
+    match value {
+        "AWS" => CloudProvider::AWS,
+        "Aliyun" => CloudProvider::Aliyun,
+        "Azure" => CloudProvider::Azure,
+        "GCP" => CloudProvider::GCP,
+        "DeepSeek" => CloudProvider::DeepSeek,
+        _ => CloudProvider::AWS,
+    }
+}
+
+/// Provider of an account, read on the caller's connection
+fn account_provider(conn: &Connection, account_id: &str) -> CloudProvider {
+    conn.query_row(
+        "SELECT provider FROM cloud_accounts WHERE id = ?",
+        params![account_id],
+        |row| row.get::<_, String>(0),
+    )
+    .map(|provider| parse_provider(&provider))
+    .unwrap_or_default()
+}
+
+/// Parse a cache timestamp; unreadable timestamps count as expired
+fn parse_cached_at(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.with_timezone(&Utc))
+        .ok()
+}
+
+/// Whether cached daily rows are still usable: rows of settled months never expire,
+/// the others must be younger than `ttl`, and a range reaching into the current
+/// month needs at least one row from it.
+fn daily_rows_fresh<'a>(
+    rows: impl Iterator<Item = (&'a str, Option<DateTime<Utc>>)>,
+    end_date: &str,
+    ttl: Duration,
+) -> bool {
+    let now = Utc::now();
+    let open_from = cache::open_month_start(now.date_naive())
+        .format("%Y-%m-%d")
+        .to_string();
+    let mut has_open_rows = false;
+
+    for (date, cached_at) in rows {
+        if date >= open_from.as_str() {
+            has_open_rows = true;
+        }
+        match cached_at {
+            Some(cached_at) if cache::is_final(date, cached_at) => {}
+            Some(cached_at) if !cache::is_expired(cached_at, ttl, now) => {}
+            _ => return false,
+        }
+    }
+
+    has_open_rows || end_date <= open_from.as_str()
+}
+
 /// Check if cost summary cache is valid
 /// account_name and provider are passed by the caller to avoid deadlock when acquiring lock while holding database lock
 pub fn get_cached_cost_summary_with_account(
@@ -417,15 +734,15 @@ pub fn get_cached_cost_summary_with_account(
             cached_at_str,
         )) => {
             // Check if cache is expired
-            let cached_at = DateTime::parse_from_rfc3339(&cached_at_str)
-                .map(|dt| dt.with_timezone(&Utc))
-                .unwrap_or_else(|_| Utc::now() - Duration::hours(CACHE_TTL_HOURS + 1));
-
+            let ttl = cache::ttl_for(*provider, CacheDataset::Summary);
             let now = Utc::now();
-            if now - cached_at > Duration::hours(CACHE_TTL_HOURS) {
-                tracing::info!("Cost summary cache expired (cached at: {})", cached_at_str);
-                return Ok(None);
-            }
+            let cached_at = match parse_cached_at(&cached_at_str) {
+                Some(cached_at) if !cache::is_expired(cached_at, ttl, now) => cached_at,
+                _ => {
+                    tracing::info!("Cost summary cache expired (cached at: {})", cached_at_str);
+                    return Ok(None);
+                }
+            };
 
             // Parse service details
             let current_month_details: Vec<ServiceCost> = current_details_json
@@ -436,9 +753,9 @@ pub fn get_cached_cost_summary_with_account(
                 .unwrap_or_default();
 
             tracing::info!(
-                "Using cost summary cache (cached at: {}, {} hours remaining)",
+                "Using cost summary cache (cached at: {}, {} minutes remaining)",
                 cached_at_str,
-                CACHE_TTL_HOURS - (now - cached_at).num_hours()
+                (ttl - (now - cached_at)).num_minutes()
             );
 
             Ok(Some(CostSummary {
@@ -488,6 +805,65 @@ pub fn save_cost_summary_cache(summary: &CostSummary) -> Result<()> {
     Ok(())
 }
 
+/// Save the member sub-accounts found in a payer account's bill, as (ID, name) pairs
+pub fn save_discovered_members(account_id: &str, members: &[(String, String)]) -> Result<()> {
+    let db = get_connection()?;
+    let conn = db.as_ref().unwrap();
+    save_discovered_members_on(conn, account_id, members)
+}
+
+fn save_discovered_members_on(
+    conn: &Connection,
+    account_id: &str,
+    members: &[(String, String)],
+) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO member_discovery_cache (account_id, members, cached_at) VALUES (?, ?, ?)",
+        params![
+            account_id,
+            serde_json::to_string(members)?,
+            Utc::now().to_rfc3339()
+        ],
+    )?;
+    Ok(())
+}
+
+/// Member sub-accounts last found in a payer account's bill; None when not cached or
+/// expired, using the cost summary TTL
+pub fn get_discovered_members(
+    account_id: &str,
+    provider: &CloudProvider,
+) -> Result<Option<Vec<(String, String)>>> {
+    let db = get_connection()?;
+    let conn = db.as_ref().unwrap();
+    get_discovered_members_on(conn, account_id, provider)
+}
+
+fn get_discovered_members_on(
+    conn: &Connection,
+    account_id: &str,
+    provider: &CloudProvider,
+) -> Result<Option<Vec<(String, String)>>> {
+    let result = conn.query_row(
+        "SELECT members, cached_at FROM member_discovery_cache WHERE account_id = ?",
+        params![account_id],
+        |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+    );
+    let (members, cached_at) = match result {
+        Ok(row) => row,
+        Err(duckdb::Error::QueryReturnedNoRows) => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    let ttl = cache::ttl_for(*provider, CacheDataset::Summary);
+    match parse_cached_at(&cached_at) {
+        Some(cached_at) if !cache::is_expired(cached_at, ttl, Utc::now()) => {
+            Ok(Some(serde_json::from_str(&members)?))
+        }
+        _ => Ok(None),
+    }
+}
+
 /// Get cached cost trend
 pub fn get_cached_cost_trend(
     account_id: &str,
@@ -514,21 +890,13 @@ pub fn get_cached_cost_trend(
     })?;
 
     let mut daily_costs = Vec::new();
-    let mut oldest_cache: Option<DateTime<Utc>> = None;
+    let mut cached_times = Vec::new();
     let mut currency = "USD".to_string();
 
     for row in rows {
         let (date, amount, curr, cached_at_str) = row?;
 
-        let cached_at = DateTime::parse_from_rfc3339(&cached_at_str)
-            .map(|dt| dt.with_timezone(&Utc))
-            .unwrap_or_else(|_| Utc::now() - Duration::hours(CACHE_TTL_HOURS + 1));
-
-        // Track the oldest cache time
-        if oldest_cache.is_none() || cached_at < oldest_cache.unwrap() {
-            oldest_cache = Some(cached_at);
-        }
-
+        cached_times.push(parse_cached_at(&cached_at_str));
         currency = curr;
         daily_costs.push(DailyCost { date, amount });
     }
@@ -538,20 +906,18 @@ pub fn get_cached_cost_trend(
         return Ok(None);
     }
 
-    let now = Utc::now();
-    if let Some(cached_at) = oldest_cache {
-        if now - cached_at > Duration::hours(CACHE_TTL_HOURS) {
-            tracing::info!("Cost trend cache expired");
-            return Ok(None);
-        }
-
-        tracing::info!(
-            "Using cost trend cache ({} data points, {} hours remaining)",
-            daily_costs.len(),
-            CACHE_TTL_HOURS - (now - cached_at).num_hours()
-        );
+    let ttl = cache::ttl_for(account_provider(conn, account_id), CacheDataset::Trend);
+    let rows = daily_costs
+        .iter()
+        .map(|daily| daily.date.as_str())
+        .zip(cached_times);
+    if !daily_rows_fresh(rows, end_date, ttl) {
+        tracing::info!("Cost trend cache expired");
+        return Ok(None);
     }
 
+    tracing::info!("Using cost trend cache ({} data points)", daily_costs.len());
+
     Ok(Some(CostTrend {
         account_id: account_id.to_string(),
         currency,
@@ -591,34 +957,502 @@ pub fn save_cost_trend_cache(trend: &CostTrend) -> Result<()> {
     Ok(())
 }
 
-/// Clear all cache for specified account (for force refresh, reserved interface)
-#[allow(dead_code)]
-pub fn clear_account_cache(account_id: &str) -> Result<()> {
+/// Get cached cost breakdown records for a dimension
+pub fn get_cached_cost_breakdown(
+    account_id: &str,
+    dimension: &CostDimension,
+    start_date: &str,
+    end_date: &str,
+) -> Result<Option<Vec<CostData>>> {
     let db = get_connection()?;
     let conn = db.as_ref().unwrap();
 
+    let mut stmt = conn.prepare(
+        "SELECT date, service, amount, currency, dimensions, cached_at FROM cost_breakdown_cache
+         WHERE account_id = ? AND group_by = ? AND date >= ? AND date < ?
+         ORDER BY date",
+    )?;
+
+    let rows = stmt.query_map(
+        params![account_id, dimension.key(), start_date, end_date],
+        |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, f64>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, String>(5)?,
+            ))
+        },
+    )?;
+
+    let mut costs = Vec::new();
+    let mut cached_times = Vec::new();
+
+    for row in rows {
+        let (date, service, amount, currency, dimensions_json, cached_at_str) = row?;
+
+        cached_times.push(parse_cached_at(&cached_at_str));
+
+        costs.push(CostData {
+            account_id: account_id.to_string(),
+            date,
+            service,
+            amount,
+            currency,
+            dimensions: serde_json::from_str(&dimensions_json).unwrap_or_default(),
+        });
+    }
+
+    if costs.is_empty() {
+        return Ok(None);
+    }
+
+    let ttl = cache::ttl_for(account_provider(conn, account_id), CacheDataset::Breakdown);
+    let rows = costs
+        .iter()
+        .map(|cost| cost.date.as_str())
+        .zip(cached_times);
+    if !daily_rows_fresh(rows, end_date, ttl) {
+        tracing::info!("Cost breakdown cache expired ({})", dimension.key());
+        return Ok(None);
+    }
+
+    Ok(Some(costs))
+}
+
+/// Save cost breakdown records for a dimension, replacing the cached range
+pub fn save_cost_breakdown_cache(
+    account_id: &str,
+    dimension: &CostDimension,
+    start_date: &str,
+    end_date: &str,
+    costs: &[CostData],
+) -> Result<()> {
+    let db = get_connection()?;
+    let conn = db.as_ref().unwrap();
+
+    let group_by = dimension.key();
+    let now = Utc::now().to_rfc3339();
+
     conn.execute(
-        "DELETE FROM cost_summary_cache WHERE account_id = ?",
-        params![account_id],
+        "DELETE FROM cost_breakdown_cache WHERE account_id = ? AND group_by = ? AND date >= ? AND date < ?",
+        params![account_id, group_by, start_date, end_date],
     )?;
+
+    for cost in costs {
+        conn.execute(
+            r#"
+            INSERT INTO cost_breakdown_cache
+            (account_id, group_by, date, service, amount, currency, dimensions, cached_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+            params![
+                account_id,
+                group_by,
+                cost.date,
+                cost.service,
+                cost.amount,
+                cost.currency,
+                serde_json::to_string(&cost.dimensions)?,
+                now,
+            ],
+        )?;
+    }
+
+    tracing::info!(
+        "Cached cost breakdown for account {} by {} ({} records)",
+        account_id,
+        group_by,
+        costs.len()
+    );
+    Ok(())
+}
+
+/// Get cached commitment summary
+pub fn get_cached_commitment_summary(account_id: &str) -> Result<Option<CommitmentSummary>> {
+    let db = get_connection()?;
+    let conn = db.as_ref().unwrap();
+
+    let mut stmt =
+        conn.prepare("SELECT summary, cached_at FROM commitment_cache WHERE account_id = ?")?;
+
+    let result = stmt.query_row(params![account_id], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+    });
+
+    match result {
+        Ok((summary_json, cached_at_str)) => {
+            let ttl = cache::ttl_for(
+                account_provider(conn, account_id),
+                CacheDataset::Commitments,
+            );
+            let expired = parse_cached_at(&cached_at_str).map_or(true, |cached_at| {
+                cache::is_expired(cached_at, ttl, Utc::now())
+            });
+            if expired {
+                tracing::info!("Commitment cache expired (cached at: {})", cached_at_str);
+                return Ok(None);
+            }
+
+            Ok(serde_json::from_str(&summary_json).ok())
+        }
+        Err(duckdb::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(anyhow::anyhow!("Failed to get commitment cache: {}", e)),
+    }
+}
+
+/// Save commitment summary to cache
+pub fn save_commitment_cache(summary: &CommitmentSummary) -> Result<()> {
+    let db = get_connection()?;
+    let conn = db.as_ref().unwrap();
+
     conn.execute(
-        "DELETE FROM cost_trend_cache WHERE account_id = ?",
+        "INSERT OR REPLACE INTO commitment_cache (account_id, summary, cached_at) VALUES (?, ?, ?)",
+        params![
+            summary.account_id,
+            serde_json::to_string(summary)?,
+            Utc::now().to_rfc3339(),
+        ],
+    )?;
+
+    tracing::info!(
+        "Cached commitment summary for account {}",
+        summary.account_id
+    );
+    Ok(())
+}
+
+/// Get cached cost anomalies
+pub fn get_cached_anomalies(account_id: &str) -> Result<Option<Vec<CostAnomaly>>> {
+    let db = get_connection()?;
+    let conn = db.as_ref().unwrap();
+
+    let mut stmt =
+        conn.prepare("SELECT anomalies, cached_at FROM anomaly_cache WHERE account_id = ?")?;
+
+    let result = stmt.query_row(params![account_id], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+    });
+
+    match result {
+        Ok((anomalies_json, cached_at_str)) => {
+            let ttl = cache::ttl_for(account_provider(conn, account_id), CacheDataset::Anomalies);
+            let expired = parse_cached_at(&cached_at_str).map_or(true, |cached_at| {
+                cache::is_expired(cached_at, ttl, Utc::now())
+            });
+            if expired {
+                tracing::info!("Anomaly cache expired (cached at: {})", cached_at_str);
+                return Ok(None);
+            }
+
+            Ok(serde_json::from_str(&anomalies_json).ok())
+        }
+        Err(duckdb::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(anyhow::anyhow!("Failed to get anomaly cache: {}", e)),
+    }
+}
+
+/// Save cost anomalies to cache
+pub fn save_anomaly_cache(account_id: &str, anomalies: &[CostAnomaly]) -> Result<()> {
+    let db = get_connection()?;
+    let conn = db.as_ref().unwrap();
+
+    conn.execute(
+        "INSERT OR REPLACE INTO anomaly_cache (account_id, anomalies, cached_at) VALUES (?, ?, ?)",
+        params![
+            account_id,
+            serde_json::to_string(anomalies)?,
+            Utc::now().to_rfc3339(),
+        ],
+    )?;
+
+    tracing::info!(
+        "Cached {} cost anomalies for account {}",
+        anomalies.len(),
+        account_id
+    );
+    Ok(())
+}
+
+/// Record feedback for a cached anomaly (keeps the cache timestamp)
+pub fn update_anomaly_feedback(
+    account_id: &str,
+    anomaly_id: &str,
+    feedback: AnomalyFeedback,
+) -> Result<()> {
+    let db = get_connection()?;
+    let conn = db.as_ref().unwrap();
+
+    let result = conn.query_row(
+        "SELECT anomalies FROM anomaly_cache WHERE account_id = ?",
         params![account_id],
+        |row| row.get::<_, String>(0),
+    );
+
+    let anomalies_json = match result {
+        Ok(json) => json,
+        Err(duckdb::Error::QueryReturnedNoRows) => return Ok(()),
+        Err(e) => return Err(anyhow::anyhow!("Failed to get anomaly cache: {}", e)),
+    };
+
+    let mut anomalies: Vec<CostAnomaly> = serde_json::from_str(&anomalies_json)?;
+    for anomaly in anomalies.iter_mut().filter(|a| a.anomaly_id == anomaly_id) {
+        anomaly.feedback = Some(feedback);
+    }
+
+    conn.execute(
+        "UPDATE anomaly_cache SET anomalies = ? WHERE account_id = ?",
+        params![serde_json::to_string(&anomalies)?, account_id],
     )?;
+    Ok(())
+}
+
+/// Age and size of every cached dataset, per account
+pub fn get_cache_entries() -> Result<Vec<CacheEntry>> {
+    let db = get_connection()?;
+    let conn = db.as_ref().unwrap();
+
+    let mut entries = Vec::new();
+    for dataset in CacheDataset::ALL {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT account_id, COUNT(*), MAX(cached_at) FROM {} GROUP BY account_id ORDER BY account_id",
+            dataset.table()
+        ))?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })?;
+
+        for row in rows {
+            let (account_id, count, cached_at) = row?;
+            if let Some(updated_at) = parse_cached_at(&cached_at) {
+                entries.push(CacheEntry {
+                    account_id,
+                    dataset,
+                    rows: count as usize,
+                    updated_at,
+                });
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Invalidate cached data of one account and/or dataset; `None` matches all of them.
+/// Closed-month rows are removed as well, so they are fetched again.
+pub fn invalidate_cache(account_id: Option<&str>, dataset: Option<CacheDataset>) -> Result<()> {
+    let db = get_connection()?;
+    let conn = db.as_ref().unwrap();
+
+    let datasets = match dataset {
+        Some(dataset) => vec![dataset],
+        None => CacheDataset::ALL.to_vec(),
+    };
+    for dataset in datasets {
+        match account_id {
+            Some(account_id) => conn.execute(
+                &format!("DELETE FROM {} WHERE account_id = ?", dataset.table()),
+                params![account_id],
+            )?,
+            None => conn.execute(&format!("DELETE FROM {}", dataset.table()), [])?,
+        };
+    }
 
-    tracing::info!("Cleared all cache for account {}", account_id);
+    tracing::info!(
+        "Invalidated {} cache for {}",
+        dataset.map_or("all", |d| d.key()),
+        account_id.unwrap_or("all accounts")
+    );
     Ok(())
 }
 
 /// Clear all cache (for global force refresh)
 pub fn clear_all_cache() -> Result<()> {
+    invalidate_cache(None, None)
+}
+
+// ==================== FOCUS Functions ====================
+
+/// Replace an account's FOCUS rows whose charge period starts in [start_date, end_date)
+pub fn save_focus_costs(
+    account_id: &str,
+    start_date: &str,
+    end_date: &str,
+    costs: &[FocusCost],
+) -> Result<()> {
     let db = get_connection()?;
     let conn = db.as_ref().unwrap();
+    replace_focus_costs(conn, account_id, start_date, end_date, costs)
+}
+
+pub(crate) fn replace_focus_costs(
+    conn: &Connection,
+    account_id: &str,
+    start_date: &str,
+    end_date: &str,
+    costs: &[FocusCost],
+) -> Result<()> {
+    conn.execute(
+        "DELETE FROM focus_costs WHERE account_id = ? AND ChargePeriodStart >= ? AND ChargePeriodStart < ?",
+        params![account_id, start_date, end_date],
+    )?;
+
+    let mut stmt = conn.prepare(
+        r#"
+        INSERT INTO focus_costs
+        (account_id, BilledCost, EffectiveCost, ListCost, BillingCurrency, ChargeCategory,
+         ChargePeriodStart, ChargePeriodEnd, ProviderName, ServiceName, ServiceCategory,
+         RegionId, ResourceId, SubAccountId, SubAccountName, Tags)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )?;
+    for cost in costs {
+        stmt.execute(params![
+            account_id,
+            cost.billed_cost,
+            cost.effective_cost,
+            cost.list_cost,
+            cost.billing_currency,
+            cost.charge_category.as_str(),
+            cost.charge_period_start,
+            cost.charge_period_end,
+            cost.provider_name,
+            cost.service_name,
+            cost.service_category,
+            cost.region_id,
+            cost.resource_id,
+            cost.sub_account_id,
+            cost.sub_account_name,
+            serde_json::to_string(&cost.tags)?,
+        ])?;
+    }
+
+    tracing::info!(
+        "Saved {} FOCUS cost rows for account {}",
+        costs.len(),
+        account_id
+    );
+    Ok(())
+}
+
+/// Export FOCUS rows for the given accounts to a FOCUS dataset file (CSV or Parquet, by extension)
+pub fn export_focus_dataset(
+    account_ids: &[String],
+    start_date: &str,
+    end_date: &str,
+    path: &std::path::Path,
+) -> Result<usize> {
+    let db = get_connection()?;
+    let conn = db.as_ref().unwrap();
+    copy_focus_dataset(conn, account_ids, start_date, end_date, path)
+}
+
+pub(crate) fn copy_focus_dataset(
+    conn: &Connection,
+    account_ids: &[String],
+    start_date: &str,
+    end_date: &str,
+    path: &std::path::Path,
+) -> Result<usize> {
+    let format = match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) if ext.eq_ignore_ascii_case("parquet") => "FORMAT PARQUET",
+        _ => "FORMAT CSV, HEADER",
+    };
+    let accounts = if account_ids.is_empty() {
+        "NULL".to_string()
+    } else {
+        account_ids
+            .iter()
+            .map(|id| sql_literal(id))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let path_str = path
+        .to_str()
+        .ok_or_else(|| anyhow::anyhow!("Unsupported export path: {}", path.display()))?;
+
+    // COPY does not take parameters, so values are inlined as escaped literals.
+    // Charge periods are exported as UTC datetimes as required by FOCUS.
+    let sql = format!(
+        r#"
+        COPY (
+            SELECT BilledCost, EffectiveCost, ListCost, BillingCurrency, ChargeCategory,
+                   ChargePeriodStart || 'T00:00:00Z' AS ChargePeriodStart,
+                   ChargePeriodEnd || 'T00:00:00Z' AS ChargePeriodEnd,
+                   ProviderName, ProviderName AS PublisherName, ProviderName AS InvoiceIssuerName,
+                   ServiceName, ServiceCategory, RegionId, ResourceId, SubAccountId,
+                   SubAccountName, Tags
+            FROM focus_costs
+            WHERE account_id IN ({accounts})
+              AND ChargePeriodStart >= {start} AND ChargePeriodStart < {end}
+            ORDER BY ChargePeriodStart, ProviderName, ServiceName
+        ) TO {path} ({format})
+        "#,
+        start = sql_literal(start_date),
+        end = sql_literal(end_date),
+        path = sql_literal(path_str),
+    );
+
+    let rows = conn.execute(&sql, [])?;
+    tracing::info!("Exported {} FOCUS rows to {}", rows, path.display());
+    Ok(rows)
+}
+
+/// Quote a string as a SQL literal
+pub(crate) fn sql_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+// ==================== Digest Functions ====================
+
+/// Claim the period starting at `period_start` for a digest before sending it.
+/// Returns false if the period is already recorded, e.g. by another sender.
+pub fn claim_digest_period(name: &str, period_start: &str) -> Result<bool> {
+    let db = get_connection()?;
+    let conn = db.as_ref().unwrap();
+    claim_digest_period_on(conn, name, period_start)
+}
+
+fn claim_digest_period_on(conn: &Connection, name: &str, period_start: &str) -> Result<bool> {
+    let claimed = conn.execute(
+        r#"
+        INSERT INTO digest_deliveries (name, period_start, sent_at) VALUES (?, ?, ?)
+        ON CONFLICT (name) DO UPDATE
+        SET period_start = excluded.period_start, sent_at = excluded.sent_at
+        WHERE digest_deliveries.period_start <> excluded.period_start
+        "#,
+        params![name, period_start, Utc::now().to_rfc3339()],
+    )?;
+    Ok(claimed > 0)
+}
+
+/// Give up a claimed period after a failed send, so the next check retries it
+pub fn release_digest_period(name: &str, period_start: &str) -> Result<()> {
+    let db = get_connection()?;
+    let conn = db.as_ref().unwrap();
+
+    conn.execute(
+        "DELETE FROM digest_deliveries WHERE name = ? AND period_start = ?",
+        params![name, period_start],
+    )?;
+    Ok(())
+}
 
-    conn.execute("DELETE FROM cost_summary_cache", [])?;
-    conn.execute("DELETE FROM cost_trend_cache", [])?;
+/// Record that a digest was sent for the period starting at `period_start`
+pub fn save_digest_delivery(name: &str, period_start: &str) -> Result<()> {
+    let db = get_connection()?;
+    let conn = db.as_ref().unwrap();
 
-    tracing::info!("Cleared all cost cache");
+    conn.execute(
+        "INSERT OR REPLACE INTO digest_deliveries (name, period_start, sent_at) VALUES (?, ?, ?)",
+        params![name, period_start, Utc::now().to_rfc3339()],
+    )?;
     Ok(())
 }
 
@@ -628,7 +1462,10 @@ pub fn clear_all_cache() -> Result<()> {
 pub fn save_budget(budget: &BudgetInfo) -> Result<()> {
     let db = get_connection()?;
     let conn = db.as_ref().unwrap();
+    save_budget_on(conn, budget)
+}
 
+fn save_budget_on(conn: &Connection, budget: &BudgetInfo) -> Result<()> {
     conn.execute(
         r#"
         INSERT OR REPLACE INTO budgets
@@ -645,7 +1482,24 @@ pub fn save_budget(budget: &BudgetInfo) -> Result<()> {
         ],
     )?;
 
-    tracing::info!("Saved budget for account {}", budget.account_id);
+    conn.execute(
+        r#"
+        INSERT OR REPLACE INTO budget_sources (account_id, source, external_name, synced_at)
+        VALUES (?, ?, ?, ?)
+        "#,
+        params![
+            budget.account_id,
+            budget.source.key(),
+            budget.external_name,
+            Utc::now().to_rfc3339(),
+        ],
+    )?;
+
+    tracing::info!(
+        "Saved budget for account {} (source: {})",
+        budget.account_id,
+        budget.source.label()
+    );
     Ok(())
 }
 
@@ -655,13 +1509,16 @@ pub fn get_budget(account_id: &str) -> Result<Option<BudgetInfo>> {
     let conn = db.as_ref().unwrap();
 
     let mut stmt = conn.prepare(
-        "SELECT account_id, monthly_budget, currency, alert_threshold, created_at, updated_at
-         FROM budgets WHERE account_id = ?",
+        "SELECT b.account_id, b.monthly_budget, b.currency, b.alert_threshold, b.created_at,
+                b.updated_at, s.source, s.external_name
+         FROM budgets b LEFT JOIN budget_sources s ON b.account_id = s.account_id
+         WHERE b.account_id = ?",
     )?;
 
     let result = stmt.query_row(params![account_id], |row| {
         let created_at_str: String = row.get(4)?;
         let updated_at_str: String = row.get(5)?;
+        let source: Option<String> = row.get(6)?;
 
         let created_at = DateTime::parse_from_rfc3339(&created_at_str)
             .map(|dt| dt.with_timezone(&Utc))
@@ -675,6 +1532,8 @@ pub fn get_budget(account_id: &str) -> Result<Option<BudgetInfo>> {
             monthly_budget: row.get(1)?,
             currency: row.get(2)?,
             alert_threshold: row.get(3)?,
+            source: BudgetSource::from_key(&source.unwrap_or_default()),
+            external_name: row.get(7)?,
             created_at,
             updated_at,
         })
@@ -693,14 +1552,16 @@ pub fn get_all_budgets() -> Result<Vec<BudgetInfo>> {
     let conn = db.as_ref().unwrap();
 
     let mut stmt = conn.prepare(
-        "SELECT account_id, monthly_budget, currency, alert_threshold, created_at, updated_at
-         FROM budgets",
+        "SELECT b.account_id, b.monthly_budget, b.currency, b.alert_threshold, b.created_at,
+                b.updated_at, s.source, s.external_name
+         FROM budgets b LEFT JOIN budget_sources s ON b.account_id = s.account_id",
     )?;
 
     let budgets = stmt
         .query_map([], |row| {
             let created_at_str: String = row.get(4)?;
             let updated_at_str: String = row.get(5)?;
+            let source: Option<String> = row.get(6)?;
 
             let created_at = DateTime::parse_from_rfc3339(&created_at_str)
                 .map(|dt| dt.with_timezone(&Utc))
@@ -714,6 +1575,8 @@ pub fn get_all_budgets() -> Result<Vec<BudgetInfo>> {
                 monthly_budget: row.get(1)?,
                 currency: row.get(2)?,
                 alert_threshold: row.get(3)?,
+                source: BudgetSource::from_key(&source.unwrap_or_default()),
+                external_name: row.get(7)?,
                 created_at,
                 updated_at,
             })
@@ -723,17 +1586,6 @@ pub fn get_all_budgets() -> Result<Vec<BudgetInfo>> {
     Ok(budgets)
 }
 
-/// Delete budget for an account
-pub fn delete_budget(account_id: &str) -> Result<()> {
-    let db = get_connection()?;
-    let conn = db.as_ref().unwrap();
-
-    conn.execute("DELETE FROM budgets WHERE account_id = ?", params![account_id])?;
-
-    tracing::info!("Deleted budget for account {}", account_id);
-    Ok(())
-}
-
 /// Get budget status (compares budget with current costs)
 pub fn get_budget_status(account_id: &str) -> Result<Option<BudgetStatus>> {
     // Get budget
@@ -756,38 +1608,131 @@ pub fn get_budget_status(account_id: &str) -> Result<Option<BudgetStatus>> {
         .map(|cs| cs.current_month_cost)
         .unwrap_or(0.0);
 
-    // Calculate metrics
-    let percentage_used = if budget.monthly_budget > 0.0 {
-        (current_cost / budget.monthly_budget) * 100.0
-    } else {
-        0.0
-    };
-
-    let remaining = budget.monthly_budget - current_cost;
-    let alert_triggered = percentage_used >= budget.alert_threshold;
-
-    Ok(Some(BudgetStatus {
-        account_id: account_id.to_string(),
-        account_name: account.name.clone(),
-        monthly_budget: budget.monthly_budget,
+    Ok(Some(BudgetStatus::from_budget(
+        &budget,
+        &account.name,
         current_cost,
-        currency: budget.currency,
-        percentage_used,
-        remaining,
-        alert_triggered,
-    }))
+    )))
 }
 
-/// Get all budget statuses
-pub fn get_all_budget_statuses() -> Result<Vec<BudgetStatus>> {
-    let budgets = get_all_budgets()?;
-    let mut statuses = Vec::new();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    for budget in budgets {
-        if let Some(status) = get_budget_status(&budget.account_id)? {
-            statuses.push(status);
-        }
+    #[test]
+    fn test_claim_digest_period_once() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::migrations::migrate(&conn).unwrap();
+
+        assert!(claim_digest_period_on(&conn, "Platform", "2024-05-06").unwrap());
+        assert!(!claim_digest_period_on(&conn, "Platform", "2024-05-06").unwrap());
+        assert!(claim_digest_period_on(&conn, "Leadership", "2024-05-06").unwrap());
+        assert!(claim_digest_period_on(&conn, "Platform", "2024-05-13").unwrap());
+    }
+
+    #[test]
+    fn test_discovered_members() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::migrations::migrate(&conn).unwrap();
+
+        assert_eq!(
+            get_discovered_members_on(&conn, "acc", &CloudProvider::Aliyun).unwrap(),
+            None
+        );
+        let members = vec![(
+            "acc#1234567890123457".to_string(),
+            "Finance / shop".to_string(),
+        )];
+        save_discovered_members_on(&conn, "acc", &members).unwrap();
+        assert_eq!(
+            get_discovered_members_on(&conn, "acc", &CloudProvider::Aliyun).unwrap(),
+            Some(members)
+        );
+    }
+
+    #[test]
+    fn test_delete_account_removes_member_rows() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::migrations::migrate(&conn).unwrap();
+        conn.execute_batch(
+            r#"
+            INSERT INTO cloud_accounts (id, name, provider, created_at) VALUES
+                ('acc', 'Finance', 'Aliyun', '2024-01-01T00:00:00+00:00'),
+                ('acc-2', 'Other', 'Aliyun', '2024-01-01T00:00:00+00:00');
+            INSERT INTO cost_summary_cache VALUES
+                ('acc', 1.0, 1.0, 'CNY', 0.0, NULL, NULL, '2024-01-01T00:00:00+00:00'),
+                ('acc#1234567890123457', 1.0, 1.0, 'CNY', 0.0, NULL, NULL, '2024-01-01T00:00:00+00:00'),
+                ('acc-2', 1.0, 1.0, 'CNY', 0.0, NULL, NULL, '2024-01-01T00:00:00+00:00');
+            INSERT INTO cost_trend_cache VALUES
+                ('acc#1234567890123457', '2024-01-01', 1.0, 'CNY', '2024-01-01T00:00:00+00:00');
+            INSERT INTO member_discovery_cache VALUES ('acc', '[]', '2024-01-01T00:00:00+00:00');
+            INSERT INTO budgets VALUES
+                ('acc', 1000.0, 'CNY', 80.0, '2024-01-01T00:00:00+00:00', '2024-01-01T00:00:00+00:00');
+            "#,
+        )
+        .unwrap();
+
+        delete_account_rows(&conn, "acc").unwrap();
+
+        let count = |sql: &str| conn.query_row(sql, [], |row| row.get::<_, i64>(0)).unwrap();
+        assert_eq!(count("SELECT COUNT(*) FROM cloud_accounts"), 1);
+        assert_eq!(count("SELECT COUNT(*) FROM cost_summary_cache"), 1);
+        assert_eq!(count("SELECT COUNT(*) FROM cost_trend_cache"), 0);
+        assert_eq!(count("SELECT COUNT(*) FROM member_discovery_cache"), 0);
+        assert_eq!(count("SELECT COUNT(*) FROM budgets"), 0);
     }
 
-    Ok(statuses)
+    #[test]
+    fn test_failed_account_delete_keeps_data() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::migrations::migrate(&conn).unwrap();
+        conn.execute_batch(
+            r#"
+            INSERT INTO cloud_accounts (id, name, provider, created_at)
+                VALUES ('acc', 'Prod', 'AWS', '2024-01-01T00:00:00+00:00');
+            INSERT INTO cost_summary_cache
+                VALUES ('acc', 1.0, 1.0, 'USD', 0.0, NULL, NULL, '2024-01-01T00:00:00+00:00');
+            DROP TABLE budget_sources;
+            "#,
+        )
+        .unwrap();
+
+        assert!(delete_account_rows(&conn, "acc").is_err());
+
+        let count = |sql: &str| conn.query_row(sql, [], |row| row.get::<_, i64>(0)).unwrap();
+        assert_eq!(count("SELECT COUNT(*) FROM cloud_accounts"), 1);
+        assert_eq!(count("SELECT COUNT(*) FROM cost_summary_cache"), 1);
+    }
+
+    #[test]
+    fn test_delete_account_with_synced_budget() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::migrations::migrate(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO cloud_accounts (id, name, provider, created_at) VALUES ('acc', 'Prod', 'AWS', '2024-01-01T00:00:00+00:00')",
+            [],
+        )
+        .unwrap();
+        save_budget_on(
+            &conn,
+            &BudgetInfo {
+                account_id: "acc".to_string(),
+                monthly_budget: 1000.0,
+                currency: "USD".to_string(),
+                alert_threshold: 80.0,
+                source: BudgetSource::AwsBudgets,
+                external_name: Some("Monthly".to_string()),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            },
+        )
+        .unwrap();
+
+        delete_account_rows(&conn, "acc").unwrap();
+
+        let count = |sql: &str| conn.query_row(sql, [], |row| row.get::<_, i64>(0)).unwrap();
+        assert_eq!(count("SELECT COUNT(*) FROM cloud_accounts"), 0);
+        assert_eq!(count("SELECT COUNT(*) FROM budgets"), 0);
+        assert_eq!(count("SELECT COUNT(*) FROM budget_sources"), 0);
+    }
 }
@@ -0,0 +1,255 @@
+//! Scheduled report digests
+//!
+//! Sends the cost report of the last closed week or month to the recipients of each
+//! configured account group. Deliveries are recorded per schedule, so each period is
+//! sent once whether the check runs in the app's background thread or from the CLI
+//! (`cloudbridge send-digests`, e.g. from cron).
+
+use anyhow::Result;
+use chrono::{Datelike, Duration, NaiveDate, Utc};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::cloud::CloudAccount;
+use crate::config::{load_config, AppConfig, DigestFrequency, DigestSchedule, SmtpConfig};
+use crate::crypto;
+use crate::db;
+use crate::mail::{self, Email, SmtpSettings};
+use crate::report::{self, CostReport, ReportPeriod};
+use crate::secret_store;
+
+/// How often the background thread checks for due digests
+const CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+/// Latest closed period for a schedule as of `today`
+pub fn due_period(frequency: DigestFrequency, today: NaiveDate) -> ReportPeriod {
+    match frequency {
+        DigestFrequency::Weekly => {
+            let this_monday = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+            ReportPeriod::week(this_monday - Duration::days(7))
+        }
+        DigestFrequency::Monthly => {
+            let last_month = today.with_day(1).unwrap_or(today) - Duration::days(1);
+            ReportPeriod::month(&last_month.format("%Y-%m").to_string())
+                .expect("formatted month is always valid")
+        }
+    }
+}
+
+/// Connection settings with the password from the keyring
+pub fn smtp_settings(smtp: &SmtpConfig) -> Result<SmtpSettings> {
+    let credentials = match &smtp.username {
+        Some(username) if !username.is_empty() => {
+            let password = secret_store::get_smtp_password(username)?.ok_or_else(|| {
+                anyhow::anyhow!(
+                    "No SMTP password stored for {}; set it in Settings",
+                    username
+                )
+            })?;
+            Some((username.clone(), password))
+        }
+        _ => None,
+    };
+
+    Ok(SmtpSettings {
+        host: smtp.host.clone(),
+        port: smtp.port,
+        security: smtp.security,
+        credentials,
+    })
+}
+
+/// Account IDs of a schedule's group; empty means all enabled accounts
+fn group_account_ids(schedule: &DigestSchedule, accounts: &[CloudAccount]) -> Vec<String> {
+    schedule
+        .accounts
+        .iter()
+        .filter_map(|wanted| {
+            let found = accounts
+                .iter()
+                .find(|a| &a.id == wanted || a.name.eq_ignore_ascii_case(wanted));
+            if found.is_none() {
+                tracing::warn!("Digest '{}': unknown account '{}'", schedule.name, wanted);
+            }
+            found.map(|a| a.id.clone())
+        })
+        .collect()
+}
+
+fn digest_email(smtp: &SmtpConfig, schedule: &DigestSchedule, report: &CostReport) -> Email {
+    Email {
+        from: smtp.from.clone(),
+        to: schedule.recipients.clone(),
+        subject: format!(
+            "[CloudBridge] {} cost digest - {}",
+            schedule.name, report.period.title
+        ),
+        text: report::render_text(report),
+        html: report::render_html(report),
+    }
+}
+
+/// Build and send one digest for `period`
+pub fn send_digest(
+    config: &AppConfig,
+    schedule: &DigestSchedule,
+    period: &ReportPeriod,
+) -> Result<()> {
+    let smtp = config
+        .smtp
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("SMTP is not configured"))?;
+    if schedule.recipients.is_empty() {
+        anyhow::bail!("Digest '{}' has no recipients", schedule.name);
+    }
+
+    let account_ids = group_account_ids(schedule, &db::get_all_accounts()?);
+    if !schedule.accounts.is_empty() && account_ids.is_empty() {
+        anyhow::bail!("Digest '{}' matches no accounts", schedule.name);
+    }
+
+    let report = report::build_report(period, &account_ids)?;
+    mail::send(
+        &smtp_settings(smtp)?,
+        &digest_email(smtp, schedule, &report),
+    )
+}
+
+/// Send every digest whose latest period has not been delivered yet, or all of them
+/// again with `force`. Returns the names of the digests sent; fails if any failed.
+pub fn run_due_digests(force: bool) -> Result<Vec<String>> {
+    let config = load_config()?;
+    let today = Utc::now().date_naive();
+    let mut sent = Vec::new();
+    let mut failures = Vec::new();
+
+    for schedule in &config.digests {
+        let period = due_period(schedule.frequency, today);
+        // Claim the period before sending so concurrent checks cannot send it twice
+        let claimed = if force {
+            db::save_digest_delivery(&schedule.name, &period.start).map(|_| true)
+        } else {
+            db::claim_digest_period(&schedule.name, &period.start)
+        };
+        match claimed {
+            Ok(true) => {}
+            Ok(false) => continue,
+            Err(e) => {
+                tracing::error!("Failed to claim digest '{}': {}", schedule.name, e);
+                failures.push(format!("{}: {}", schedule.name, e));
+                continue;
+            }
+        }
+
+        match send_digest(&config, schedule, &period) {
+            Ok(()) => {
+                tracing::info!("Sent digest '{}' for {}", schedule.name, period.title);
+                sent.push(schedule.name.clone());
+            }
+            Err(e) => {
+                tracing::error!("Failed to send digest '{}': {}", schedule.name, e);
+                failures.push(format!("{}: {}", schedule.name, e));
+                if !force {
+                    // Otherwise the period counts as sent and is not retried
+                    if let Err(e) = db::release_digest_period(&schedule.name, &period.start) {
+                        tracing::error!(
+                            "Failed to release digest '{}' for retry: {}",
+                            schedule.name,
+                            e
+                        );
+                        failures.push(format!("{}: {}", schedule.name, e));
+                    }
+                }
+            }
+        }
+    }
+
+    if !failures.is_empty() {
+        anyhow::bail!("Digest delivery failed ({})", failures.join("; "));
+    }
+    Ok(sent)
+}
+
+/// Check for due digests in a background thread for as long as the app runs.
+/// Called once the app is unlocked; later calls do nothing, and checks are skipped
+/// while the app is locked again.
+pub fn start_background_delivery() {
+    static STARTED: AtomicBool = AtomicBool::new(false);
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    std::thread::spawn(|| loop {
+        if !crypto::is_locked() {
+            if let Err(e) = run_due_digests(false) {
+                tracing::warn!("{}", e);
+            }
+        }
+        std::thread::sleep(CHECK_INTERVAL);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SmtpSecurity;
+    use crate::mail::tests::{decoded_parts, smtp_sink};
+
+    #[test]
+    fn test_due_period() {
+        // Wednesday 2024-05-15
+        let today = NaiveDate::from_ymd_opt(2024, 5, 15).unwrap();
+
+        let weekly = due_period(DigestFrequency::Weekly, today);
+        assert_eq!(weekly.start, "2024-05-06");
+        assert_eq!(weekly.end, "2024-05-13");
+
+        let monthly = due_period(DigestFrequency::Monthly, today);
+        assert_eq!(monthly.start, "2024-04-01");
+        assert_eq!(monthly.end, "2024-05-01");
+
+        // On a Monday the week that just ended is due
+        let monday = NaiveDate::from_ymd_opt(2024, 5, 13).unwrap();
+        assert_eq!(
+            due_period(DigestFrequency::Weekly, monday).start,
+            "2024-05-06"
+        );
+    }
+
+    #[test]
+    fn test_digest_email_to_local_sink() {
+        let (port, sink) = smtp_sink();
+        let smtp = SmtpConfig {
+            host: "127.0.0.1".to_string(),
+            port,
+            security: SmtpSecurity::None,
+            username: None,
+            from: "reports@example.com".to_string(),
+        };
+        let schedule = DigestSchedule {
+            name: "Platform".to_string(),
+            frequency: DigestFrequency::Monthly,
+            recipients: vec!["platform-leads@example.com".to_string()],
+            accounts: Vec::new(),
+        };
+        let report = CostReport {
+            period: ReportPeriod::month("2024-04").unwrap(),
+            generated_at: Utc::now(),
+            accounts: Vec::new(),
+        };
+
+        let settings = smtp_settings(&smtp).unwrap();
+        mail::send(&settings, &digest_email(&smtp, &schedule, &report)).unwrap();
+        let received = sink.join().unwrap();
+
+        assert!(!received.commands.iter().any(|c| c.starts_with("AUTH")));
+        assert!(received
+            .commands
+            .contains(&"RCPT TO:<platform-leads@example.com>".to_string()));
+        assert!(received
+            .data
+            .contains("Subject: [CloudBridge] Platform cost digest - April 2024"));
+        let parts = decoded_parts(&received.data);
+        assert!(parts[0].starts_with("CloudBridge cost report - April 2024"));
+        assert!(parts[1].contains("<h1>Cost Report - April 2024</h1>"));
+    }
+}
@@ -0,0 +1,804 @@
+//! Data export
+//!
+//! Writes data from the local DuckDB store to files for use in other tools.
+//!
+//! Cost exports contain up to three datasets for a set of accounts and a date range:
+//! a per-account summary, a per-service breakdown and a daily trend. Each dataset has a
+//! fixed column schema (see [`ExportDataset::columns`]) so downstream spreadsheets and
+//! pipelines keep working across releases.
+
+use anyhow::{Context, Result};
+use duckdb::{params, Connection};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::cloud::{
+    group_costs_by_dimension, service_for_account, CloudAccount, CostDimension, CostTrend,
+    DailyCost, ServiceCost,
+};
+use crate::config::load_config;
+use crate::db;
+
+/// Export file format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    JsonLines,
+    Parquet,
+    Xlsx,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::JsonLines => "jsonl",
+            ExportFormat::Parquet => "parquet",
+            ExportFormat::Xlsx => "xlsx",
+        }
+    }
+
+    /// Parse a format name as used on the command line
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "csv" => Some(ExportFormat::Csv),
+            "jsonl" | "ndjson" | "json" => Some(ExportFormat::JsonLines),
+            "parquet" => Some(ExportFormat::Parquet),
+            "xlsx" | "excel" => Some(ExportFormat::Xlsx),
+            _ => None,
+        }
+    }
+
+    /// Infer the format from a file extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        path.extension()
+            .and_then(|e| e.to_str())
+            .and_then(Self::from_name)
+    }
+}
+
+/// Exported dataset
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportDataset {
+    /// One row per account: total and daily average for the range
+    Summary,
+    /// One row per account and service
+    Services,
+    /// One row per account and day
+    Daily,
+}
+
+/// Column value type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnKind {
+    Text,
+    Number,
+}
+
+impl ColumnKind {
+    fn sql_type(&self) -> &'static str {
+        match self {
+            ColumnKind::Text => "VARCHAR",
+            ColumnKind::Number => "DOUBLE",
+        }
+    }
+}
+
+const SUMMARY_COLUMNS: &[(&str, ColumnKind)] = &[
+    ("account_id", ColumnKind::Text),
+    ("account_name", ColumnKind::Text),
+    ("provider", ColumnKind::Text),
+    ("start_date", ColumnKind::Text),
+    ("end_date", ColumnKind::Text),
+    ("currency", ColumnKind::Text),
+    ("total_cost", ColumnKind::Number),
+    ("daily_average", ColumnKind::Number),
+    ("top_service", ColumnKind::Text),
+];
+
+const SERVICES_COLUMNS: &[(&str, ColumnKind)] = &[
+    ("account_id", ColumnKind::Text),
+    ("account_name", ColumnKind::Text),
+    ("provider", ColumnKind::Text),
+    ("service", ColumnKind::Text),
+    ("currency", ColumnKind::Text),
+    ("cost", ColumnKind::Number),
+    ("share_percent", ColumnKind::Number),
+];
+
+const DAILY_COLUMNS: &[(&str, ColumnKind)] = &[
+    ("account_id", ColumnKind::Text),
+    ("account_name", ColumnKind::Text),
+    ("provider", ColumnKind::Text),
+    ("date", ColumnKind::Text),
+    ("currency", ColumnKind::Text),
+    ("cost", ColumnKind::Number),
+];
+
+impl ExportDataset {
+    pub const ALL: [ExportDataset; 3] = [
+        ExportDataset::Summary,
+        ExportDataset::Services,
+        ExportDataset::Daily,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExportDataset::Summary => "summary",
+            ExportDataset::Services => "services",
+            ExportDataset::Daily => "daily",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|d| d.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Column names and types in export order. These are part of the export contract:
+    /// add new columns at the end and never rename or reorder existing ones.
+    pub fn columns(&self) -> &'static [(&'static str, ColumnKind)] {
+        match self {
+            ExportDataset::Summary => SUMMARY_COLUMNS,
+            ExportDataset::Services => SERVICES_COLUMNS,
+            ExportDataset::Daily => DAILY_COLUMNS,
+        }
+    }
+
+    fn table(&self) -> &'static str {
+        match self {
+            ExportDataset::Summary => "export_summary",
+            ExportDataset::Services => "export_services",
+            ExportDataset::Daily => "export_daily",
+        }
+    }
+
+    fn order_by(&self) -> &'static str {
+        match self {
+            ExportDataset::Summary => "provider, account_name",
+            ExportDataset::Services => "provider, account_name, cost DESC, service",
+            ExportDataset::Daily => "provider, account_name, date",
+        }
+    }
+}
+
+/// What to export and where
+#[derive(Debug, Clone)]
+pub struct ExportRequest {
+    /// Account IDs to include; empty means all enabled accounts
+    pub account_ids: Vec<String>,
+    /// Inclusive start date (YYYY-MM-DD)
+    pub start_date: String,
+    /// Exclusive end date (YYYY-MM-DD)
+    pub end_date: String,
+    pub datasets: Vec<ExportDataset>,
+    pub format: ExportFormat,
+    /// Output file. XLSX writes one sheet per dataset into this file; other formats
+    /// write `<stem>-<dataset>.<ext>` next to it when more than one dataset is selected.
+    pub output: PathBuf,
+}
+
+/// Costs of one account for the export range
+#[derive(Debug, Clone)]
+pub(crate) struct AccountCosts {
+    pub account_id: String,
+    pub account_name: String,
+    pub provider: String,
+    pub currency: String,
+    pub services: Vec<ServiceCost>,
+    pub daily: Vec<DailyCost>,
+}
+
+/// Export cost summaries, service breakdowns and daily trends (see [`collect_costs`]).
+/// Accounts that fail are skipped and logged. Returns the written files.
+pub fn export_costs(request: &ExportRequest) -> Result<Vec<PathBuf>> {
+    if request.datasets.is_empty() {
+        anyhow::bail!("No datasets selected for export");
+    }
+
+    let config = load_config().unwrap_or_default();
+    let accounts: Vec<CloudAccount> = db::get_all_accounts()?
+        .into_iter()
+        .filter(|a| {
+            if request.account_ids.is_empty() {
+                a.enabled
+            } else {
+                request.account_ids.contains(&a.id)
+            }
+        })
+        .collect();
+    if accounts.is_empty() {
+        anyhow::bail!("No accounts to export");
+    }
+
+    let mut costs = Vec::new();
+    for account in &accounts {
+        crate::cur::ingest_configured(account, &config);
+
+        match collect_account_costs(account, &request.start_date, &request.end_date) {
+            Ok(account_costs) => costs.push(account_costs),
+            Err(e) => tracing::error!("Failed to get costs for {}: {}", account.name, e),
+        }
+    }
+
+    db::with_connection(|conn| write_export(conn, &costs, request))
+}
+
+/// Costs of an account for a date range
+pub(crate) struct RangeCosts {
+    /// Service totals of the range
+    pub services: Vec<ServiceCost>,
+    /// Service totals of the comparison range; empty when none was requested
+    pub previous_services: Vec<ServiceCost>,
+    /// Daily totals of the range; None when the provider could not return them
+    pub trend: Option<CostTrend>,
+}
+
+/// Service totals and daily trend of an account for a date range, plus the service
+/// totals of an optional comparison range.
+///
+/// Accounts with ingested CUR data for the range (see [`crate::cur::ingest_configured`])
+/// are read from `cost_data`; other accounts are fetched from the provider, where a
+/// failed trend request only loses the trend.
+pub(crate) fn collect_costs(
+    account: &CloudAccount,
+    start_date: &str,
+    end_date: &str,
+    previous: Option<(&str, &str)>,
+) -> Result<RangeCosts> {
+    if let Some(trend) = db::get_cost_trend_from_cost_data(&account.id, start_date, end_date)? {
+        let previous_services = match previous {
+            Some((start, end)) => db::get_service_costs_from_cost_data(&account.id, start, end)?,
+            None => Vec::new(),
+        };
+        return Ok(RangeCosts {
+            services: db::get_service_costs_from_cost_data(&account.id, start_date, end_date)?,
+            previous_services,
+            trend: Some(trend),
+        });
+    }
+
+    let service = service_for_account(account)?;
+    let services = |start: &str, end: &str| -> Result<Vec<ServiceCost>> {
+        let costs = service.get_cost_breakdown(start, end, &CostDimension::Service)?;
+        Ok(group_costs_by_dimension(&costs, &CostDimension::Service))
+    };
+    let current = services(start_date, end_date)?;
+    let previous_services = match previous {
+        Some((start, end)) => services(start, end)?,
+        None => Vec::new(),
+    };
+    let trend = match service.get_cost_trend(start_date, end_date) {
+        Ok(trend) => Some(trend),
+        Err(e) => {
+            tracing::warn!("Failed to get cost trend for {}: {}", account.name, e);
+            None
+        }
+    };
+    Ok(RangeCosts {
+        services: current,
+        previous_services,
+        trend,
+    })
+}
+
+/// Get the service breakdown and daily trend of an account for the export range
+fn collect_account_costs(
+    account: &CloudAccount,
+    start_date: &str,
+    end_date: &str,
+) -> Result<AccountCosts> {
+    let RangeCosts {
+        services, trend, ..
+    } = collect_costs(account, start_date, end_date, None)?;
+
+    let currency = services
+        .first()
+        .map(|s| s.currency.clone())
+        .or_else(|| trend.as_ref().map(|t| t.currency.clone()))
+        .unwrap_or_else(|| "USD".to_string());
+
+    Ok(AccountCosts {
+        account_id: account.id.clone(),
+        account_name: account.name.clone(),
+        provider: account.provider.display_name().to_string(),
+        currency,
+        services,
+        daily: trend.map(|t| t.daily_costs).unwrap_or_default(),
+    })
+}
+
+/// Number of days the range covers so far, at least one
+fn elapsed_days(start_date: &str, end_date: &str) -> i64 {
+    use chrono::NaiveDate;
+
+    let parse = |d: &str| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok();
+    let tomorrow = chrono::Utc::now().date_naive() + chrono::Duration::days(1);
+    match (parse(start_date), parse(end_date)) {
+        (Some(start), Some(end)) => (end.min(tomorrow) - start).num_days().max(1),
+        _ => 1,
+    }
+}
+
+/// Load the costs into temporary tables and write the selected datasets
+pub(crate) fn write_export(
+    conn: &Connection,
+    costs: &[AccountCosts],
+    request: &ExportRequest,
+) -> Result<Vec<PathBuf>> {
+    load_export_tables(conn, costs, &request.start_date, &request.end_date)?;
+
+    if request.format == ExportFormat::Xlsx {
+        write_xlsx(conn, &request.datasets, &request.output)?;
+        return Ok(vec![request.output.clone()]);
+    }
+
+    let mut paths = Vec::new();
+    for dataset in &request.datasets {
+        let path = if request.datasets.len() == 1 {
+            request.output.clone()
+        } else {
+            dataset_path(&request.output, *dataset, request.format)
+        };
+        write_dataset(conn, *dataset, request.format, &path)?;
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+/// `<dir>/<stem>-<dataset>.<ext>` for multi-dataset exports
+fn dataset_path(output: &Path, dataset: ExportDataset, format: ExportFormat) -> PathBuf {
+    let stem = output
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("cloudbridge-export");
+    output.with_file_name(format!(
+        "{}-{}.{}",
+        stem,
+        dataset.name(),
+        format.extension()
+    ))
+}
+
+fn load_export_tables(
+    conn: &Connection,
+    costs: &[AccountCosts],
+    start_date: &str,
+    end_date: &str,
+) -> Result<()> {
+    for dataset in ExportDataset::ALL {
+        let columns = dataset
+            .columns()
+            .iter()
+            .map(|(name, kind)| format!("{} {}", name, kind.sql_type()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        conn.execute(
+            &format!(
+                "CREATE OR REPLACE TEMP TABLE {} ({})",
+                dataset.table(),
+                columns
+            ),
+            [],
+        )?;
+    }
+
+    let days = elapsed_days(start_date, end_date) as f64;
+    let mut summary =
+        conn.prepare("INSERT INTO export_summary VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)")?;
+    let mut services = conn.prepare("INSERT INTO export_services VALUES (?, ?, ?, ?, ?, ?, ?)")?;
+    let mut daily = conn.prepare("INSERT INTO export_daily VALUES (?, ?, ?, ?, ?, ?)")?;
+
+    for account in costs {
+        let services_total: f64 = account.services.iter().map(|s| s.amount).sum();
+        let total = if account.services.is_empty() {
+            account.daily.iter().map(|d| d.amount).sum()
+        } else {
+            services_total
+        };
+        let top_service = account
+            .services
+            .iter()
+            .max_by(|a, b| a.amount.total_cmp(&b.amount))
+            .map(|s| s.service.clone());
+
+        summary.execute(params![
+            account.account_id,
+            account.account_name,
+            account.provider,
+            start_date,
+            end_date,
+            account.currency,
+            total,
+            total / days,
+            top_service,
+        ])?;
+
+        for service in &account.services {
+            let share = if services_total > 0.0 {
+                service.amount / services_total * 100.0
+            } else {
+                0.0
+            };
+            services.execute(params![
+                account.account_id,
+                account.account_name,
+                account.provider,
+                service.service,
+                service.currency,
+                service.amount,
+                share,
+            ])?;
+        }
+
+        for day in &account.daily {
+            daily.execute(params![
+                account.account_id,
+                account.account_name,
+                account.provider,
+                day.date,
+                account.currency,
+                day.amount,
+            ])?;
+        }
+    }
+
+    Ok(())
+}
+
+fn select_sql(dataset: ExportDataset) -> String {
+    let columns = dataset
+        .columns()
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!(
+        "SELECT {} FROM {} ORDER BY {}",
+        columns,
+        dataset.table(),
+        dataset.order_by()
+    )
+}
+
+fn write_dataset(
+    conn: &Connection,
+    dataset: ExportDataset,
+    format: ExportFormat,
+    path: &Path,
+) -> Result<()> {
+    let copy_format = match format {
+        ExportFormat::Csv => "FORMAT CSV, HEADER",
+        ExportFormat::Parquet => "FORMAT PARQUET",
+        ExportFormat::JsonLines => return write_json_lines(conn, dataset, path),
+        ExportFormat::Xlsx => return write_xlsx(conn, &[dataset], path),
+    };
+    let path_str = path
+        .to_str()
+        .ok_or_else(|| anyhow::anyhow!("Unsupported export path: {}", path.display()))?;
+
+    let rows = conn.execute(
+        &format!(
+            "COPY ({}) TO '{}' ({})",
+            select_sql(dataset),
+            path_str.replace('\'', "''"),
+            copy_format
+        ),
+        [],
+    )?;
+    tracing::info!(
+        "Exported {} {} rows to {}",
+        rows,
+        dataset.name(),
+        path.display()
+    );
+    Ok(())
+}
+
+/// Cell value read back from an export table
+enum Cell {
+    Null,
+    Text(String),
+    Number(f64),
+}
+
+impl Cell {
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            Cell::Null => serde_json::Value::Null,
+            Cell::Text(s) => serde_json::Value::String(s.clone()),
+            Cell::Number(n) => serde_json::Number::from_f64(*n)
+                .map(serde_json::Value::Number)
+                .unwrap_or(serde_json::Value::Null),
+        }
+    }
+}
+
+fn query_rows(conn: &Connection, dataset: ExportDataset) -> Result<Vec<Vec<Cell>>> {
+    let columns = dataset.columns();
+    let mut stmt = conn.prepare(&select_sql(dataset))?;
+    let rows = stmt
+        .query_map([], |row| {
+            columns
+                .iter()
+                .enumerate()
+                .map(|(i, (_, kind))| {
+                    Ok(match kind {
+                        ColumnKind::Text => row
+                            .get::<_, Option<String>>(i)?
+                            .map(Cell::Text)
+                            .unwrap_or(Cell::Null),
+                        ColumnKind::Number => row
+                            .get::<_, Option<f64>>(i)?
+                            .map(Cell::Number)
+                            .unwrap_or(Cell::Null),
+                    })
+                })
+                .collect::<Result<Vec<_>, duckdb::Error>>()
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+/// Write one JSON object per line, keys in schema order
+fn write_json_lines(conn: &Connection, dataset: ExportDataset, path: &Path) -> Result<()> {
+    let rows = query_rows(conn, dataset)?;
+    let file = std::fs::File::create(path)
+        .with_context(|| format!("Failed to create {}", path.display()))?;
+    let mut writer = std::io::BufWriter::new(file);
+
+    for row in &rows {
+        let fields = dataset
+            .columns()
+            .iter()
+            .zip(row)
+            .map(|((name, _), cell)| {
+                format!("{}:{}", serde_json::Value::from(*name), cell.to_json())
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        writeln!(writer, "{{{}}}", fields)?;
+    }
+    writer.flush()?;
+
+    tracing::info!(
+        "Exported {} {} rows to {}",
+        rows.len(),
+        dataset.name(),
+        path.display()
+    );
+    Ok(())
+}
+
+/// Write an XLSX workbook with one sheet per dataset
+fn write_xlsx(conn: &Connection, datasets: &[ExportDataset], path: &Path) -> Result<()> {
+    use rust_xlsxwriter::{Format, Workbook};
+
+    let header = Format::new().set_bold();
+    let amount = Format::new().set_num_format("0.00");
+    let mut workbook = Workbook::new();
+
+    for dataset in datasets {
+        let sheet = workbook.add_worksheet();
+        sheet.set_name(dataset.name())?;
+
+        for (col, (name, _)) in dataset.columns().iter().enumerate() {
+            sheet.write_string_with_format(0, col as u16, *name, &header)?;
+        }
+        for (row_index, row) in query_rows(conn, *dataset)?.iter().enumerate() {
+            let row_num = row_index as u32 + 1;
+            for (col, cell) in row.iter().enumerate() {
+                match cell {
+                    Cell::Null => {}
+                    Cell::Text(s) => {
+                        sheet.write_string(row_num, col as u16, s)?;
+                    }
+                    Cell::Number(n) => {
+                        sheet.write_number_with_format(row_num, col as u16, *n, &amount)?;
+                    }
+                }
+            }
+        }
+        sheet.autofit();
+    }
+
+    workbook
+        .save(path)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    tracing::info!("Exported workbook to {}", path.display());
+    Ok(())
+}
+
+/// Refresh FOCUS rows for all enabled accounts and export them as a FOCUS dataset.
+///
+/// FOCUS rows of accounts with a CUR directory come from the ingested line items; other
+/// accounts are fetched from the provider, and skipped (and logged) when that fails.
+/// Returns the number of exported rows.
+pub fn export_focus(path: &Path, start_date: &str, end_date: &str) -> Result<usize> {
+    let config = load_config().unwrap_or_default();
+    let mut account_ids = Vec::new();
+
+    for account in db::get_all_accounts()? {
+        if !account.enabled {
+            continue;
+        }
+
+        if crate::cur::ingest_configured(&account, &config).is_none() {
+            let result = service_for_account(&account)
+                .and_then(|service| service.get_focus_costs(start_date, end_date))
+                .and_then(|costs| db::save_focus_costs(&account.id, start_date, end_date, &costs));
+            if let Err(e) = result {
+                tracing::error!("Failed to get FOCUS costs for {}: {}", account.name, e);
+                continue;
+            }
+        }
+
+        account_ids.push(account.id);
+    }
+
+    db::export_focus_dataset(&account_ids, start_date, end_date, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_costs() -> Vec<AccountCosts> {
+        vec![AccountCosts {
+            account_id: "acc-1".to_string(),
+            account_name: "Prod".to_string(),
+            provider: "AWS".to_string(),
+            currency: "USD".to_string(),
+            services: vec![
+                ServiceCost {
+                    service: "Amazon S3".to_string(),
+                    amount: 25.0,
+                    currency: "USD".to_string(),
+                },
+                ServiceCost {
+                    service: "Amazon EC2".to_string(),
+                    amount: 75.0,
+                    currency: "USD".to_string(),
+                },
+            ],
+            daily: vec![
+                DailyCost {
+                    date: "2024-05-01".to_string(),
+                    amount: 60.0,
+                },
+                DailyCost {
+                    date: "2024-05-02".to_string(),
+                    amount: 40.0,
+                },
+            ],
+        }]
+    }
+
+    fn request(dir: &Path, file: &str, format: ExportFormat) -> ExportRequest {
+        ExportRequest {
+            account_ids: Vec::new(),
+            start_date: "2024-05-01".to_string(),
+            end_date: "2024-05-03".to_string(),
+            datasets: ExportDataset::ALL.to_vec(),
+            format,
+            output: dir.join(file),
+        }
+    }
+
+    fn header(dataset: ExportDataset) -> String {
+        dataset
+            .columns()
+            .iter()
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// The exported column schemas are a stable contract; update consumers and the
+    /// CHANGELOG before changing these.
+    #[test]
+    fn test_column_schemas() {
+        assert_eq!(
+            header(ExportDataset::Summary),
+            "account_id,account_name,provider,start_date,end_date,currency,total_cost,daily_average,top_service"
+        );
+        assert_eq!(
+            header(ExportDataset::Services),
+            "account_id,account_name,provider,service,currency,cost,share_percent"
+        );
+        assert_eq!(
+            header(ExportDataset::Daily),
+            "account_id,account_name,provider,date,currency,cost"
+        );
+        assert_eq!(
+            ExportFormat::from_path(Path::new("costs.JSONL")),
+            Some(ExportFormat::JsonLines)
+        );
+        assert_eq!(
+            ExportDataset::from_name("Daily"),
+            Some(ExportDataset::Daily)
+        );
+    }
+
+    #[test]
+    fn test_export_csv_and_json_lines() {
+        let dir = crate::config::scratch_dir("export").unwrap();
+        let conn = Connection::open_in_memory().unwrap();
+
+        let paths = write_export(
+            &conn,
+            &sample_costs(),
+            &request(&dir, "costs.csv", ExportFormat::Csv),
+        )
+        .unwrap();
+        assert_eq!(paths.len(), 3);
+        assert!(paths[0].ends_with("costs-summary.csv"));
+
+        let summary = std::fs::read_to_string(&paths[0]).unwrap();
+        let lines: Vec<&str> = summary.lines().collect();
+        assert_eq!(lines[0], header(ExportDataset::Summary));
+        assert_eq!(
+            lines[1],
+            "acc-1,Prod,AWS,2024-05-01,2024-05-03,USD,100.0,50.0,Amazon EC2"
+        );
+
+        let services = std::fs::read_to_string(&paths[1]).unwrap();
+        let lines: Vec<&str> = services.lines().collect();
+        assert_eq!(lines[0], header(ExportDataset::Services));
+        assert_eq!(lines[1], "acc-1,Prod,AWS,Amazon EC2,USD,75.0,75.0");
+
+        let mut json_request = request(&dir, "daily.jsonl", ExportFormat::JsonLines);
+        json_request.datasets = vec![ExportDataset::Daily];
+        let paths = write_export(&conn, &sample_costs(), &json_request).unwrap();
+        assert_eq!(paths, vec![dir.join("daily.jsonl")]);
+
+        let daily = std::fs::read_to_string(&paths[0]).unwrap();
+        let first = daily.lines().next().unwrap();
+        assert_eq!(
+            first,
+            r#"{"account_id":"acc-1","account_name":"Prod","provider":"AWS","date":"2024-05-01","currency":"USD","cost":60.0}"#
+        );
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_export_parquet_and_xlsx() {
+        let dir = crate::config::scratch_dir("export").unwrap();
+        let conn = Connection::open_in_memory().unwrap();
+
+        let paths = write_export(
+            &conn,
+            &sample_costs(),
+            &request(&dir, "costs.parquet", ExportFormat::Parquet),
+        )
+        .unwrap();
+        for (path, dataset) in paths.iter().zip(ExportDataset::ALL) {
+            let mut stmt = conn
+                .prepare(&format!(
+                    "SELECT column_name FROM (DESCRIBE SELECT * FROM read_parquet('{}'))",
+                    path.display()
+                ))
+                .unwrap();
+            let columns = stmt
+                .query_map([], |row| row.get::<_, String>(0))
+                .unwrap()
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+            assert_eq!(columns.join(","), header(dataset));
+        }
+
+        let paths = write_export(
+            &conn,
+            &sample_costs(),
+            &request(&dir, "costs.xlsx", ExportFormat::Xlsx),
+        )
+        .unwrap();
+        assert_eq!(paths, vec![dir.join("costs.xlsx")]);
+        let bytes = std::fs::read(&paths[0]).unwrap();
+        assert!(bytes.starts_with(b"PK"));
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
@@ -0,0 +1,490 @@
+//! SMTP delivery
+//!
+//! Minimal SMTP client for sending report emails: EHLO, STARTTLS or implicit TLS,
+//! AUTH PLAIN/LOGIN and a single message per connection. TLS uses the same rustls
+//! stack as the HTTP client.
+
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::config::SmtpSecurity;
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Connection settings with the resolved password
+#[derive(Debug, Clone)]
+pub struct SmtpSettings {
+    pub host: String,
+    pub port: u16,
+    pub security: SmtpSecurity,
+    /// Username and password; no authentication when unset
+    pub credentials: Option<(String, String)>,
+}
+
+/// Email with an HTML body and a plain text alternative
+#[derive(Debug, Clone)]
+pub struct Email {
+    pub from: String,
+    pub to: Vec<String>,
+    pub subject: String,
+    pub text: String,
+    pub html: String,
+}
+
+impl Email {
+    /// Format as an RFC 5322 message with a multipart/alternative body
+    fn to_mime(&self) -> String {
+        let boundary = format!("cloudbridge-{}", uuid::Uuid::new_v4().simple());
+        let domain = self.from.rsplit('@').next().unwrap_or("cloudbridge.local");
+
+        let mut message = String::new();
+        message.push_str(&format!("From: {}\r\n", self.from));
+        message.push_str(&format!("To: {}\r\n", self.to.join(", ")));
+        message.push_str(&format!("Subject: {}\r\n", encode_header(&self.subject)));
+        message.push_str(&format!("Date: {}\r\n", chrono::Utc::now().to_rfc2822()));
+        message.push_str(&format!(
+            "Message-ID: <{}@{}>\r\n",
+            uuid::Uuid::new_v4(),
+            domain.trim_end_matches('>')
+        ));
+        message.push_str("MIME-Version: 1.0\r\n");
+        message.push_str(&format!(
+            "Content-Type: multipart/alternative; boundary=\"{}\"\r\n\r\n",
+            boundary
+        ));
+
+        for (content_type, body) in [("text/plain", &self.text), ("text/html", &self.html)] {
+            message.push_str(&format!("--{}\r\n", boundary));
+            message.push_str(&format!(
+                "Content-Type: {}; charset=utf-8\r\nContent-Transfer-Encoding: base64\r\n\r\n",
+                content_type
+            ));
+            // Base64 keeps lines short and avoids any line starting with a dot
+            let encoded = BASE64.encode(body.as_bytes());
+            for chunk in encoded.as_bytes().chunks(76) {
+                message.push_str(std::str::from_utf8(chunk).unwrap_or_default());
+                message.push_str("\r\n");
+            }
+        }
+        message.push_str(&format!("--{}--\r\n", boundary));
+        message
+    }
+}
+
+/// RFC 2047 encoded-word for non-ASCII header values
+fn encode_header(value: &str) -> String {
+    if value.is_ascii() {
+        value.to_string()
+    } else {
+        format!("=?UTF-8?B?{}?=", BASE64.encode(value.as_bytes()))
+    }
+}
+
+/// Address part of "Name <user@example.com>" or a bare address
+fn envelope_address(address: &str) -> &str {
+    match (address.find('<'), address.rfind('>')) {
+        (Some(start), Some(end)) if start < end => &address[start + 1..end],
+        _ => address.trim(),
+    }
+}
+
+enum Stream {
+    Plain(TcpStream),
+    Tls(Box<rustls::StreamOwned<rustls::ClientConnection, TcpStream>>),
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            Stream::Plain(s) => s.read(buf),
+            Stream::Tls(s) => s.read(buf),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Stream::Plain(s) => s.write(buf),
+            Stream::Tls(s) => s.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Stream::Plain(s) => s.flush(),
+            Stream::Tls(s) => s.flush(),
+        }
+    }
+}
+
+fn tls_config() -> Result<Arc<rustls::ClientConfig>> {
+    // A configured CA bundle replaces the built-in roots, as for provider requests
+    let ca_bundle = crate::config::load_config()
+        .ok()
+        .and_then(|config| config.network.ca_bundle);
+    let roots = match ca_bundle {
+        Some(path) => {
+            let mut roots = rustls::RootCertStore::empty();
+            roots.add_parsable_certificates(crate::cloud::http::load_ca_bundle(&path)?);
+            roots
+        }
+        None => rustls::RootCertStore {
+            roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+        },
+    };
+    let config = rustls::ClientConfig::builder_with_provider(Arc::new(
+        rustls::crypto::ring::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()?
+    .with_root_certificates(roots)
+    .with_no_client_auth();
+    Ok(Arc::new(config))
+}
+
+fn wrap_tls(stream: TcpStream, host: &str) -> Result<Stream> {
+    let server_name = rustls::pki_types::ServerName::try_from(host.to_string())
+        .with_context(|| format!("Invalid SMTP host name: {}", host))?;
+    let connection = rustls::ClientConnection::new(tls_config()?, server_name)?;
+    Ok(Stream::Tls(Box::new(rustls::StreamOwned::new(
+        connection, stream,
+    ))))
+}
+
+/// SMTP reply: code and text lines
+struct Reply {
+    code: u16,
+    lines: Vec<String>,
+}
+
+struct SmtpConnection {
+    stream: Option<Stream>,
+    buffer: Vec<u8>,
+}
+
+impl SmtpConnection {
+    fn stream(&mut self) -> &mut Stream {
+        self.stream.as_mut().expect("SMTP stream is always set")
+    }
+
+    fn read_line(&mut self) -> Result<String> {
+        loop {
+            if let Some(pos) = self.buffer.windows(2).position(|w| w == b"\r\n") {
+                let line: Vec<u8> = self.buffer.drain(..pos + 2).collect();
+                return Ok(String::from_utf8_lossy(&line[..pos]).into_owned());
+            }
+            let mut chunk = [0u8; 1024];
+            let read = self.stream().read(&mut chunk)?;
+            if read == 0 {
+                anyhow::bail!("SMTP server closed the connection");
+            }
+            self.buffer.extend_from_slice(&chunk[..read]);
+        }
+    }
+
+    fn read_reply(&mut self) -> Result<Reply> {
+        let mut lines = Vec::new();
+        loop {
+            let line = self.read_line()?;
+            let code: u16 = line
+                .get(..3)
+                .and_then(|c| c.parse().ok())
+                .ok_or_else(|| anyhow::anyhow!("Malformed SMTP reply: {}", line))?;
+            let last = line.as_bytes().get(3) != Some(&b'-');
+            lines.push(line.get(4..).unwrap_or_default().to_string());
+            if last {
+                return Ok(Reply { code, lines });
+            }
+        }
+    }
+
+    /// Send a command and require one of the expected reply codes.
+    /// `shown` replaces the command in errors so credentials are not echoed.
+    fn command(&mut self, command: &str, expected: &[u16], shown: Option<&str>) -> Result<Reply> {
+        self.stream().write_all(command.as_bytes())?;
+        self.stream().write_all(b"\r\n")?;
+        self.stream().flush()?;
+        let reply = self.read_reply()?;
+        if !expected.contains(&reply.code) {
+            anyhow::bail!(
+                "SMTP server rejected {}: {} {}",
+                shown.unwrap_or(command),
+                reply.code,
+                reply.lines.join(" ")
+            );
+        }
+        Ok(reply)
+    }
+
+    fn ehlo(&mut self) -> Result<Vec<String>> {
+        let reply = self.command("EHLO cloudbridge", &[250], None)?;
+        Ok(reply
+            .lines
+            .into_iter()
+            .skip(1)
+            .map(|l| l.to_uppercase())
+            .collect())
+    }
+}
+
+/// Send an email
+pub fn send(settings: &SmtpSettings, email: &Email) -> Result<()> {
+    if email.to.is_empty() {
+        anyhow::bail!("Email has no recipients");
+    }
+
+    let address = (settings.host.as_str(), settings.port)
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("Cannot resolve SMTP host {}", settings.host))?;
+    // Only a local relay may receive credentials over an unencrypted connection
+    if settings.security == SmtpSecurity::None
+        && settings.credentials.is_some()
+        && !address.ip().is_loopback()
+    {
+        anyhow::bail!(
+            "Refusing to send SMTP credentials to {} without TLS; choose TLS or STARTTLS",
+            settings.host
+        );
+    }
+    let tcp = TcpStream::connect_timeout(&address, TIMEOUT)
+        .with_context(|| format!("Failed to connect to {}:{}", settings.host, settings.port))?;
+    tcp.set_read_timeout(Some(TIMEOUT))?;
+    tcp.set_write_timeout(Some(TIMEOUT))?;
+
+    let stream = match settings.security {
+        SmtpSecurity::Tls => wrap_tls(tcp, &settings.host)?,
+        SmtpSecurity::StartTls | SmtpSecurity::None => Stream::Plain(tcp),
+    };
+    let mut conn = SmtpConnection {
+        stream: Some(stream),
+        buffer: Vec::new(),
+    };
+
+    let greeting = conn.read_reply()?;
+    if greeting.code != 220 {
+        anyhow::bail!("SMTP server not ready: {}", greeting.lines.join(" "));
+    }
+    let mut capabilities = conn.ehlo()?;
+
+    if settings.security == SmtpSecurity::StartTls {
+        if !capabilities.iter().any(|c| c == "STARTTLS") {
+            anyhow::bail!("SMTP server {} does not support STARTTLS", settings.host);
+        }
+        conn.command("STARTTLS", &[220], None)?;
+        let Some(Stream::Plain(tcp)) = conn.stream.take() else {
+            anyhow::bail!("STARTTLS on an encrypted connection");
+        };
+        conn.stream = Some(wrap_tls(tcp, &settings.host)?);
+        conn.buffer.clear();
+        capabilities = conn.ehlo()?;
+    }
+
+    if let Some((username, password)) = &settings.credentials {
+        let auth = capabilities
+            .iter()
+            .find(|c| c.starts_with("AUTH"))
+            .cloned()
+            .unwrap_or_default();
+        if auth.split_whitespace().any(|m| m == "PLAIN") {
+            let token = BASE64.encode(format!("\0{}\0{}", username, password));
+            conn.command(&format!("AUTH PLAIN {}", token), &[235], Some("AUTH PLAIN"))?;
+        } else if auth.split_whitespace().any(|m| m == "LOGIN") {
+            conn.command("AUTH LOGIN", &[334], None)?;
+            conn.command(
+                &BASE64.encode(username),
+                &[334],
+                Some("AUTH LOGIN username"),
+            )?;
+            conn.command(
+                &BASE64.encode(password),
+                &[235],
+                Some("AUTH LOGIN password"),
+            )?;
+        } else {
+            anyhow::bail!(
+                "SMTP server {} offers no supported AUTH mechanism (PLAIN or LOGIN)",
+                settings.host
+            );
+        }
+    }
+
+    conn.command(
+        &format!("MAIL FROM:<{}>", envelope_address(&email.from)),
+        &[250],
+        None,
+    )?;
+    for recipient in &email.to {
+        conn.command(
+            &format!("RCPT TO:<{}>", envelope_address(recipient)),
+            &[250, 251],
+            None,
+        )?;
+    }
+    conn.command("DATA", &[354], None)?;
+    let message = email.to_mime();
+    conn.stream().write_all(message.as_bytes())?;
+    conn.command(".", &[250], Some("message"))?;
+    // The message is accepted at this point; a failed QUIT does not matter
+    let _ = conn.command("QUIT", &[221], None);
+
+    tracing::info!(
+        "Sent '{}' to {} recipient(s)",
+        email.subject,
+        email.to.len()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+
+    /// What a local SMTP sink received
+    #[derive(Debug, Default)]
+    pub(crate) struct Received {
+        pub commands: Vec<String>,
+        pub data: String,
+    }
+
+    /// Local SMTP sink accepting one session. Advertises AUTH PLAIN LOGIN but not STARTTLS.
+    pub(crate) fn smtp_sink() -> (u16, std::thread::JoinHandle<Received>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let handle = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut received = Received::default();
+            stream.write_all(b"220 sink ESMTP\r\n").unwrap();
+
+            let mut line = String::new();
+            loop {
+                line.clear();
+                if reader.read_line(&mut line).unwrap() == 0 {
+                    break;
+                }
+                let command = line.trim_end().to_string();
+                received.commands.push(command.clone());
+                let reply: &[u8] = match command.split(' ').next().unwrap_or_default() {
+                    "EHLO" => b"250-sink\r\n250-AUTH PLAIN LOGIN\r\n250 8BITMIME\r\n",
+                    "AUTH" => b"235 Authenticated\r\n",
+                    "DATA" => {
+                        stream.write_all(b"354 Go ahead\r\n").unwrap();
+                        loop {
+                            line.clear();
+                            reader.read_line(&mut line).unwrap();
+                            if line == ".\r\n" {
+                                break;
+                            }
+                            received.data.push_str(&line);
+                        }
+                        b"250 Queued\r\n"
+                    }
+                    "QUIT" => {
+                        stream.write_all(b"221 Bye\r\n").unwrap();
+                        break;
+                    }
+                    _ => b"250 OK\r\n",
+                };
+                stream.write_all(reply).unwrap();
+            }
+            received
+        });
+
+        (port, handle)
+    }
+
+    /// Decode the base64 body parts of a received message
+    pub(crate) fn decoded_parts(data: &str) -> Vec<String> {
+        data.split("\r\n\r\n")
+            .skip(2)
+            .map(|part| {
+                let encoded: String = part.lines().take_while(|l| !l.starts_with("--")).collect();
+                String::from_utf8(BASE64.decode(encoded).unwrap_or_default()).unwrap_or_default()
+            })
+            .filter(|p| !p.is_empty())
+            .collect()
+    }
+
+    fn email() -> Email {
+        Email {
+            from: "CloudBridge <reports@example.com>".to_string(),
+            to: vec![
+                "finance@example.com".to_string(),
+                "cto@example.com".to_string(),
+            ],
+            subject: "Cost digest – May 2024".to_string(),
+            text: "Total: 1,500.00 USD".to_string(),
+            html: "<p>.leading dot</p>".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_send_with_auth_to_local_sink() {
+        // Plain AUTH is allowed only because the sink is on the loopback interface
+        let (port, sink) = smtp_sink();
+        let settings = SmtpSettings {
+            host: "127.0.0.1".to_string(),
+            port,
+            security: SmtpSecurity::None,
+            credentials: Some(("reports".to_string(), "s3cret".to_string())),
+        };
+
+        send(&settings, &email()).unwrap();
+        let received = sink.join().unwrap();
+
+        let auth = BASE64.encode("\0reports\0s3cret");
+        assert!(received.commands.contains(&format!("AUTH PLAIN {}", auth)));
+        assert!(received
+            .commands
+            .contains(&"MAIL FROM:<reports@example.com>".to_string()));
+        assert!(received
+            .commands
+            .contains(&"RCPT TO:<cto@example.com>".to_string()));
+        assert!(received
+            .data
+            .contains("To: finance@example.com, cto@example.com"));
+        assert!(received.data.contains("Subject: =?UTF-8?B?"));
+        let parts = decoded_parts(&received.data);
+        assert_eq!(parts, vec!["Total: 1,500.00 USD", "<p>.leading dot</p>"]);
+    }
+
+    #[test]
+    fn test_starttls_required() {
+        let (port, sink) = smtp_sink();
+        let settings = SmtpSettings {
+            host: "127.0.0.1".to_string(),
+            port,
+            security: SmtpSecurity::StartTls,
+            credentials: Some(("reports".to_string(), "s3cret".to_string())),
+        };
+
+        let err = send(&settings, &email()).unwrap_err();
+        assert!(err.to_string().contains("does not support STARTTLS"));
+        // Credentials are never sent over the unencrypted connection
+        drop(err);
+        let received = sink.join().unwrap();
+        assert!(!received.commands.iter().any(|c| c.starts_with("AUTH")));
+    }
+
+    #[test]
+    fn test_credentials_require_tls_for_remote_hosts() {
+        let settings = SmtpSettings {
+            // TEST-NET-1: rejected before any connection is attempted
+            host: "192.0.2.1".to_string(),
+            port: 25,
+            security: SmtpSecurity::None,
+            credentials: Some(("reports".to_string(), "s3cret".to_string())),
+        };
+
+        let err = send(&settings, &email()).unwrap_err();
+        assert!(err.to_string().contains("without TLS"));
+    }
+}
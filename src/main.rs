@@ -1,9 +1,22 @@
 mod app;
+mod backup;
+mod cache;
+mod cli;
 mod cloud;
 mod config;
 mod crypto;
+mod cur;
 mod db;
+mod digest;
+mod export;
+mod mail;
+mod master_key;
+mod migrations;
+mod redact;
+mod report;
+mod scheduler;
 mod secret_store;
+mod transfer;
 mod ui;
 
 use gpui::*;
@@ -23,10 +36,23 @@ fn main() {
     });
 
     tracing_subscriber::registry()
-        .with(tracing_subscriber::fmt::layer())
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_writer(redact::RedactingMakeWriter::new(std::io::stderr)),
+        )
         .with(filter)
         .init();
 
+    // Headless commands (export, ...) run without opening a window
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(result) = cli::run(&args) {
+        if let Err(e) = result {
+            eprintln!("Error: {}", redact::error_message(&e));
+            std::process::exit(1);
+        }
+        return;
+    }
+
     tracing::info!("Starting CloudBridge...");
 
     let app = Application::new().with_assets(gpui_component_assets::Assets);
@@ -0,0 +1,125 @@
+//! Master passphrase mode
+//!
+//! Instead of keeping the encryption key in config.json, the key is derived from a
+//! passphrase entered at startup (Argon2id with a salt stored in the config). Turning
+//! the mode on or off re-encrypts the stored credentials with the new key.
+
+use anyhow::{anyhow, Result};
+
+use crate::cloud::CloudAccount;
+use crate::config::{load_config, save_config, AppConfig};
+use crate::crypto;
+use crate::db;
+use crate::secret_store;
+
+/// Credentials readable with the current key
+struct Secrets {
+    accounts: Vec<CloudAccount>,
+    smtp_password: Option<(String, String)>,
+}
+
+fn read_secrets(config: &AppConfig) -> Result<Secrets> {
+    let smtp_password = match config.smtp.as_ref().and_then(|s| s.username.clone()) {
+        Some(username) => {
+            secret_store::get_smtp_password(&username)?.map(|password| (username, password))
+        }
+        None => None,
+    };
+    Ok(Secrets {
+        accounts: db::get_all_accounts()?,
+        smtp_password,
+    })
+}
+
+/// Store the credentials again, encrypted according to the saved configuration
+fn write_secrets(secrets: &Secrets) -> Result<()> {
+    for account in &secrets.accounts {
+        if account.access_key_id.is_empty() && account.secret_access_key.is_empty() {
+            continue;
+        }
+        secret_store::store_account_secrets(
+            &account.id,
+            &account.access_key_id,
+            &account.secret_access_key,
+        )?;
+    }
+    if let Some((username, password)) = &secrets.smtp_password {
+        secret_store::store_smtp_password(username, password)?;
+    }
+    Ok(())
+}
+
+/// Save the configuration and keep the lock state in step with it
+fn save(config: &AppConfig) -> Result<()> {
+    save_config(config)?;
+    crypto::set_master_state(config.master_key.as_ref());
+    Ok(())
+}
+
+/// Turn on master passphrase mode, removing the plaintext key from config.json.
+///
+/// The old key is removed only after every secret was re-encrypted with the master
+/// key; if that fails, the secrets are written back under the old key.
+pub fn enable(passphrase: &str) -> Result<()> {
+    let mut config = load_config()?;
+    if config.master_key.is_some() {
+        return Err(anyhow!("Master passphrase is already enabled"));
+    }
+
+    let secrets = read_secrets(&config)?;
+    let (master, key) = crypto::new_master_key(passphrase)?;
+    crypto::set_session_key(Some(key));
+
+    // The master key takes precedence over the still saved old key when encrypting
+    config.master_key = Some(master);
+    save(&config)?;
+    if let Err(e) = write_secrets(&secrets) {
+        config.master_key = None;
+        save(&config)?;
+        crypto::set_session_key(None);
+        write_secrets(&secrets)?;
+        return Err(e.context("Failed to re-encrypt the stored credentials"));
+    }
+
+    config.encryption_key = None;
+    save(&config)?;
+
+    tracing::info!(
+        "Master passphrase enabled, re-encrypted credentials of {} accounts",
+        secrets.accounts.len()
+    );
+    Ok(())
+}
+
+/// Turn off master passphrase mode after checking the passphrase
+pub fn disable(passphrase: &str) -> Result<()> {
+    let mut config = load_config()?;
+    let Some(master) = config.master_key.take() else {
+        return Err(anyhow!("Master passphrase is not enabled"));
+    };
+    crypto::set_session_key(Some(crypto::verify_master_key(&master, passphrase)?));
+
+    let secrets = read_secrets(&config)?;
+    save(&config)?;
+    if let Err(e) = write_secrets(&secrets) {
+        config.master_key = Some(master);
+        save(&config)?;
+        write_secrets(&secrets)?;
+        return Err(e.context("Failed to re-encrypt the stored credentials"));
+    }
+    crypto::set_session_key(None);
+
+    tracing::info!("Master passphrase disabled");
+    Ok(())
+}
+
+/// Set the idle time after which the app locks (0 = never)
+pub fn set_auto_lock(minutes: u32) -> Result<()> {
+    let mut config = load_config()?;
+    let master = config
+        .master_key
+        .as_mut()
+        .ok_or_else(|| anyhow!("Master passphrase is not enabled"))?;
+    master.auto_lock_minutes = minutes;
+    save(&config)
+}
@@ -0,0 +1,480 @@
+//! Database schema migrations
+//!
+//! The applied schema version is recorded in `schema_version`. Databases created
+//! before versioning have no such table and count as version 1, the layout created by
+//! `db::create_schema_v1`. Each migration upgrades the schema by one version inside
+//! a transaction; migrations are never edited once released, only appended.
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use duckdb::{params, Connection};
+
+use crate::db;
+
+/// An up-migration to `version`
+pub struct Migration {
+    pub version: i64,
+    pub description: &'static str,
+    up: fn(&Connection) -> Result<()>,
+}
+
+/// All migrations, in order
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 2,
+        description: "Add cost breakdown cache",
+        up: add_cost_breakdown_cache,
+    },
+    Migration {
+        version: 3,
+        description: "Add commitment cache",
+        up: add_commitment_cache,
+    },
+    Migration {
+        version: 4,
+        description: "Add budget sources",
+        up: add_budget_sources,
+    },
+    Migration {
+        version: 5,
+        description: "Add anomaly cache",
+        up: add_anomaly_cache,
+    },
+    Migration {
+        version: 6,
+        description: "Add CUR line items",
+        up: add_cur_tables,
+    },
+    Migration {
+        version: 7,
+        description: "Add FOCUS cost table",
+        up: add_focus_costs,
+    },
+    Migration {
+        version: 8,
+        description: "Add digest deliveries",
+        up: add_digest_deliveries,
+    },
+    Migration {
+        version: 9,
+        description: "Drop legacy credential columns from cloud_accounts",
+        up: drop_credential_columns,
+    },
+    Migration {
+        version: 10,
+        description: "Add member discovery cache",
+        up: add_member_discovery_cache,
+    },
+];
+
+/// Schema version this build expects
+pub fn latest_version() -> i64 {
+    MIGRATIONS.last().map_or(1, |m| m.version)
+}
+
+fn create_version_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS schema_version (
+            version BIGINT PRIMARY KEY,
+            description VARCHAR NOT NULL,
+            applied_at VARCHAR NOT NULL
+        )
+        "#,
+        [],
+    )?;
+    Ok(())
+}
+
+fn record_version(conn: &Connection, version: i64, description: &str) -> Result<()> {
+    conn.execute(
+        "INSERT INTO schema_version (version, description, applied_at) VALUES (?, ?, ?)",
+        params![version, description, Utc::now().to_rfc3339()],
+    )?;
+    Ok(())
+}
+
+/// Current schema version; 0 for an unversioned database
+pub fn schema_version(conn: &Connection) -> Result<i64> {
+    create_version_table(conn)?;
+    let version: Option<i64> =
+        conn.query_row("SELECT MAX(version) FROM schema_version", [], |row| {
+            row.get(0)
+        })?;
+    Ok(version.unwrap_or(0))
+}
+
+/// Migrations not applied yet
+pub fn pending_migrations(conn: &Connection) -> Result<Vec<&'static Migration>> {
+    let version = schema_version(conn)?;
+    if version > latest_version() {
+        anyhow::bail!(
+            "Database schema version {} is newer than this version of CloudBridge supports ({}); please upgrade CloudBridge",
+            version,
+            latest_version()
+        );
+    }
+    Ok(MIGRATIONS.iter().filter(|m| m.version > version).collect())
+}
+
+/// Bring the database up to the latest schema version. Returns the versions applied.
+pub fn migrate(conn: &Connection) -> Result<Vec<i64>> {
+    if schema_version(conn)? == 0 {
+        // New databases, and databases from before versioning, get the version 1 layout
+        db::create_schema_v1(conn)?;
+        record_version(conn, 1, "Initial schema")?;
+    }
+
+    let mut applied = Vec::new();
+    for migration in pending_migrations(conn)? {
+        tracing::info!(
+            "Migrating database to version {}: {}",
+            migration.version,
+            migration.description
+        );
+
+        db::in_transaction(conn, |conn| {
+            (migration.up)(conn)?;
+            record_version(conn, migration.version, migration.description)
+        })
+        .with_context(|| format!("Database migration to version {} failed", migration.version))?;
+        applied.push(migration.version);
+    }
+
+    Ok(applied)
+}
+
+// ==================== Migrations ====================
+
+/// Version 2: cost records grouped by a cost allocation dimension
+fn add_cost_breakdown_cache(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS cost_breakdown_cache (
+            account_id VARCHAR NOT NULL,
+            group_by VARCHAR NOT NULL,
+            date VARCHAR NOT NULL,
+            service VARCHAR NOT NULL,
+            amount DOUBLE NOT NULL,
+            currency VARCHAR NOT NULL,
+            dimensions TEXT NOT NULL,
+            cached_at VARCHAR NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_cost_breakdown_account
+            ON cost_breakdown_cache(account_id, group_by, date);
+        "#,
+    )?;
+    Ok(())
+}
+
+/// Version 3: Savings Plans / Reserved Instances summaries
+fn add_commitment_cache(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS commitment_cache (
+            account_id VARCHAR PRIMARY KEY,
+            summary TEXT NOT NULL,
+            cached_at VARCHAR NOT NULL
+        );
+        "#,
+    )?;
+    Ok(())
+}
+
+/// Version 4: where each budget is maintained, e.g. AWS Budgets
+fn add_budget_sources(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS budget_sources (
+            account_id VARCHAR PRIMARY KEY,
+            source VARCHAR NOT NULL,
+            external_name VARCHAR,
+            synced_at VARCHAR
+        );
+        "#,
+    )?;
+    Ok(())
+}
+
+/// Version 5: cost anomalies
+fn add_anomaly_cache(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS anomaly_cache (
+            account_id VARCHAR PRIMARY KEY,
+            anomalies TEXT NOT NULL,
+            cached_at VARCHAR NOT NULL
+        );
+        "#,
+    )?;
+    Ok(())
+}
+
+/// Version 6: CUR line items aggregated into cost_data, the ingested report files,
+/// and the sequence for cost_data IDs
+fn add_cur_tables(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE SEQUENCE IF NOT EXISTS cost_data_id_seq;
+        CREATE TABLE IF NOT EXISTS cost_line_items (
+            account_id VARCHAR NOT NULL,
+            linked_account VARCHAR,
+            date VARCHAR NOT NULL,
+            service VARCHAR NOT NULL,
+            region VARCHAR,
+            usage_type VARCHAR,
+            resource_id VARCHAR,
+            amount DOUBLE NOT NULL,
+            currency VARCHAR NOT NULL,
+            source_file VARCHAR NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS cur_files (
+            path VARCHAR PRIMARY KEY,
+            account_id VARCHAR NOT NULL,
+            modified_at VARCHAR NOT NULL,
+            size BIGINT NOT NULL,
+            row_count BIGINT NOT NULL,
+            ingested_at VARCHAR NOT NULL
+        );
+        "#,
+    )?;
+    Ok(())
+}
+
+/// Version 7: FOCUS-aligned costs (column names follow the FOCUS specification)
+fn add_focus_costs(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS focus_costs (
+            account_id VARCHAR NOT NULL,
+            BilledCost DOUBLE NOT NULL,
+            EffectiveCost DOUBLE NOT NULL,
+            ListCost DOUBLE NOT NULL,
+            BillingCurrency VARCHAR NOT NULL,
+            ChargeCategory VARCHAR NOT NULL,
+            ChargePeriodStart VARCHAR NOT NULL,
+            ChargePeriodEnd VARCHAR NOT NULL,
+            ProviderName VARCHAR NOT NULL,
+            ServiceName VARCHAR NOT NULL,
+            ServiceCategory VARCHAR NOT NULL,
+            RegionId VARCHAR,
+            ResourceId VARCHAR,
+            SubAccountId VARCHAR,
+            SubAccountName VARCHAR,
+            Tags VARCHAR
+        );
+        CREATE INDEX IF NOT EXISTS idx_focus_costs_account
+            ON focus_costs(account_id, ChargePeriodStart);
+        "#,
+    )?;
+    Ok(())
+}
+
+/// Version 8: last period sent per digest schedule
+fn add_digest_deliveries(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS digest_deliveries (
+            name VARCHAR PRIMARY KEY,
+            period_start VARCHAR NOT NULL,
+            sent_at VARCHAR NOT NULL
+        );
+        "#,
+    )?;
+    Ok(())
+}
+
+/// Version 10: members found in the bill of a payer account without a member list
+fn add_member_discovery_cache(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS member_discovery_cache (
+            account_id VARCHAR PRIMARY KEY,
+            members TEXT NOT NULL,
+            cached_at VARCHAR NOT NULL
+        );
+        "#,
+    )?;
+    Ok(())
+}
+
+/// Version 9: credentials live in the secret backend. Encrypted values still in
+/// `cloud_accounts` are kept in `legacy_credentials` until `db::migrate_legacy_credentials`
+/// moves them after unlock (decrypting needs the encryption key, which may be locked
+/// while migrating), then `access_key_id`/`secret_access_key` are dropped. Tables
+/// referencing `cloud_accounts` are rebuilt with it, as DuckDB does not alter tables
+/// that others depend on.
+fn drop_credential_columns(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS legacy_credentials (
+            account_id VARCHAR PRIMARY KEY,
+            access_key_id VARCHAR NOT NULL,
+            secret_access_key VARCHAR NOT NULL
+        );
+        INSERT INTO legacy_credentials
+            SELECT id, access_key_id, secret_access_key FROM cloud_accounts
+            WHERE access_key_id <> '' OR secret_access_key <> '';
+        "#,
+    )?;
+
+    conn.execute_batch(
+        r#"
+        CREATE TABLE cost_data_v1 AS SELECT * FROM cost_data;
+        CREATE TABLE budgets_v1 AS SELECT * FROM budgets;
+        DROP TABLE cost_data;
+        DROP TABLE budgets;
+
+        CREATE TABLE cloud_accounts_v2 (
+            id VARCHAR PRIMARY KEY,
+            name VARCHAR NOT NULL,
+            provider VARCHAR NOT NULL,
+            region VARCHAR,
+            created_at VARCHAR NOT NULL,
+            last_synced_at VARCHAR,
+            enabled BOOLEAN NOT NULL DEFAULT true
+        );
+        INSERT INTO cloud_accounts_v2
+            SELECT id, name, provider, region, created_at, last_synced_at, enabled
+            FROM cloud_accounts;
+        DROP TABLE cloud_accounts;
+        ALTER TABLE cloud_accounts_v2 RENAME TO cloud_accounts;
+
+        CREATE TABLE cost_data (
+            id INTEGER PRIMARY KEY,
+            account_id VARCHAR NOT NULL,
+            date VARCHAR NOT NULL,
+            service VARCHAR NOT NULL,
+            amount DOUBLE NOT NULL,
+            currency VARCHAR NOT NULL,
+            created_at VARCHAR,
+            FOREIGN KEY (account_id) REFERENCES cloud_accounts(id)
+        );
+        INSERT INTO cost_data SELECT * FROM cost_data_v1;
+        CREATE INDEX idx_cost_data_account_date ON cost_data(account_id, date);
+
+        CREATE TABLE budgets (
+            account_id VARCHAR PRIMARY KEY,
+            monthly_budget DOUBLE NOT NULL,
+            currency VARCHAR NOT NULL,
+            alert_threshold DOUBLE NOT NULL DEFAULT 80.0,
+            created_at VARCHAR NOT NULL,
+            updated_at VARCHAR NOT NULL,
+            FOREIGN KEY (account_id) REFERENCES cloud_accounts(id)
+        );
+        INSERT INTO budgets SELECT * FROM budgets_v1;
+
+        DROP TABLE cost_data_v1;
+        DROP TABLE budgets_v1;
+        "#,
+    )?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Database as written by the last release before schema versioning (version 1)
+    fn v1_fixture() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        db::create_schema_v1(&conn).unwrap();
+        conn.execute_batch(
+            r#"
+            INSERT INTO cloud_accounts
+                (id, name, provider, access_key_id, secret_access_key, region, created_at, last_synced_at, enabled)
+                VALUES ('acc-1', 'Prod', 'AWS', '', '', 'us-east-1', '2024-01-01T00:00:00+00:00', NULL, true),
+                       ('acc-2', 'Legacy', 'Aliyun', 'ZW5jLWFr', 'ZW5jLXNr', NULL, '2024-01-01T00:00:00+00:00', NULL, true);
+            INSERT INTO cost_data (id, account_id, date, service, amount, currency, created_at)
+                VALUES (1, 'acc-1', '2024-05-01', 'Amazon EC2', 12.5, 'USD', NULL);
+            INSERT INTO budgets
+                (account_id, monthly_budget, currency, alert_threshold, created_at, updated_at)
+                VALUES ('acc-1', 1000.0, 'USD', 80.0, '2024-01-01T00:00:00+00:00', '2024-01-01T00:00:00+00:00');
+            "#,
+        )
+        .unwrap();
+        conn
+    }
+
+    fn columns(conn: &Connection, table: &str) -> Vec<String> {
+        let mut stmt = conn
+            .prepare(&format!("SELECT name FROM pragma_table_info('{}')", table))
+            .unwrap();
+        stmt.query_map([], |row| row.get::<_, String>(0))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_migrate_v1_fixture() {
+        let conn = v1_fixture();
+        assert_eq!(schema_version(&conn).unwrap(), 0);
+
+        let applied = migrate(&conn).unwrap();
+        assert_eq!(applied, (2..=latest_version()).collect::<Vec<_>>());
+        assert_eq!(schema_version(&conn).unwrap(), latest_version());
+        for table in [
+            "cost_breakdown_cache",
+            "cur_files",
+            "focus_costs",
+            "digest_deliveries",
+            "member_discovery_cache",
+        ] {
+            assert!(!columns(&conn, table).is_empty(), "{} missing", table);
+        }
+
+        let account_columns = columns(&conn, "cloud_accounts");
+        assert!(account_columns.contains(&"region".to_string()));
+        assert!(!account_columns.contains(&"access_key_id".to_string()));
+        assert!(!account_columns.contains(&"secret_access_key".to_string()));
+
+        // Stored credentials wait, still encrypted, for the move after unlock
+        let legacy: Vec<(String, String)> = conn
+            .prepare("SELECT account_id, access_key_id FROM legacy_credentials")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(legacy, vec![("acc-2".to_string(), "ZW5jLWFr".to_string())]);
+
+        // Data survives, and references to accounts still hold
+        let (service, amount): (String, f64) = conn
+            .query_row(
+                "SELECT service, amount FROM cost_data WHERE account_id = 'acc-1'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((service.as_str(), amount), ("Amazon EC2", 12.5));
+        let budget: f64 = conn
+            .query_row("SELECT monthly_budget FROM budgets", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(budget, 1000.0);
+        assert!(conn
+            .execute(
+                "INSERT INTO budgets VALUES ('missing', 1.0, 'USD', 80.0, '', '')",
+                []
+            )
+            .is_err());
+
+        // Running again is a no-op
+        assert!(migrate(&conn).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_fresh_database_and_newer_schema() {
+        let conn = Connection::open_in_memory().unwrap();
+        migrate(&conn).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), latest_version());
+        let versions: i64 = conn
+            .query_row("SELECT COUNT(*) FROM schema_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(versions, latest_version());
+
+        record_version(&conn, latest_version() + 1, "From the future").unwrap();
+        assert!(migrate(&conn).is_err());
+    }
+}
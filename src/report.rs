@@ -0,0 +1,921 @@
+//! Cost reports
+//!
+//! Renders a self-contained HTML report for a month: totals per provider with
+//! month-over-month change, top services, the largest movers, budget status and daily
+//! trend charts as inline SVG. Reports can also be printed to PDF with a locally
+//! installed Chromium-based browser.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use crate::cloud::{
+    focus, BudgetStatus, CloudAccount, CloudProvider, CostSummary, CostTrend, ServiceCost,
+};
+use crate::config::load_config;
+use crate::db;
+
+/// Number of rows in the top services and largest movers tables
+const TABLE_ROWS: usize = 10;
+
+/// Reporting period and the period it is compared with (end dates exclusive)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReportPeriod {
+    /// Heading, e.g. "May 2024"
+    pub title: String,
+    pub start: String,
+    pub end: String,
+    pub previous_start: String,
+    pub previous_end: String,
+    /// Whether the period is a calendar month, so budgets can be compared with its cost
+    pub calendar_month: bool,
+}
+
+impl ReportPeriod {
+    /// Calendar month in YYYY-MM format, compared with the month before
+    pub fn month(month: &str) -> Result<Self> {
+        let start = NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d")
+            .with_context(|| format!("Invalid month '{}', expected YYYY-MM", month))?;
+        let previous = start - Duration::days(1);
+        let previous_start = previous.with_day(1).unwrap_or(previous);
+        let start_str = start.format("%Y-%m-%d").to_string();
+
+        Ok(ReportPeriod {
+            title: start.format("%B %Y").to_string(),
+            end: focus::next_month_start(&start_str),
+            previous_start: previous_start.format("%Y-%m-%d").to_string(),
+            previous_end: start_str.clone(),
+            start: start_str,
+            calendar_month: true,
+        })
+    }
+
+    /// Monday-Sunday week starting at `monday`, compared with the week before
+    pub fn week(monday: NaiveDate) -> Self {
+        let day = |d: NaiveDate| d.format("%Y-%m-%d").to_string();
+        ReportPeriod {
+            title: format!("Week of {}", monday.format("%B %-d, %Y")),
+            start: day(monday),
+            end: day(monday + Duration::days(7)),
+            previous_start: day(monday - Duration::days(7)),
+            previous_end: day(monday),
+            calendar_month: false,
+        }
+    }
+
+    /// The last closed month
+    pub fn last_month() -> Self {
+        let today = Utc::now().date_naive();
+        let last_month = today.with_day(1).unwrap_or(today) - Duration::days(1);
+        Self::month(&last_month.format("%Y-%m").to_string())
+            .expect("formatted month is always valid")
+    }
+}
+
+/// One account's costs for the report period
+#[derive(Debug, Clone)]
+pub struct AccountReport {
+    /// `current_month_*` is the report period, `last_month_*` the previous period
+    pub summary: CostSummary,
+    pub trend: Option<CostTrend>,
+    pub budget: Option<BudgetStatus>,
+}
+
+/// Report data for all enabled accounts
+#[derive(Debug, Clone)]
+pub struct CostReport {
+    pub period: ReportPeriod,
+    pub generated_at: DateTime<Utc>,
+    pub accounts: Vec<AccountReport>,
+}
+
+/// Collect report data for the given accounts, or all enabled accounts when empty.
+///
+/// Costs are collected with [`crate::export::collect_costs`]; accounts that fail are
+/// left out of the report. Budgets are compared with the period's cost for monthly
+/// reports and with the cached month-to-date cost otherwise.
+pub fn build_report(period: &ReportPeriod, account_ids: &[String]) -> Result<CostReport> {
+    let config = load_config().unwrap_or_default();
+    let budgets: HashMap<String, _> = db::get_all_budgets()?
+        .into_iter()
+        .map(|b| (b.account_id.clone(), b))
+        .collect();
+
+    let mut accounts = Vec::new();
+    for account in db::get_all_accounts()? {
+        let included = if account_ids.is_empty() {
+            account.enabled
+        } else {
+            account_ids.contains(&account.id)
+        };
+        if !included {
+            continue;
+        }
+
+        crate::cur::ingest_configured(&account, &config);
+
+        match collect_account(&account, period) {
+            Ok((summary, trend)) => {
+                let budget = if period.calendar_month {
+                    budgets.get(&account.id).map(|b| {
+                        BudgetStatus::from_budget(b, &account.name, summary.current_month_cost)
+                    })
+                } else {
+                    db::get_budget_status(&account.id).ok().flatten()
+                };
+                accounts.push(AccountReport {
+                    summary,
+                    trend,
+                    budget,
+                });
+            }
+            Err(e) => tracing::error!("Failed to get report data for {}: {}", account.name, e),
+        }
+    }
+
+    Ok(CostReport {
+        period: period.clone(),
+        generated_at: Utc::now(),
+        accounts,
+    })
+}
+
+/// Service costs of the period and previous period plus the daily trend of the period
+fn collect_account(
+    account: &CloudAccount,
+    period: &ReportPeriod,
+) -> Result<(CostSummary, Option<CostTrend>)> {
+    let costs = crate::export::collect_costs(
+        account,
+        &period.start,
+        &period.end,
+        Some((&period.previous_start, &period.previous_end)),
+    )?;
+    let (current, previous, trend) = (costs.services, costs.previous_services, costs.trend);
+
+    let current_month_cost: f64 = current.iter().map(|s| s.amount).sum();
+    let last_month_cost: f64 = previous.iter().map(|s| s.amount).sum();
+    let month_over_month_change = if last_month_cost > 0.0 {
+        ((current_month_cost - last_month_cost) / last_month_cost) * 100.0
+    } else {
+        0.0
+    };
+    let currency = current
+        .first()
+        .or(previous.first())
+        .map(|s| s.currency.clone())
+        .or_else(|| trend.as_ref().map(|t| t.currency.clone()))
+        .unwrap_or_else(|| "USD".to_string());
+
+    Ok((
+        CostSummary {
+            account_id: account.id.clone(),
+            account_name: account.name.clone(),
+            provider: account.provider,
+            current_month_cost,
+            last_month_cost,
+            currency,
+            month_over_month_change,
+            current_month_details: current,
+            last_month_details: previous,
+        },
+        trend,
+    ))
+}
+
+/// Total of one provider in one currency
+#[derive(Debug, Clone, PartialEq)]
+struct ProviderTotal {
+    provider: CloudProvider,
+    currency: String,
+    current: f64,
+    previous: f64,
+}
+
+/// Change of one service between the two periods
+#[derive(Debug, Clone, PartialEq)]
+struct ServiceMove {
+    account_name: String,
+    service: String,
+    currency: String,
+    previous: f64,
+    current: f64,
+}
+
+impl ServiceMove {
+    fn delta(&self) -> f64 {
+        self.current - self.previous
+    }
+}
+
+/// Percentage change, None when there is nothing to compare with
+fn change_percent(current: f64, previous: f64) -> Option<f64> {
+    (previous > 0.0).then(|| (current - previous) / previous * 100.0)
+}
+
+impl CostReport {
+    fn provider_totals(&self) -> Vec<ProviderTotal> {
+        let mut totals: BTreeMap<(&'static str, String), ProviderTotal> = BTreeMap::new();
+        for account in &self.accounts {
+            let summary = &account.summary;
+            let total = totals
+                .entry((summary.provider.display_name(), summary.currency.clone()))
+                .or_insert_with(|| ProviderTotal {
+                    provider: summary.provider,
+                    currency: summary.currency.clone(),
+                    current: 0.0,
+                    previous: 0.0,
+                });
+            total.current += summary.current_month_cost;
+            total.previous += summary.last_month_cost;
+        }
+        totals.into_values().collect()
+    }
+
+    /// Largest services of the period across accounts of the same provider
+    fn top_services(&self) -> Vec<(CloudProvider, ServiceCost)> {
+        let mut services: BTreeMap<(&'static str, String, String), (CloudProvider, f64)> =
+            BTreeMap::new();
+        for account in &self.accounts {
+            let provider = account.summary.provider;
+            for service in &account.summary.current_month_details {
+                services
+                    .entry((
+                        provider.display_name(),
+                        service.service.clone(),
+                        service.currency.clone(),
+                    ))
+                    .or_insert((provider, 0.0))
+                    .1 += service.amount;
+            }
+        }
+
+        let mut top: Vec<(CloudProvider, ServiceCost)> = services
+            .into_iter()
+            .map(|((_, service, currency), (provider, amount))| {
+                (
+                    provider,
+                    ServiceCost {
+                        service,
+                        amount,
+                        currency,
+                    },
+                )
+            })
+            .collect();
+        top.sort_by(|a, b| b.1.amount.total_cmp(&a.1.amount));
+        top.truncate(TABLE_ROWS);
+        top
+    }
+
+    /// Services with the largest absolute change against the previous period
+    fn largest_movers(&self) -> Vec<ServiceMove> {
+        let mut moves = Vec::new();
+        for account in &self.accounts {
+            let summary = &account.summary;
+            let mut by_service: BTreeMap<&str, (f64, f64, &str)> = BTreeMap::new();
+            for service in &summary.last_month_details {
+                let entry = by_service.entry(&service.service).or_insert((
+                    0.0,
+                    0.0,
+                    service.currency.as_str(),
+                ));
+                entry.0 += service.amount;
+            }
+            for service in &summary.current_month_details {
+                let entry = by_service.entry(&service.service).or_insert((
+                    0.0,
+                    0.0,
+                    service.currency.as_str(),
+                ));
+                entry.1 += service.amount;
+            }
+
+            moves.extend(
+                by_service
+                    .into_iter()
+                    .map(|(service, (previous, current, currency))| ServiceMove {
+                        account_name: summary.account_name.clone(),
+                        service: service.to_string(),
+                        currency: currency.to_string(),
+                        previous,
+                        current,
+                    })
+                    .filter(|m| m.delta().abs() >= 0.01),
+            );
+        }
+
+        moves.sort_by(|a, b| b.delta().abs().total_cmp(&a.delta().abs()));
+        moves.truncate(TABLE_ROWS);
+        moves
+    }
+
+    /// Daily totals across accounts, per currency
+    fn daily_totals(&self) -> BTreeMap<String, Vec<(String, f64)>> {
+        let mut totals: BTreeMap<String, BTreeMap<String, f64>> = BTreeMap::new();
+        for trend in self.accounts.iter().filter_map(|a| a.trend.as_ref()) {
+            let days = totals.entry(trend.currency.clone()).or_default();
+            for day in &trend.daily_costs {
+                *days.entry(day.date.clone()).or_insert(0.0) += day.amount;
+            }
+        }
+        totals
+            .into_iter()
+            .filter(|(_, days)| !days.is_empty())
+            .map(|(currency, days)| (currency, days.into_iter().collect()))
+            .collect()
+    }
+}
+
+/// Escape text for HTML element content and attribute values
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Format an amount with thousands separators, e.g. "1,234.50 USD"
+fn format_amount(amount: f64, currency: &str) -> String {
+    let formatted = format!("{:.2}", amount.abs());
+    let (integer, fraction) = formatted.split_once('.').unwrap_or((&formatted, "00"));
+    let mut grouped = String::new();
+    for (i, c) in integer.chars().enumerate() {
+        if i > 0 && (integer.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+    let sign = if amount < -0.005 { "-" } else { "" };
+    format!("{}{}.{} {}", sign, grouped, fraction, currency)
+}
+
+/// Month-over-month change cell; increases are highlighted as bad
+fn change_cell(current: f64, previous: f64) -> String {
+    match change_percent(current, previous) {
+        Some(change) => format!(
+            r#"<td class="num {}">{}{:.1}%</td>"#,
+            if change > 0.0 { "up" } else { "down" },
+            if change > 0.0 { "+" } else { "" },
+            change
+        ),
+        None => r#"<td class="num muted">n/a</td>"#.to_string(),
+    }
+}
+
+const CHART_WIDTH: f64 = 720.0;
+
+/// Horizontal bar chart comparing the previous and current period of each row
+fn bar_chart_svg(rows: &[(String, f64, f64)]) -> String {
+    let label_width = 200.0;
+    let row_height = 36.0;
+    let bar_area = CHART_WIDTH - label_width - 20.0;
+    let height = 30.0 + row_height * rows.len() as f64;
+    let max = rows
+        .iter()
+        .flat_map(|(_, previous, current)| [*previous, *current])
+        .fold(0.0_f64, f64::max)
+        .max(f64::EPSILON);
+
+    let mut svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" role="img">"#,
+        w = CHART_WIDTH,
+        h = height
+    );
+    svg.push_str(
+        r##"<rect x="0" y="6" width="10" height="10" fill="#cbd5e1"/><text x="16" y="15" class="legend">Previous</text><rect x="90" y="6" width="10" height="10" fill="#2563eb"/><text x="106" y="15" class="legend">Current</text>"##,
+    );
+    for (i, (label, previous, current)) in rows.iter().enumerate() {
+        let y = 30.0 + row_height * i as f64;
+        let _ = write!(
+            svg,
+            r##"<text x="0" y="{ty:.1}" class="label">{label}</text><rect x="{x}" y="{y1:.1}" width="{pw:.1}" height="12" fill="#cbd5e1"/><rect x="{x}" y="{y2:.1}" width="{cw:.1}" height="12" fill="#2563eb"/>"##,
+            ty = y + 16.0,
+            label = escape(label),
+            x = label_width,
+            y1 = y,
+            pw = previous / max * bar_area,
+            y2 = y + 14.0,
+            cw = current / max * bar_area,
+        );
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+/// Line chart of daily amounts
+fn line_chart_svg(points: &[(String, f64)], currency: &str) -> String {
+    let (left, right, top, bottom) = (80.0, 10.0, 10.0, 30.0);
+    let height = 220.0;
+    let plot_width = CHART_WIDTH - left - right;
+    let plot_height = height - top - bottom;
+    let max = points
+        .iter()
+        .map(|(_, amount)| *amount)
+        .fold(0.0_f64, f64::max)
+        .max(f64::EPSILON);
+    let step = if points.len() > 1 {
+        plot_width / (points.len() - 1) as f64
+    } else {
+        0.0
+    };
+    let coordinates: Vec<(f64, f64)> = points
+        .iter()
+        .enumerate()
+        .map(|(i, (_, amount))| {
+            (
+                left + step * i as f64,
+                top + plot_height - amount / max * plot_height,
+            )
+        })
+        .collect();
+    let polyline = coordinates
+        .iter()
+        .map(|(x, y)| format!("{:.1},{:.1}", x, y))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let mut svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" role="img">"#,
+        w = CHART_WIDTH,
+        h = height
+    );
+    let _ = write!(
+        svg,
+        r##"<line x1="{left}" y1="{base}" x2="{right}" y2="{base}" stroke="#94a3b8"/><line x1="{left}" y1="{top}" x2="{right}" y2="{top}" stroke="#e2e8f0"/><text x="{label_x}" y="{top_label:.1}" class="axis" text-anchor="end">{max}</text><text x="{label_x}" y="{base:.1}" class="axis" text-anchor="end">0</text>"##,
+        left = left,
+        right = CHART_WIDTH - right,
+        top = top,
+        base = top + plot_height,
+        label_x = left - 6.0,
+        top_label = top + 4.0,
+        max = escape(&format_amount(max, currency)),
+    );
+    let _ = write!(
+        svg,
+        r##"<polyline points="{}" fill="none" stroke="#2563eb" stroke-width="2"/>"##,
+        polyline
+    );
+    for (x, y) in &coordinates {
+        let _ = write!(
+            svg,
+            r##"<circle cx="{:.1}" cy="{:.1}" r="2.5" fill="#2563eb"/>"##,
+            x, y
+        );
+    }
+    if let (Some((first, _)), Some((last, _))) = (points.first(), points.last()) {
+        let _ = write!(
+            svg,
+            r#"<text x="{left}" y="{y}" class="axis">{first}</text><text x="{right}" y="{y}" class="axis" text-anchor="end">{last}</text>"#,
+            left = left,
+            right = CHART_WIDTH - right,
+            y = height - 8.0,
+            first = escape(first),
+            last = escape(last),
+        );
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+const STYLE: &str = r#"
+body { font-family: -apple-system, "Segoe UI", Roboto, Helvetica, Arial, sans-serif; color: #0f172a; margin: 32px auto; max-width: 820px; }
+h1 { font-size: 24px; margin-bottom: 4px; }
+h2 { font-size: 18px; margin-top: 32px; border-bottom: 1px solid #e2e8f0; padding-bottom: 4px; }
+table { border-collapse: collapse; width: 100%; font-size: 13px; }
+th, td { padding: 6px 8px; border-bottom: 1px solid #f1f5f9; text-align: left; }
+th { color: #475569; font-weight: 600; }
+.num { text-align: right; font-variant-numeric: tabular-nums; }
+.muted, .meta { color: #64748b; }
+.up { color: #dc2626; }
+.down { color: #16a34a; }
+.over { color: #dc2626; font-weight: 600; }
+.alert { color: #d97706; font-weight: 600; }
+.ok { color: #16a34a; }
+svg text { font-size: 11px; fill: #334155; }
+svg .label { font-size: 12px; }
+section { page-break-inside: avoid; }
+"#;
+
+/// Render the report as a self-contained HTML document
+pub fn render_html(report: &CostReport) -> String {
+    let period = &report.period;
+    let mut html = String::new();
+
+    let _ = write!(
+        html,
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>CloudBridge Cost Report - {title}</title>
+<style>{style}</style>
+</head>
+<body>
+<h1>Cost Report - {title}</h1>
+<p class="meta">{start} to {end} (exclusive), compared with {previous_start} to {previous_end}. Generated {generated} UTC by CloudBridge.</p>
+"#,
+        title = escape(&period.title),
+        style = STYLE,
+        start = period.start,
+        end = period.end,
+        previous_start = period.previous_start,
+        previous_end = period.previous_end,
+        generated = report.generated_at.format("%Y-%m-%d %H:%M"),
+    );
+
+    if report.accounts.is_empty() {
+        html.push_str("<p>No enabled accounts with cost data.</p>\n</body>\n</html>\n");
+        return html;
+    }
+
+    // Totals per provider
+    let totals = report.provider_totals();
+    html.push_str("<section>\n<h2>Totals by Provider</h2>\n<table>\n<tr><th>Provider</th><th class=\"num\">Previous</th><th class=\"num\">Current</th><th class=\"num\">Change</th></tr>\n");
+    for total in &totals {
+        let _ = writeln!(
+            html,
+            r#"<tr><td>{}</td><td class="num">{}</td><td class="num">{}</td>{}</tr>"#,
+            escape(total.provider.display_name()),
+            format_amount(total.previous, &total.currency),
+            format_amount(total.current, &total.currency),
+            change_cell(total.current, total.previous),
+        );
+    }
+    html.push_str("</table>\n");
+    // One chart per currency so bars share a scale
+    let mut currencies: Vec<&str> = totals.iter().map(|t| t.currency.as_str()).collect();
+    currencies.dedup();
+    for currency in currencies {
+        let rows: Vec<(String, f64, f64)> = totals
+            .iter()
+            .filter(|t| t.currency == currency)
+            .map(|t| {
+                (
+                    format!("{} ({})", t.provider.display_name(), t.currency),
+                    t.previous,
+                    t.current,
+                )
+            })
+            .collect();
+        html.push_str(&bar_chart_svg(&rows));
+        html.push('\n');
+    }
+    html.push_str("</section>\n");
+
+    // Accounts
+    html.push_str("<section>\n<h2>Accounts</h2>\n<table>\n<tr><th>Account</th><th>Provider</th><th class=\"num\">Previous</th><th class=\"num\">Current</th><th class=\"num\">Change</th></tr>\n");
+    for account in &report.accounts {
+        let summary = &account.summary;
+        let _ = writeln!(
+            html,
+            r#"<tr><td>{}</td><td>{}</td><td class="num">{}</td><td class="num">{}</td>{}</tr>"#,
+            escape(&summary.account_name),
+            escape(summary.provider.display_name()),
+            format_amount(summary.last_month_cost, &summary.currency),
+            format_amount(summary.current_month_cost, &summary.currency),
+            change_cell(summary.current_month_cost, summary.last_month_cost),
+        );
+    }
+    html.push_str("</table>\n</section>\n");
+
+    // Budgets
+    let budgets: Vec<&BudgetStatus> = report
+        .accounts
+        .iter()
+        .filter_map(|a| a.budget.as_ref())
+        .collect();
+    if !budgets.is_empty() {
+        html.push_str("<section>\n<h2>Budget Status</h2>\n<table>\n<tr><th>Account</th><th class=\"num\">Budget</th><th class=\"num\">Spent</th><th class=\"num\">Used</th><th class=\"num\">Remaining</th><th>Status</th></tr>\n");
+        for budget in budgets {
+            let (class, status) = if budget.percentage_used >= 100.0 {
+                ("over", "Over budget")
+            } else if budget.alert_triggered {
+                ("alert", "Alert")
+            } else {
+                ("ok", "On track")
+            };
+            let _ = writeln!(
+                html,
+                r#"<tr><td>{}</td><td class="num">{}</td><td class="num">{}</td><td class="num">{:.0}%</td><td class="num">{}</td><td class="{}">{}</td></tr>"#,
+                escape(&budget.account_name),
+                format_amount(budget.monthly_budget, &budget.currency),
+                format_amount(budget.current_cost, &budget.currency),
+                budget.percentage_used,
+                format_amount(budget.remaining, &budget.currency),
+                class,
+                status,
+            );
+        }
+        html.push_str("</table>\n</section>\n");
+    }
+
+    // Top services
+    let top = report.top_services();
+    if !top.is_empty() {
+        html.push_str("<section>\n<h2>Top Services</h2>\n<table>\n<tr><th>Service</th><th>Provider</th><th class=\"num\">Cost</th></tr>\n");
+        for (provider, service) in &top {
+            let _ = writeln!(
+                html,
+                r#"<tr><td>{}</td><td>{}</td><td class="num">{}</td></tr>"#,
+                escape(&service.service),
+                escape(provider.display_name()),
+                format_amount(service.amount, &service.currency),
+            );
+        }
+        html.push_str("</table>\n</section>\n");
+    }
+
+    // Largest movers
+    let movers = report.largest_movers();
+    if !movers.is_empty() {
+        html.push_str("<section>\n<h2>Largest Movers</h2>\n<table>\n<tr><th>Service</th><th>Account</th><th class=\"num\">Previous</th><th class=\"num\">Current</th><th class=\"num\">Change</th></tr>\n");
+        for mover in &movers {
+            let delta = mover.delta();
+            let _ = writeln!(
+                html,
+                r#"<tr><td>{}</td><td>{}</td><td class="num">{}</td><td class="num">{}</td><td class="num {}">{}{}</td></tr>"#,
+                escape(&mover.service),
+                escape(&mover.account_name),
+                format_amount(mover.previous, &mover.currency),
+                format_amount(mover.current, &mover.currency),
+                if delta > 0.0 { "up" } else { "down" },
+                if delta > 0.0 { "+" } else { "" },
+                format_amount(delta, &mover.currency),
+            );
+        }
+        html.push_str("</table>\n</section>\n");
+    }
+
+    // Daily trend
+    let daily = report.daily_totals();
+    if !daily.is_empty() {
+        html.push_str("<section>\n<h2>Daily Trend</h2>\n");
+        for (currency, points) in &daily {
+            let _ = writeln!(
+                html,
+                "<p class=\"muted\">All accounts, {}</p>\n{}",
+                escape(currency),
+                line_chart_svg(points, currency)
+            );
+        }
+        html.push_str("</section>\n");
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+/// Render a short plain text version of the report (totals and budget alerts)
+pub fn render_text(report: &CostReport) -> String {
+    let mut text = format!("CloudBridge cost report - {}\n\n", report.period.title);
+
+    if report.accounts.is_empty() {
+        text.push_str("No enabled accounts with cost data.\n");
+        return text;
+    }
+
+    for total in report.provider_totals() {
+        let change = change_percent(total.current, total.previous)
+            .map(|c| format!("{:+.1}%", c))
+            .unwrap_or_else(|| "n/a".to_string());
+        let _ = writeln!(
+            text,
+            "{}: {} (previous {}, {})",
+            total.provider.display_name(),
+            format_amount(total.current, &total.currency),
+            format_amount(total.previous, &total.currency),
+            change
+        );
+    }
+
+    for budget in report.accounts.iter().filter_map(|a| a.budget.as_ref()) {
+        if budget.alert_triggered {
+            let _ = writeln!(
+                text,
+                "Budget alert: {} at {:.0}% of {}",
+                budget.account_name,
+                budget.percentage_used,
+                format_amount(budget.monthly_budget, &budget.currency)
+            );
+        }
+    }
+
+    text.push_str("\nOpen the HTML version of this email for the full report.\n");
+    text
+}
+
+/// Write the report as HTML, or as PDF when the path ends in `.pdf`
+pub fn write_report(report: &CostReport, path: &Path) -> Result<()> {
+    let html = render_html(report);
+    let is_pdf = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("pdf"));
+
+    if !is_pdf {
+        std::fs::write(path, html)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        tracing::info!("Wrote report to {}", path.display());
+        return Ok(());
+    }
+
+    let html_path =
+        std::env::temp_dir().join(format!("cloudbridge-report-{}.html", uuid::Uuid::new_v4()));
+    std::fs::write(&html_path, html)?;
+    let result = print_pdf(&html_path, path);
+    std::fs::remove_file(&html_path).ok();
+    result
+}
+
+/// Well-known install locations of Chromium-based browsers outside PATH
+const BROWSER_PATHS: &[&str] = &[
+    "/Applications/Google Chrome.app/Contents/MacOS/Google Chrome",
+    "/Applications/Chromium.app/Contents/MacOS/Chromium",
+    "/Applications/Microsoft Edge.app/Contents/MacOS/Microsoft Edge",
+    r"C:\Program Files\Google\Chrome\Application\chrome.exe",
+    r"C:\Program Files (x86)\Google\Chrome\Application\chrome.exe",
+    r"C:\Program Files (x86)\Microsoft\Edge\Application\msedge.exe",
+    r"C:\Program Files\Microsoft\Edge\Application\msedge.exe",
+];
+
+/// Browser executables searched on PATH
+const BROWSER_NAMES: &[&str] = &[
+    "chromium",
+    "chromium-browser",
+    "google-chrome",
+    "google-chrome-stable",
+    "microsoft-edge",
+    "chrome",
+    "msedge",
+];
+
+/// Find a Chromium-based browser for PDF printing
+fn find_browser() -> Option<PathBuf> {
+    if let Some(browser) = load_config().ok().and_then(|c| c.pdf_browser) {
+        return Some(browser);
+    }
+
+    let path_var = std::env::var_os("PATH").unwrap_or_default();
+    std::env::split_paths(&path_var)
+        .flat_map(|dir| {
+            BROWSER_NAMES.iter().map(move |name| {
+                dir.join(name)
+                    .with_extension(std::env::consts::EXE_EXTENSION)
+            })
+        })
+        .chain(BROWSER_PATHS.iter().map(PathBuf::from))
+        .find(|path| path.is_file())
+}
+
+/// Print an HTML file to PDF with a headless browser
+fn print_pdf(html_path: &Path, pdf_path: &Path) -> Result<()> {
+    let browser = find_browser().ok_or_else(|| {
+        anyhow::anyhow!(
+            "PDF output needs Chrome, Chromium or Edge. Install one or set pdf_browser in config.json"
+        )
+    })?;
+
+    let output = std::process::Command::new(&browser)
+        .arg("--headless")
+        .arg("--disable-gpu")
+        .arg("--no-pdf-header-footer")
+        .arg(format!("--print-to-pdf={}", pdf_path.display()))
+        .arg(html_path)
+        .output()
+        .with_context(|| format!("Failed to run {}", browser.display()))?;
+
+    if !output.status.success() || !pdf_path.exists() {
+        anyhow::bail!(
+            "{} failed to print the report: {}",
+            browser.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    tracing::info!("Wrote PDF report to {}", pdf_path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cloud::DailyCost;
+
+    fn service(name: &str, amount: f64) -> ServiceCost {
+        ServiceCost {
+            service: name.to_string(),
+            amount,
+            currency: "USD".to_string(),
+        }
+    }
+
+    fn sample_report() -> CostReport {
+        let summary = CostSummary {
+            account_id: "acc-1".to_string(),
+            account_name: "Prod <main>".to_string(),
+            provider: CloudProvider::AWS,
+            current_month_cost: 1500.0,
+            last_month_cost: 1000.0,
+            currency: "USD".to_string(),
+            month_over_month_change: 50.0,
+            current_month_details: vec![service("Amazon EC2", 1200.0), service("Amazon S3", 300.0)],
+            last_month_details: vec![service("Amazon EC2", 600.0), service("Amazon S3", 400.0)],
+        };
+        let trend = CostTrend {
+            account_id: "acc-1".to_string(),
+            currency: "USD".to_string(),
+            daily_costs: vec![
+                DailyCost {
+                    date: "2024-05-01".to_string(),
+                    amount: 40.0,
+                },
+                DailyCost {
+                    date: "2024-05-02".to_string(),
+                    amount: 60.0,
+                },
+            ],
+        };
+        let budget = BudgetStatus {
+            account_id: "acc-1".to_string(),
+            account_name: "Prod <main>".to_string(),
+            monthly_budget: 1200.0,
+            current_cost: 1500.0,
+            currency: "USD".to_string(),
+            percentage_used: 125.0,
+            remaining: -300.0,
+            alert_triggered: true,
+        };
+
+        CostReport {
+            period: ReportPeriod::month("2024-05").unwrap(),
+            generated_at: Utc::now(),
+            accounts: vec![AccountReport {
+                summary,
+                trend: Some(trend),
+                budget: Some(budget),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_report_period() {
+        let period = ReportPeriod::month("2024-01").unwrap();
+        assert_eq!(period.title, "January 2024");
+        assert_eq!(period.start, "2024-01-01");
+        assert_eq!(period.end, "2024-02-01");
+        assert_eq!(period.previous_start, "2023-12-01");
+        assert_eq!(period.previous_end, "2024-01-01");
+        assert!(ReportPeriod::month("2024-13").is_err());
+
+        let week = ReportPeriod::week(NaiveDate::from_ymd_opt(2024, 12, 30).unwrap());
+        assert_eq!(week.title, "Week of December 30, 2024");
+        assert_eq!(week.end, "2025-01-06");
+        assert_eq!(week.previous_start, "2024-12-23");
+        assert!(!week.calendar_month);
+        assert_eq!(format_amount(1234567.891, "USD"), "1,234,567.89 USD");
+        assert_eq!(format_amount(-300.0, "CNY"), "-300.00 CNY");
+    }
+
+    #[test]
+    fn test_movers_and_top_services() {
+        let report = sample_report();
+
+        let movers = report.largest_movers();
+        assert_eq!(movers[0].service, "Amazon EC2");
+        assert_eq!(movers[0].delta(), 600.0);
+        assert_eq!(movers[1].delta(), -100.0);
+
+        let top = report.top_services();
+        assert_eq!(top[0].1.service, "Amazon EC2");
+        assert_eq!(top.len(), 2);
+
+        let totals = report.provider_totals();
+        assert_eq!(totals.len(), 1);
+        assert_eq!(
+            change_percent(totals[0].current, totals[0].previous),
+            Some(50.0)
+        );
+    }
+
+    #[test]
+    fn test_render_html() {
+        let html = render_html(&sample_report());
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("Cost Report - May 2024"));
+        assert!(html.contains("Totals by Provider"));
+        assert!(html.contains("1,500.00 USD"));
+        assert!(html.contains("+50.0%"));
+        assert!(html.contains("Over budget"));
+        assert!(html.contains("Largest Movers"));
+        assert!(html.contains("+600.00 USD"));
+        assert!(html.contains("<polyline"));
+        assert_eq!(html.matches("<svg").count(), 2);
+        // Names are escaped and nothing is loaded from outside the document
+        assert!(html.contains("Prod &lt;main&gt;"));
+        assert!(!html.contains("<main>"));
+        assert!(!html.contains("src=\"http"));
+    }
+}
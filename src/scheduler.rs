@@ -0,0 +1,202 @@
+//! Background sync scheduling
+//!
+//! Re-syncs account cost summaries every `refresh_interval_minutes`. Sync cycles skip
+//! accounts whose cache is still fresh, space out calls to the same provider, add
+//! jitter to the interval and back off exponentially after failed cycles.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use rand::Rng;
+use std::collections::HashMap;
+use std::time::Instant;
+
+use crate::cloud::{fetch_cost_summaries, CloudProvider};
+use crate::config::load_config;
+use crate::db;
+
+/// Share of the interval added or removed at random so clients do not sync in lockstep
+const JITTER_FRACTION: f64 = 0.1;
+
+/// Failed cycles after which the delay stops doubling
+const MAX_BACKOFF_DOUBLINGS: u32 = 4;
+
+/// Upper bound for the delay after repeated failures
+const MAX_DELAY_HOURS: i64 = 24;
+
+/// Delay until the next sync.
+///
+/// `failures` is the number of consecutive failed cycles; `jitter` is in [-1, 1] and
+/// scales the random offset.
+pub fn next_delay(interval_minutes: u32, failures: u32, jitter: f64) -> Duration {
+    let base = interval_minutes as f64 * 60.0;
+    let backoff = base * 2f64.powi(failures.min(MAX_BACKOFF_DOUBLINGS) as i32);
+    let capped = backoff.min((MAX_DELAY_HOURS * 3600) as f64);
+    let seconds = capped * (1.0 + JITTER_FRACTION * jitter.clamp(-1.0, 1.0));
+    Duration::seconds(seconds.max(60.0) as i64)
+}
+
+/// Delay until the next sync with a random jitter
+pub fn next_delay_with_jitter(interval_minutes: u32, failures: u32) -> Duration {
+    next_delay(
+        interval_minutes,
+        failures,
+        rand::thread_rng().gen_range(-1.0..=1.0),
+    )
+}
+
+/// Minimum gap between two calls to the same provider during a sync cycle.
+///
+/// Cost Explorer allows only a few requests per second per account and charges per
+/// request; the Aliyun billing API is limited per account as well.
+pub fn provider_spacing(provider: CloudProvider) -> std::time::Duration {
+    match provider {
+        CloudProvider::AWS => std::time::Duration::from_secs(2),
+        CloudProvider::Aliyun => std::time::Duration::from_secs(1),
+        _ => std::time::Duration::from_millis(200),
+    }
+}
+
+/// Human readable countdown, e.g. "45s", "12m" or "1h 5m"
+pub fn format_countdown(duration: Duration) -> String {
+    let seconds = duration.num_seconds().max(0);
+    if seconds < 60 {
+        format!("{}s", seconds)
+    } else if seconds < 3600 {
+        format!("{}m", seconds / 60)
+    } else {
+        format!("{}h {}m", seconds / 3600, seconds % 3600 / 60)
+    }
+}
+
+/// Result of one sync cycle
+#[derive(Debug, Clone, Default)]
+pub struct SyncOutcome {
+    pub finished_at: Option<DateTime<Utc>>,
+    /// Accounts fetched from the provider or re-read from CUR files
+    pub synced: usize,
+    /// Accounts whose cache was still fresh
+    pub skipped: usize,
+    /// Account names and errors of failed accounts
+    pub failed: Vec<(String, String)>,
+}
+
+impl SyncOutcome {
+    /// A cycle fails when accounts failed and none could be synced
+    pub fn is_failure(&self) -> bool {
+        !self.failed.is_empty() && self.synced == 0
+    }
+
+    pub fn summary(&self) -> String {
+        let mut parts = vec![format!("{} synced", self.synced)];
+        if self.skipped > 0 {
+            parts.push(format!("{} fresh", self.skipped));
+        }
+        if !self.failed.is_empty() {
+            parts.push(format!("{} failed", self.failed.len()));
+        }
+        parts.join(", ")
+    }
+}
+
+/// Sync the cost summary of every enabled account whose cache has expired
+pub fn run_sync_cycle() -> Result<SyncOutcome> {
+    let config = load_config().unwrap_or_default();
+    let mut outcome = SyncOutcome::default();
+    let mut last_call: HashMap<CloudProvider, Instant> = HashMap::new();
+
+    for account in db::get_all_accounts()? {
+        if !account.enabled {
+            continue;
+        }
+
+        // Unchanged CUR files are skipped by the ingestion itself
+        if let Some(result) = crate::cur::ingest_configured(&account, &config) {
+            match result {
+                Ok(_) => outcome.synced += 1,
+                Err(e) => outcome
+                    .failed
+                    .push((account.name.clone(), crate::cloud::describe_error(&e))),
+            }
+            continue;
+        }
+
+        let cached = match crate::cloud::member_billing_for(&account, &config) {
+            Some(billing) => crate::cloud::cached_member_summaries(&account, &billing).is_some(),
+            None => db::get_cached_cost_summary_with_account(
+                &account.id,
+                &account.name,
+                &account.provider,
+            )
+            .ok()
+            .flatten()
+            .is_some(),
+        };
+        if cached {
+            outcome.skipped += 1;
+            continue;
+        }
+
+        if let Some(last) = last_call.get(&account.provider) {
+            let spacing = provider_spacing(account.provider);
+            let elapsed = last.elapsed();
+            if elapsed < spacing {
+                std::thread::sleep(spacing - elapsed);
+            }
+        }
+        last_call.insert(account.provider, Instant::now());
+
+        let result = fetch_cost_summaries(&account, &config)
+            .and_then(|summaries| summaries.iter().try_for_each(db::save_cost_summary_cache));
+        match result {
+            Ok(()) => outcome.synced += 1,
+            Err(e) => {
+                tracing::warn!("Scheduled sync failed for {}: {}", account.name, e);
+                outcome
+                    .failed
+                    .push((account.name.clone(), crate::cloud::describe_error(&e)));
+            }
+        }
+    }
+
+    outcome.finished_at = Some(Utc::now());
+    tracing::info!("Scheduled sync finished: {}", outcome.summary());
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_delay_backoff_and_jitter() {
+        assert_eq!(next_delay(60, 0, 0.0), Duration::minutes(60));
+        assert_eq!(next_delay(60, 0, 1.0), Duration::minutes(66));
+        assert_eq!(next_delay(60, 0, -1.0), Duration::minutes(54));
+        assert_eq!(next_delay(60, 1, 0.0), Duration::minutes(120));
+        assert_eq!(next_delay(60, 3, 0.0), Duration::minutes(480));
+        // Doubling stops after a few failures and never exceeds a day
+        assert_eq!(next_delay(60, 10, 0.0), Duration::minutes(960));
+        assert_eq!(next_delay(720, 5, 0.0), Duration::hours(24));
+        // Never sync more than once a minute
+        assert_eq!(next_delay(0, 0, -1.0), Duration::seconds(60));
+
+        for _ in 0..20 {
+            let delay = next_delay_with_jitter(30, 0);
+            assert!(delay >= Duration::minutes(27) && delay <= Duration::minutes(33));
+        }
+    }
+
+    #[test]
+    fn test_outcome_summary() {
+        let outcome = SyncOutcome {
+            finished_at: None,
+            synced: 2,
+            skipped: 3,
+            failed: vec![("Prod".to_string(), "throttled".to_string())],
+        };
+        assert_eq!(outcome.summary(), "2 synced, 3 fresh, 1 failed");
+        assert!(!outcome.is_failure());
+        assert_eq!(format_countdown(Duration::seconds(3900)), "1h 5m");
+        assert_eq!(format_countdown(Duration::seconds(125)), "2m");
+    }
+}
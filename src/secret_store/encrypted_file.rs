@@ -0,0 +1,141 @@
+//! Encrypted file backend
+//!
+//! Secrets are kept in `secrets.json` in the app data directory, each value encrypted
+//! with AES-256-GCM under the app's encryption key (derived from the master
+//! passphrase when one is set). Used where no OS keyring is available.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use super::{SecretBackend, SecretKey};
+use crate::config::{get_app_data_dir, SecretBackendKind};
+use crate::crypto::{get_crypto_manager, CryptoManager};
+
+const FILE_FORMAT: &str = "cloudbridge-secrets";
+const FILE_VERSION: u32 = 1;
+
+/// Serializes read-modify-write cycles of the secrets file
+static FILE_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SecretsFile {
+    format: String,
+    version: u32,
+    /// Encrypted values keyed `service/user`
+    entries: BTreeMap<String, String>,
+}
+
+impl Default for SecretsFile {
+    fn default() -> Self {
+        Self {
+            format: FILE_FORMAT.to_string(),
+            version: FILE_VERSION,
+            entries: BTreeMap::new(),
+        }
+    }
+}
+
+pub struct EncryptedFileBackend {
+    path: PathBuf,
+    /// Fixed key for tests; the app's key otherwise
+    crypto: Option<CryptoManager>,
+}
+
+impl EncryptedFileBackend {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path, crypto: None }
+    }
+
+    /// Backend for `secrets.json` in the app data directory
+    pub fn default_location() -> Result<Self> {
+        Ok(Self::new(get_app_data_dir()?.join("secrets.json")))
+    }
+
+    #[cfg(test)]
+    pub fn with_key(path: PathBuf, key: &[u8; 32]) -> Self {
+        Self {
+            path,
+            crypto: Some(CryptoManager::new(key)),
+        }
+    }
+
+    fn with_crypto<T>(&self, f: impl FnOnce(&CryptoManager) -> Result<T>) -> Result<T> {
+        match &self.crypto {
+            Some(crypto) => f(crypto),
+            None => f(&get_crypto_manager()?),
+        }
+    }
+
+    fn read(&self) -> Result<SecretsFile> {
+        if !self.path.exists() {
+            return Ok(SecretsFile::default());
+        }
+        let file: SecretsFile = serde_json::from_str(&fs::read_to_string(&self.path)?)
+            .with_context(|| format!("Damaged secrets file {}", self.path.display()))?;
+        if file.format != FILE_FORMAT || file.version > FILE_VERSION {
+            anyhow::bail!("Unsupported secrets file {}", self.path.display());
+        }
+        Ok(file)
+    }
+
+    /// Write through a temporary file so an interruption never truncates the secrets
+    fn write(&self, file: &SecretsFile) -> Result<()> {
+        let partial = self.path.with_extension("json.partial");
+        fs::write(&partial, serde_json::to_string_pretty(file)?)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&partial, fs::Permissions::from_mode(0o600))?;
+        }
+        fs::rename(&partial, &self.path)?;
+        Ok(())
+    }
+
+    fn update(&self, f: impl FnOnce(&mut BTreeMap<String, String>) -> Result<()>) -> Result<()> {
+        let _guard = FILE_LOCK.lock().map_err(|e| anyhow::anyhow!("{}", e))?;
+        let mut file = self.read()?;
+        f(&mut file.entries)?;
+        self.write(&file)
+    }
+}
+
+impl SecretBackend for EncryptedFileBackend {
+    fn kind(&self) -> SecretBackendKind {
+        SecretBackendKind::File
+    }
+
+    fn is_available(&self) -> bool {
+        true
+    }
+
+    fn get(&self, key: &SecretKey) -> Result<Option<String>> {
+        match self.read()?.entries.get(&key.id()) {
+            Some(encrypted) => self
+                .with_crypto(|crypto| crypto.decrypt(encrypted))
+                .map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn set(&self, key: &SecretKey, value: &str) -> Result<()> {
+        let encrypted = self.with_crypto(|crypto| crypto.encrypt(value))?;
+        self.update(|entries| {
+            entries.insert(key.id(), encrypted);
+            Ok(())
+        })
+    }
+
+    fn delete(&self, key: &SecretKey) -> Result<()> {
+        if !self.path.exists() {
+            return Ok(());
+        }
+        self.update(|entries| {
+            entries.remove(&key.id());
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,73 @@
+//! Environment variable backend for CI and containers
+//!
+//! Each secret is read from a variable named after its key, e.g.
+//! `CLOUDBRIDGE_AK_<ACCOUNT_ID>`, or from the file named by `<VARIABLE>_FILE` (as
+//! used for Docker and Kubernetes secrets). Nothing is ever written.
+
+use anyhow::Result;
+
+use super::{SecretBackend, SecretKey};
+use crate::config::SecretBackendKind;
+
+pub struct EnvBackend;
+
+/// Variable holding a secret: `CLOUDBRIDGE_<KIND>_<USER>`, upper case, with every
+/// character other than letters and digits replaced by `_`
+pub fn variable_name(key: &SecretKey) -> String {
+    let kind = key
+        .service
+        .rsplit_once(':')
+        .map_or(key.service.as_str(), |(_, kind)| kind);
+    format!("CLOUDBRIDGE_{}_{}", kind, key.user)
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+impl SecretBackend for EnvBackend {
+    fn kind(&self) -> SecretBackendKind {
+        SecretBackendKind::Env
+    }
+
+    fn is_available(&self) -> bool {
+        true
+    }
+
+    fn get(&self, key: &SecretKey) -> Result<Option<String>> {
+        let name = variable_name(key);
+        if let Ok(value) = std::env::var(&name) {
+            return Ok(Some(value));
+        }
+        match std::env::var(format!("{}_FILE", name)) {
+            Ok(path) => {
+                let contents = std::fs::read_to_string(&path).map_err(|e| {
+                    anyhow::anyhow!("Failed to read {}_FILE ({}): {}", name, path, e)
+                })?;
+                Ok(Some(contents.trim_end_matches(['\r', '\n']).to_string()))
+            }
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Accepts only the value the environment already provides
+    fn set(&self, key: &SecretKey, value: &str) -> Result<()> {
+        if self.get(key)?.as_deref() == Some(value) {
+            return Ok(());
+        }
+        anyhow::bail!(
+            "Secrets from the environment are read-only; set {} (or {}_FILE) instead",
+            variable_name(key),
+            variable_name(key)
+        )
+    }
+
+    fn delete(&self, _key: &SecretKey) -> Result<()> {
+        Ok(())
+    }
+}
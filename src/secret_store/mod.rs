@@ -0,0 +1,363 @@
+//! Secret storage
+//!
+//! Credentials and the SMTP password are kept in a pluggable backend selected by
+//! `secret_backend` in config.json: the OS keyring, an encrypted file, or (read-only)
+//! environment variables. When the OS keyring is unavailable, e.g. on headless Linux
+//! without a Secret Service daemon, the encrypted file is used instead. Secrets found
+//! in another backend than the active one are moved over on first use.
+//!
+//! Account credentials may also be references to an external secret manager (see
+//! `references`), resolved only when a provider is called.
+
+mod encrypted_file;
+mod env;
+mod os_keyring;
+mod references;
+
+use anyhow::{Context, Result};
+
+use crate::cloud::CloudAccount;
+use crate::config::{load_config, save_config, SecretBackendKind};
+use crate::db;
+use crate::redact;
+
+pub use encrypted_file::EncryptedFileBackend;
+pub use env::EnvBackend;
+pub use os_keyring::KeyringBackend;
+pub use references::{clear_cache as clear_resolved_references, is_reference};
+
+const SERVICE_NAME: &str = "CloudBridge";
+
+/// Location of a secret: keyring service and user name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecretKey {
+    pub service: String,
+    pub user: String,
+}
+
+impl SecretKey {
+    fn new(kind: &str, user: &str) -> Self {
+        Self {
+            service: format!("{}:{}", SERVICE_NAME, kind),
+            user: user.to_string(),
+        }
+    }
+
+    pub fn access_key_id(account_id: &str) -> Self {
+        Self::new("ak", account_id)
+    }
+
+    pub fn secret_access_key(account_id: &str) -> Self {
+        Self::new("sk", account_id)
+    }
+
+    pub fn smtp_password(username: &str) -> Self {
+        Self::new("smtp", username)
+    }
+
+    /// Key used by the encrypted file backend
+    pub fn id(&self) -> String {
+        format!("{}/{}", self.service, self.user)
+    }
+}
+
+/// A place secrets are stored
+pub trait SecretBackend {
+    fn kind(&self) -> SecretBackendKind;
+    /// Whether the backend can be used on this machine
+    fn is_available(&self) -> bool;
+    fn get(&self, key: &SecretKey) -> Result<Option<String>>;
+    fn set(&self, key: &SecretKey, value: &str) -> Result<()>;
+    /// Remove a secret; succeeds when there is none
+    fn delete(&self, key: &SecretKey) -> Result<()>;
+}
+
+fn file_backend() -> Result<Box<dyn SecretBackend>> {
+    Ok(Box::new(EncryptedFileBackend::default_location()?))
+}
+
+/// Backend used for `kind`, falling back to the encrypted file when the keyring is
+/// unavailable
+pub fn resolve_backend(kind: SecretBackendKind) -> Result<Box<dyn SecretBackend>> {
+    match kind {
+        SecretBackendKind::Auto | SecretBackendKind::Keyring => {
+            if KeyringBackend.is_available() {
+                Ok(Box::new(KeyringBackend))
+            } else {
+                if kind == SecretBackendKind::Keyring {
+                    tracing::warn!("OS keyring unavailable, using the encrypted secrets file");
+                }
+                file_backend()
+            }
+        }
+        SecretBackendKind::File => file_backend(),
+        SecretBackendKind::Env => Ok(Box::new(EnvBackend)),
+    }
+}
+
+/// Backend selected in the saved configuration
+pub fn active_backend() -> Result<Box<dyn SecretBackend>> {
+    resolve_backend(load_config()?.secret_backend)
+}
+
+/// Other backends that may hold secrets written before a backend change
+fn previous_backends(active: SecretBackendKind) -> Vec<Box<dyn SecretBackend>> {
+    let mut backends: Vec<Box<dyn SecretBackend>> = Vec::new();
+    if active != SecretBackendKind::Keyring && KeyringBackend.is_available() {
+        backends.push(Box::new(KeyringBackend));
+    }
+    if active != SecretBackendKind::File {
+        if let Ok(file) = file_backend() {
+            backends.push(file);
+        }
+    }
+    backends
+}
+
+fn get_secret(key: &SecretKey) -> Result<Option<String>> {
+    let value = load_secret(key)?;
+    if let Some(value) = &value {
+        redact::register_secret(value);
+    }
+    Ok(value)
+}
+
+fn load_secret(key: &SecretKey) -> Result<Option<String>> {
+    let backend = active_backend()?;
+    if let Some(value) = backend.get(key)? {
+        return Ok(Some(value));
+    }
+    if backend.kind() == SecretBackendKind::Env {
+        return Ok(None);
+    }
+
+    for previous in previous_backends(backend.kind()) {
+        if let Ok(Some(value)) = previous.get(key) {
+            backend.set(key, &value)?;
+            if let Err(e) = previous.delete(key) {
+                tracing::warn!("Failed to remove {} after moving it: {}", key.id(), e);
+            }
+            tracing::info!(
+                "Moved {} from {} to {}",
+                key.id(),
+                previous.kind().label(),
+                backend.kind().label()
+            );
+            return Ok(Some(value));
+        }
+    }
+    Ok(None)
+}
+
+/// Copy the secrets under `keys` from one backend to another, then remove them from
+/// the source. Returns the number of secrets moved.
+pub fn migrate_secrets(
+    from: &dyn SecretBackend,
+    to: &dyn SecretBackend,
+    keys: &[SecretKey],
+) -> Result<usize> {
+    // Copy everything first, so an interruption leaves the secrets in both places
+    let mut moved = Vec::new();
+    for key in keys {
+        if let Some(value) = from.get(key)? {
+            to.set(key, &value)?;
+            moved.push(key);
+        }
+    }
+    for key in &moved {
+        from.delete(key)?;
+    }
+    Ok(moved.len())
+}
+
+/// Keys of all secrets CloudBridge stores
+fn known_keys() -> Result<Vec<SecretKey>> {
+    let mut keys = Vec::new();
+    for id in db::get_account_ids()? {
+        keys.push(SecretKey::access_key_id(&id));
+        keys.push(SecretKey::secret_access_key(&id));
+    }
+    if let Some(username) = load_config()?.smtp.and_then(|s| s.username) {
+        keys.push(SecretKey::smtp_password(&username));
+    }
+    Ok(keys)
+}
+
+/// Select another backend and move the stored secrets to it. Switching to environment
+/// variables moves nothing, as they cannot be written. Returns the number of secrets moved.
+pub fn switch_backend(kind: SecretBackendKind) -> Result<usize> {
+    let mut config = load_config()?;
+    let from = resolve_backend(config.secret_backend)?;
+    let to = resolve_backend(kind)?;
+
+    let moved = if from.kind() == to.kind() || to.kind() == SecretBackendKind::Env {
+        0
+    } else {
+        migrate_secrets(from.as_ref(), to.as_ref(), &known_keys()?)?
+    };
+
+    config.secret_backend = kind;
+    save_config(&config)?;
+    tracing::info!(
+        "Secret backend set to {} ({} secrets moved)",
+        to.kind().label(),
+        moved
+    );
+    Ok(moved)
+}
+
+pub fn store_account_secrets(
+    account_id: &str,
+    access_key_id: &str,
+    secret_access_key: &str,
+) -> Result<()> {
+    let backend = active_backend()?;
+    backend.set(&SecretKey::access_key_id(account_id), access_key_id)?;
+    backend.set(&SecretKey::secret_access_key(account_id), secret_access_key)?;
+    Ok(())
+}
+
+pub fn get_account_secrets(account_id: &str) -> Result<Option<(String, String)>> {
+    match (
+        get_secret(&SecretKey::access_key_id(account_id))?,
+        get_secret(&SecretKey::secret_access_key(account_id))?,
+    ) {
+        (Some(a), Some(s)) => Ok(Some((a, s))),
+        _ => Ok(None),
+    }
+}
+
+pub fn delete_account_secrets(account_id: &str) -> Result<()> {
+    let backend = active_backend()?;
+    backend.delete(&SecretKey::access_key_id(account_id))?;
+    backend.delete(&SecretKey::secret_access_key(account_id))?;
+    Ok(())
+}
+
+/// Access key ID and secret with references resolved
+pub fn resolve_key_pair(access_key_id: &str, secret_access_key: &str) -> Result<(String, String)> {
+    let pair = (
+        references::resolve(access_key_id).context("Failed to resolve the access key ID")?,
+        references::resolve(secret_access_key)
+            .context("Failed to resolve the secret access key")?,
+    );
+    redact::register_secret(&pair.0);
+    redact::register_secret(&pair.1);
+    Ok(pair)
+}
+
+/// Copy of `account` with credential references resolved, for calling its provider.
+/// The copy must not be saved, so resolved values are never persisted.
+pub fn resolve_credentials(account: &CloudAccount) -> Result<CloudAccount> {
+    let (access_key_id, secret_access_key) =
+        resolve_key_pair(&account.access_key_id, &account.secret_access_key)
+            .with_context(|| format!("Credentials of {}", account.name))?;
+    Ok(CloudAccount {
+        access_key_id,
+        secret_access_key,
+        ..account.clone()
+    })
+}
+
+pub fn store_smtp_password(username: &str, password: &str) -> Result<()> {
+    active_backend()?.set(&SecretKey::smtp_password(username), password)
+}
+
+pub fn get_smtp_password(username: &str) -> Result<Option<String>> {
+    get_secret(&SecretKey::smtp_password(username))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_file() -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("cloudbridge-secrets-{}.json", uuid::Uuid::new_v4()))
+    }
+
+    #[test]
+    fn test_encrypted_file_backend() {
+        let path = scratch_file();
+        let backend = EncryptedFileBackend::with_key(path.clone(), &[7u8; 32]);
+        let key = SecretKey::secret_access_key("acc-1");
+
+        assert_eq!(backend.get(&key).unwrap(), None);
+        backend.set(&key, "wJalrXUtnFEMI/K7MDENG").unwrap();
+        backend
+            .set(&SecretKey::smtp_password("reports"), "smtp-secret")
+            .unwrap();
+        assert_eq!(
+            backend.get(&key).unwrap().as_deref(),
+            Some("wJalrXUtnFEMI/K7MDENG")
+        );
+        // Values are not readable from the file
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(contents.contains("CloudBridge:sk/acc-1"));
+        assert!(!contents.contains("wJalrXUtnFEMI"));
+
+        // Another key cannot decrypt
+        let other = EncryptedFileBackend::with_key(path.clone(), &[8u8; 32]);
+        assert!(other.get(&key).is_err());
+
+        backend.delete(&key).unwrap();
+        assert_eq!(backend.get(&key).unwrap(), None);
+        assert!(backend
+            .get(&SecretKey::smtp_password("reports"))
+            .unwrap()
+            .is_some());
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_env_backend() {
+        let key = SecretKey::access_key_id("3f2a-prod.eu");
+        assert_eq!(env::variable_name(&key), "CLOUDBRIDGE_AK_3F2A_PROD_EU");
+        assert_eq!(
+            env::variable_name(&SecretKey::smtp_password("reports@example.com")),
+            "CLOUDBRIDGE_SMTP_REPORTS_EXAMPLE_COM"
+        );
+
+        assert_eq!(EnvBackend.get(&key).unwrap(), None);
+        std::env::set_var("CLOUDBRIDGE_AK_3F2A_PROD_EU", "AKIAFROMENV");
+        assert_eq!(
+            EnvBackend.get(&key).unwrap().as_deref(),
+            Some("AKIAFROMENV")
+        );
+        assert!(EnvBackend.set(&key, "AKIAFROMENV").is_ok());
+        assert!(EnvBackend.set(&key, "AKIAOTHER").is_err());
+
+        // File references, as mounted by Docker and Kubernetes secrets
+        let path = scratch_file();
+        std::fs::write(&path, "secret-from-file\n").unwrap();
+        let file_key = SecretKey::secret_access_key("3f2a-prod.eu");
+        std::env::set_var("CLOUDBRIDGE_SK_3F2A_PROD_EU_FILE", &path);
+        assert_eq!(
+            EnvBackend.get(&file_key).unwrap().as_deref(),
+            Some("secret-from-file")
+        );
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_migrate_between_backends() {
+        let (from_path, to_path) = (scratch_file(), scratch_file());
+        let from = EncryptedFileBackend::with_key(from_path.clone(), &[1u8; 32]);
+        let to = EncryptedFileBackend::with_key(to_path.clone(), &[2u8; 32]);
+        let keys = vec![
+            SecretKey::access_key_id("acc-1"),
+            SecretKey::secret_access_key("acc-1"),
+            SecretKey::smtp_password("reports"),
+        ];
+        from.set(&keys[0], "AKIA1").unwrap();
+        from.set(&keys[1], "SECRET1").unwrap();
+
+        assert_eq!(migrate_secrets(&from, &to, &keys).unwrap(), 2);
+        assert_eq!(to.get(&keys[0]).unwrap().as_deref(), Some("AKIA1"));
+        assert_eq!(to.get(&keys[1]).unwrap().as_deref(), Some("SECRET1"));
+        assert_eq!(to.get(&keys[2]).unwrap(), None);
+        assert_eq!(from.get(&keys[0]).unwrap(), None);
+
+        let _ = std::fs::remove_file(from_path);
+        let _ = std::fs::remove_file(to_path);
+    }
+}
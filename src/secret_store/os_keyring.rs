@@ -0,0 +1,78 @@
+//! OS keyring backend
+
+use ::keyring::Entry;
+use anyhow::Result;
+use std::sync::OnceLock;
+
+use super::{SecretBackend, SecretKey};
+use crate::config::{load_config, SecretBackendKind};
+use crate::crypto::get_crypto_manager;
+
+/// Marks keyring values encrypted with the master key
+const ENCRYPTED_PREFIX: &str = "cloudbridge-enc:";
+
+/// Windows Credential Manager / macOS Keychain / Linux Secret Service
+pub struct KeyringBackend;
+
+/// Value as written to the keyring: encrypted in master passphrase mode
+fn protect(value: &str) -> Result<String> {
+    if load_config()?.master_key.is_some() {
+        Ok(format!(
+            "{}{}",
+            ENCRYPTED_PREFIX,
+            get_crypto_manager()?.encrypt(value)?
+        ))
+    } else {
+        Ok(value.to_string())
+    }
+}
+
+/// Plain value of a keyring entry
+fn reveal(stored: String) -> Result<String> {
+    match stored.strip_prefix(ENCRYPTED_PREFIX) {
+        Some(encrypted) => get_crypto_manager()?.decrypt(encrypted),
+        None => Ok(stored),
+    }
+}
+
+impl SecretBackend for KeyringBackend {
+    fn kind(&self) -> SecretBackendKind {
+        SecretBackendKind::Keyring
+    }
+
+    /// Probes the keyring once per run. Headless Linux without a Secret Service
+    /// daemon fails with a platform error rather than "no entry".
+    fn is_available(&self) -> bool {
+        static AVAILABLE: OnceLock<bool> = OnceLock::new();
+        *AVAILABLE.get_or_init(|| {
+            Entry::new("CloudBridge:probe", "probe")
+                .and_then(|entry| match entry.get_password() {
+                    Ok(_) | Err(::keyring::Error::NoEntry) => Ok(()),
+                    Err(e) => Err(e),
+                })
+                .map_err(|e| tracing::warn!("OS keyring unavailable: {}", e))
+                .is_ok()
+        })
+    }
+
+    fn get(&self, key: &SecretKey) -> Result<Option<String>> {
+        let entry = Entry::new(&key.service, &key.user)?;
+        match entry.get_password() {
+            Ok(stored) => Ok(Some(reveal(stored)?)),
+            Err(::keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn set(&self, key: &SecretKey, value: &str) -> Result<()> {
+        Entry::new(&key.service, &key.user)?.set_password(&protect(value)?)?;
+        Ok(())
+    }
+
+    fn delete(&self, key: &SecretKey) -> Result<()> {
+        match Entry::new(&key.service, &key.user)?.delete_password() {
+            Ok(()) | Err(::keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
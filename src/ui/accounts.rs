@@ -8,15 +8,30 @@ use gpui_component::{
     input::{Input, InputState},
     *,
 };
+use std::collections::HashMap;
 use uuid::Uuid;
 
-use crate::cloud::{CloudAccount, CloudProvider};
+use crate::cloud::{BudgetInfo, CloudAccount, CloudProvider, POLICIES_DOC_PATH, POLICIES_DOC_URL};
 use crate::db;
+use crate::redact;
+
+/// Default regions pre-filled in the add-account form
+const DEFAULT_REGIONS: [&str; 2] = ["us-east-1", "cn-hangzhou"];
+
+fn default_region(provider: CloudProvider) -> &'static str {
+    match provider {
+        CloudProvider::AWS => DEFAULT_REGIONS[0],
+        CloudProvider::Aliyun => DEFAULT_REGIONS[1],
+        _ => "",
+    }
+}
 
 /// Account Management View
 pub struct AccountsView {
     /// Account list
     accounts: Vec<CloudAccount>,
+    /// Budgets by account ID
+    budgets: HashMap<String, BudgetInfo>,
     /// Whether to show add dialog
     show_add_dialog: bool,
     /// New account form
@@ -58,6 +73,7 @@ impl AccountsView {
 
         let mut view = Self {
             accounts: Vec::new(),
+            budgets: HashMap::new(),
             show_add_dialog: false,
             new_account_form: NewAccountForm::default(),
             error: None,
@@ -80,7 +96,22 @@ impl AccountsView {
                 self.error = None;
             }
             Err(e) => {
-                self.error = Some(format!("Failed to load accounts: {}", e));
+                self.error = Some(format!(
+                    "Failed to load accounts: {}",
+                    redact::error_message(&e)
+                ));
+            }
+        }
+
+        match db::get_all_budgets() {
+            Ok(budgets) => {
+                self.budgets = budgets
+                    .into_iter()
+                    .map(|b| (b.account_id.clone(), b))
+                    .collect();
+            }
+            Err(e) => {
+                tracing::warn!("Failed to load budgets: {}", e);
             }
         }
     }
@@ -104,43 +135,52 @@ impl AccountsView {
         // Update input placeholders based on cloud provider
         match provider {
             CloudProvider::AWS => {
-                self.ak_input.update(cx, |state, _cx| {
-                    state.set_placeholder("Access Key ID");
+                self.ak_input.update(cx, |state, cx| {
+                    state.set_placeholder("Access Key ID", window, cx);
                 });
-                self.sk_input.update(cx, |state, _cx| {
-                    state.set_placeholder("Secret Access Key");
+                self.sk_input.update(cx, |state, cx| {
+                    state.set_placeholder("Secret Access Key", window, cx);
                 });
-                self.region_input.update(cx, |state, _cx| {
-                    state.set_placeholder("Region (optional, default us-east-1)");
-                    state.set_default_value("us-east-1");
+                self.region_input.update(cx, |state, cx| {
+                    state.set_placeholder("Region (optional, default us-east-1)", window, cx);
                 });
             }
             CloudProvider::Aliyun => {
-                self.ak_input.update(cx, |state, _cx| {
-                    state.set_placeholder("AccessKey ID");
+                self.ak_input.update(cx, |state, cx| {
+                    state.set_placeholder("AccessKey ID", window, cx);
                 });
-                self.sk_input.update(cx, |state, _cx| {
-                    state.set_placeholder("AccessKey Secret");
+                self.sk_input.update(cx, |state, cx| {
+                    state.set_placeholder("AccessKey Secret", window, cx);
                 });
-                self.region_input.update(cx, |state, _cx| {
-                    state.set_placeholder("Region (optional, default cn-hangzhou)");
-                    state.set_default_value("cn-hangzhou");
+                self.region_input.update(cx, |state, cx| {
+                    state.set_placeholder(
+                        "Region (default cn-hangzhou; intl for the international site)",
+                        window,
+                        cx,
+                    );
                 });
             }
             CloudProvider::DeepSeek => {
-                self.ak_input.update(cx, |state, _cx| {
-                    state.set_placeholder("API Key");
+                self.ak_input.update(cx, |state, cx| {
+                    state.set_placeholder("API Key", window, cx);
                 });
-                self.sk_input.update(cx, |state, _cx| {
-                    state.set_placeholder("(Not required, leave empty)");
+                self.sk_input.update(cx, |state, cx| {
+                    state.set_placeholder("(Not required, leave empty)", window, cx);
                 });
-                self.region_input.update(cx, |state, _cx| {
-                    state.set_placeholder("(Not required)");
+                self.region_input.update(cx, |state, cx| {
+                    state.set_placeholder("(Not required)", window, cx);
                 });
             }
 
             _ => {}
         }
+        // Pre-fill the provider's default region unless the user typed their own
+        self.region_input.update(cx, |state, cx| {
+            let current = state.value();
+            if current.is_empty() || DEFAULT_REGIONS.contains(&current.as_ref()) {
+                state.set_value(default_region(provider), window, cx);
+            }
+        });
         cx.notify();
     }
 
@@ -198,7 +238,7 @@ impl AccountsView {
                 self.load_accounts();
             }
             Err(e) => {
-                self.error = Some(format!("Save failed: {}", e));
+                self.error = Some(format!("Save failed: {}", redact::error_message(&e)));
             }
         }
         cx.notify();
@@ -211,7 +251,7 @@ impl AccountsView {
                 self.load_accounts();
             }
             Err(e) => {
-                self.error = Some(format!("Delete failed: {}", e));
+                self.error = Some(format!("Delete failed: {}", redact::error_message(&e)));
             }
         }
         cx.notify();
@@ -245,6 +285,15 @@ impl AccountsView {
         std::thread::spawn(move || {
             use crate::cloud::CloudService;
 
+            let (access_key_id, secret_access_key) =
+                match crate::secret_store::resolve_key_pair(&access_key_id, &secret_access_key) {
+                    Ok(pair) => pair,
+                    Err(e) => {
+                        let _ = tx.send(Err(redact::error_message(&e)));
+                        return;
+                    }
+                };
+
             let result: Result<bool, String> = match provider {
                 CloudProvider::AWS => {
                     let service = crate::cloud::aws::AwsCloudService::new(
@@ -256,7 +305,7 @@ impl AccountsView {
                     );
                     match service.validate_credentials() {
                         Ok(valid) => Ok(valid),
-                        Err(e) => Err(e.to_string()),
+                        Err(e) => Err(crate::cloud::describe_error(&e)),
                     }
                 }
                 CloudProvider::Aliyun => {
@@ -269,7 +318,7 @@ impl AccountsView {
                     );
                     match service.validate_credentials() {
                         Ok(valid) => Ok(valid),
-                        Err(e) => Err(e.to_string()),
+                        Err(e) => Err(crate::cloud::describe_error(&e)),
                     }
                 }
                 CloudProvider::DeepSeek => {
@@ -282,7 +331,7 @@ impl AccountsView {
                     );
                     match service.validate_credentials() {
                         Ok(valid) => Ok(valid),
-                        Err(e) => Err(e.to_string()),
+                        Err(e) => Err(crate::cloud::describe_error(&e)),
                     }
                 }
 
@@ -332,6 +381,89 @@ impl AccountsView {
         .detach();
     }
 
+    /// Import the account's budget from AWS Budgets, or push the CloudBridge budget to AWS
+    fn sync_budget(&mut self, account: &CloudAccount, cx: &mut Context<Self>) {
+        let account = account.clone();
+        let local = self.budgets.get(&account.id).cloned();
+        let config = crate::config::load_config().unwrap_or_default();
+        let account_name = account.name.clone();
+
+        self.success = Some(format!("Syncing budget for {}...", account_name));
+        self.error = None;
+        cx.notify();
+
+        let (tx, rx) = std::sync::mpsc::channel::<Result<Option<BudgetInfo>, String>>();
+
+        std::thread::spawn(move || {
+            let account = match crate::secret_store::resolve_credentials(&account) {
+                Ok(account) => account,
+                Err(e) => {
+                    let _ = tx.send(Err(redact::error_message(&e)));
+                    return;
+                }
+            };
+            let service = crate::cloud::aws::AwsCloudService::new(
+                account.id.clone(),
+                account.name.clone(),
+                account.access_key_id.clone(),
+                account.secret_access_key.clone(),
+                account.region.clone(),
+            );
+            let result = service
+                .sync_budget(
+                    local.as_ref(),
+                    config.push_budgets_to_aws,
+                    config.budget_alert_email.as_deref(),
+                )
+                .and_then(|budget| {
+                    if let Some(budget) = &budget {
+                        db::save_budget(budget)?;
+                    }
+                    Ok(budget)
+                })
+                .map_err(|e| crate::cloud::describe_error(&e));
+
+            let _ = tx.send(result);
+        });
+
+        cx.spawn(async move |this, cx| {
+            let sync_result = smol::unblock(move || {
+                rx.recv_timeout(std::time::Duration::from_secs(60))
+                    .unwrap_or(Err("Budget sync timeout".to_string()))
+            })
+            .await;
+
+            cx.update(|cx| {
+                this.update(cx, |this, cx| {
+                    match sync_result {
+                        Ok(Some(budget)) => {
+                            this.success = Some(format!(
+                                "Budget for {} synced (source: {})",
+                                account_name,
+                                budget.source.label()
+                            ));
+                            this.error = None;
+                            this.load_accounts();
+                        }
+                        Ok(None) => {
+                            this.success =
+                                Some(format!("No monthly cost budget found for {}", account_name));
+                            this.error = None;
+                        }
+                        Err(e) => {
+                            this.error = Some(format!("Budget sync failed: {}", e));
+                            this.success = None;
+                        }
+                    }
+                    cx.notify();
+                })
+                .ok();
+            })
+            .ok();
+        })
+        .detach();
+    }
+
     fn render_provider_selector(&self, cx: &Context<Self>) -> impl IntoElement {
         let is_aws_selected = matches!(self.selected_provider, CloudProvider::AWS);
         let is_aliyun_selected = matches!(self.selected_provider, CloudProvider::Aliyun);
@@ -450,6 +582,8 @@ impl AccountsView {
     fn render_account_row(&self, account: &CloudAccount, cx: &Context<Self>) -> impl IntoElement {
         let account_id = account.id.clone();
         let account_for_validate = account.clone();
+        let account_for_budget = account.clone();
+        let budget = self.budgets.get(&account.id);
 
         div()
             .w_full()
@@ -491,12 +625,38 @@ impl AccountsView {
                                 div()
                                     .text_sm()
                                     .text_color(cx.theme().muted_foreground)
-                                    .child(format!(
-                                        "AK: {}****",
-                                        &account.access_key_id
-                                            [..8.min(account.access_key_id.len())]
+                                    .child(
+                                        if crate::secret_store::is_reference(&account.access_key_id)
+                                        {
+                                            format!("AK: {}", account.access_key_id)
+                                        } else {
+                                            format!(
+                                                "AK: {}****",
+                                                &account.access_key_id
+                                                    [..8.min(account.access_key_id.len())]
+                                            )
+                                        },
+                                    ),
+                            )
+                            .when_some(budget, |this, budget| {
+                                this.child(
+                                    div()
+                                        .text_xs()
+                                        .text_color(cx.theme().muted_foreground)
+                                        .child(format!(
+                                        "Budget: {:.2} {}/month, alert at {:.0}% · Source: {}{}",
+                                        budget.monthly_budget,
+                                        budget.currency,
+                                        budget.alert_threshold,
+                                        budget.source.label(),
+                                        budget
+                                            .external_name
+                                            .as_ref()
+                                            .map(|name| format!(" ({})", name))
+                                            .unwrap_or_default()
                                     )),
-                            ),
+                                )
+                            }),
                     ),
             )
             .child(
@@ -512,6 +672,17 @@ impl AccountsView {
                                 this.validate_account(&account_for_validate, cx);
                             })),
                     )
+                    .when(account.provider == CloudProvider::AWS, |this| {
+                        this.child(
+                            Button::new(SharedString::from(format!("budget-{}", account.id)))
+                                .label("Sync Budget")
+                                .ghost()
+                                .small()
+                                .on_click(cx.listener(move |this, _, _, cx| {
+                                    this.sync_budget(&account_for_budget, cx);
+                                })),
+                        )
+                    })
                     .child(
                         Button::new(SharedString::from(format!("delete-{}", account.id)))
                             .label("Delete")
@@ -640,17 +811,32 @@ impl AccountsView {
             )
     }
 
-    fn render_messages(&self, _cx: &Context<Self>) -> impl IntoElement {
+    fn render_messages(&self, cx: &Context<Self>) -> impl IntoElement {
         div()
             .when_some(self.error.clone(), |el, error| {
+                // Permission errors link to the policies the provider needs
+                let needs_policy = error.contains(POLICIES_DOC_PATH);
                 el.child(
                     div()
                         .w_full()
+                        .h_flex()
+                        .gap_2()
                         .p_3()
                         .rounded_md()
                         .bg(gpui::red().opacity(0.1))
                         .text_color(gpui::red())
-                        .child(error),
+                        .child(div().flex_1().child(error))
+                        .when(needs_policy, |el| {
+                            el.child(
+                                Button::new("policies-doc")
+                                    .label("Required permissions")
+                                    .ghost()
+                                    .small()
+                                    .on_click(cx.listener(|_, _, _, cx| {
+                                        cx.open_url(POLICIES_DOC_URL);
+                                    })),
+                            )
+                        }),
                 )
             })
             .when_some(self.success.clone(), |el, success| {
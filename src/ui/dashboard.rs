@@ -6,7 +6,13 @@ use gpui_component::{button::*, scroll::ScrollableElement, *};
 use std::collections::HashMap;
 
 use super::chart::{CostBarChart, CostStats, ServicePieChart};
-use crate::cloud::{CostSummary, CostTrend};
+use crate::cloud::{
+    AnomalyFeedback, CloudProvider, CommitmentSummary, CommitmentUtilization, CostAnomaly,
+    CostDimension, CostSummary, CostTrend, ServiceCost, POLICIES_DOC_PATH, POLICIES_DOC_URL,
+};
+use crate::redact;
+use crate::scheduler::{self, SyncOutcome};
+use chrono::{DateTime, Utc};
 
 /// Dashboard View
 pub struct DashboardView {
@@ -16,14 +22,45 @@ pub struct DashboardView {
     loading: bool,
     /// Error message
     error: Option<String>,
+    /// Accounts whose costs failed to load (account name, error with suggested fix)
+    account_errors: Vec<(String, String)>,
+    /// Status of the last background action (export, anomaly feedback)
+    status_message: Option<String>,
     /// Currently expanded account ID (for drill-down)
     expanded_account: Option<String>,
     /// Cost trend cache (account_id -> CostTrend)
     cost_trends: HashMap<String, CostTrend>,
     /// Accounts currently loading trends
     loading_trends: HashMap<String, bool>,
+    /// Selected "group by" dimension for the cost breakdown
+    group_by: CostDimension,
+    /// Cost breakdown cache ("account_id|dimension" -> grouped costs)
+    breakdowns: HashMap<String, Vec<ServiceCost>>,
+    /// Breakdowns currently loading ("account_id|dimension")
+    loading_breakdowns: HashMap<String, bool>,
+    /// Configured cost allocation tag keys
+    tag_keys: Vec<String>,
+    /// Commitment summary cache (account_id -> CommitmentSummary)
+    commitments: HashMap<String, CommitmentSummary>,
+    /// Accounts currently loading commitments
+    loading_commitments: HashMap<String, bool>,
+    /// Cost anomaly cache (account_id -> anomalies)
+    anomalies: HashMap<String, Vec<CostAnomaly>>,
+    /// Accounts currently loading anomalies
+    loading_anomalies: HashMap<String, bool>,
+    /// Next scheduled background sync (None when auto-sync is off)
+    next_sync_at: Option<DateTime<Utc>>,
+    /// Whether a scheduled sync is running
+    syncing: bool,
+    /// Result of the last scheduled sync
+    last_sync: Option<SyncOutcome>,
+    /// Consecutive failed scheduled syncs, used for backoff
+    sync_failures: u32,
 }
 
+/// How often the scheduler checks whether a sync is due and updates the countdown
+const SCHEDULER_TICK: std::time::Duration = std::time::Duration::from_secs(15);
+
 impl DashboardView {
     pub fn new(_window: &mut Window, cx: &mut Context<Self>) -> Self {
         // Auto-trigger refresh on initialization
@@ -40,13 +77,150 @@ impl DashboardView {
         })
         .detach();
 
+        // Background auto-sync on the configured refresh interval
+        cx.spawn(async move |this, cx| loop {
+            smol::Timer::after(SCHEDULER_TICK).await;
+            let alive = cx
+                .update(|cx| this.update(cx, |this, cx| this.scheduler_tick(cx)).is_ok())
+                .unwrap_or(false);
+            if !alive {
+                break;
+            }
+        })
+        .detach();
+
         Self {
             summaries: Vec::new(),
             loading: true, // Initial state is loading
             error: None,
+            account_errors: Vec::new(),
+            status_message: None,
             expanded_account: None,
             cost_trends: HashMap::new(),
             loading_trends: HashMap::new(),
+            group_by: CostDimension::Service,
+            breakdowns: HashMap::new(),
+            loading_breakdowns: HashMap::new(),
+            tag_keys: crate::config::load_config()
+                .map(|c| c.cost_allocation_tags)
+                .unwrap_or_default(),
+            commitments: HashMap::new(),
+            loading_commitments: HashMap::new(),
+            anomalies: HashMap::new(),
+            loading_anomalies: HashMap::new(),
+            next_sync_at: None,
+            syncing: false,
+            last_sync: None,
+            sync_failures: 0,
+        }
+    }
+
+    /// Schedule the next sync or start it when due
+    fn scheduler_tick(&mut self, cx: &mut Context<Self>) {
+        let interval = crate::config::load_config()
+            .map(|c| c.refresh_interval_minutes)
+            .unwrap_or(0);
+        if interval == 0 {
+            self.next_sync_at = None;
+            cx.notify();
+            return;
+        }
+
+        match self.next_sync_at {
+            None => {
+                self.next_sync_at =
+                    Some(Utc::now() + scheduler::next_delay_with_jitter(interval, 0));
+            }
+            Some(at) if Utc::now() >= at && !self.syncing && !self.loading => {
+                self.run_scheduled_sync(interval, cx);
+            }
+            _ => {}
+        }
+        cx.notify();
+    }
+
+    /// Sync accounts with expired caches in the background, then reload the dashboard
+    fn run_scheduled_sync(&mut self, interval: u32, cx: &mut Context<Self>) {
+        self.syncing = true;
+
+        let (tx, rx) = std::sync::mpsc::channel::<Result<SyncOutcome, String>>();
+        std::thread::spawn(move || {
+            let result = scheduler::run_sync_cycle().map_err(|e| redact::error_message(&e));
+            let _ = tx.send(result);
+        });
+
+        cx.spawn(async move |this, cx| {
+            let result = smol::unblock(move || {
+                rx.recv_timeout(std::time::Duration::from_secs(600))
+                    .unwrap_or(Err("Sync timeout".to_string()))
+            })
+            .await;
+
+            cx.update(|cx| {
+                this.update(cx, |this, cx| {
+                    this.syncing = false;
+                    let outcome = result.unwrap_or_else(|e| SyncOutcome {
+                        finished_at: Some(Utc::now()),
+                        failed: vec![("all accounts".to_string(), e)],
+                        ..Default::default()
+                    });
+
+                    this.sync_failures = if outcome.is_failure() {
+                        this.sync_failures + 1
+                    } else {
+                        0
+                    };
+                    this.next_sync_at = Some(
+                        Utc::now()
+                            + scheduler::next_delay_with_jitter(interval, this.sync_failures),
+                    );
+                    if outcome.synced > 0 {
+                        this.refresh(cx);
+                    }
+                    this.last_sync = Some(outcome);
+                    cx.notify();
+                })
+                .ok();
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    /// "Next sync in ..." and the last scheduled sync result for the header
+    fn sync_status_text(&self) -> String {
+        let next = if self.syncing {
+            "Syncing...".to_string()
+        } else {
+            match self.next_sync_at {
+                Some(at) => format!(
+                    "Next sync in {}",
+                    scheduler::format_countdown(at - Utc::now())
+                ),
+                None => "Auto-sync off".to_string(),
+            }
+        };
+
+        match &self.last_sync {
+            Some(outcome) => {
+                let time = outcome
+                    .finished_at
+                    .map(|t| t.with_timezone(&chrono::Local).format("%H:%M").to_string())
+                    .unwrap_or_default();
+                let failure = outcome
+                    .failed
+                    .first()
+                    .map(|(name, error)| format!(" ({}: {})", name, error))
+                    .unwrap_or_default();
+                format!(
+                    "{} · Last sync {}: {}{}",
+                    next,
+                    time,
+                    outcome.summary(),
+                    failure
+                )
+            }
+            None => next,
         }
     }
 
@@ -57,126 +231,87 @@ impl DashboardView {
         cx.notify();
 
         // Use channel to fetch data in background thread
-        let (tx, rx) = std::sync::mpsc::channel::<Result<Vec<CostSummary>, String>>();
+        type RefreshResult = (Vec<CostSummary>, Vec<(String, String)>);
+        let (tx, rx) = std::sync::mpsc::channel::<Result<RefreshResult, String>>();
 
         std::thread::spawn(move || {
+            let config = crate::config::load_config().unwrap_or_default();
+
             match crate::db::get_all_accounts() {
                 Ok(accounts) => {
                     let mut summaries = Vec::new();
+                    let mut failures = Vec::new();
 
                     for account in accounts {
                         if !account.enabled {
                             continue;
                         }
 
-                        // Try to get from cache first
-                        match crate::db::get_cached_cost_summary_with_account(
-                            &account.id,
-                            &account.name,
-                            &account.provider,
-                        ) {
-                            Ok(Some(cached)) => {
-                                summaries.push(cached);
-                                continue;
+                        // Accounts with a local CUR directory are summarized from report data
+                        if crate::cur::ingest_configured(&account, &config).is_some() {
+                            match crate::db::get_cost_summary_from_cost_data(
+                                &account.id,
+                                &account.name,
+                                &account.provider,
+                            ) {
+                                Ok(Some(summary)) => {
+                                    summaries.push(summary);
+                                    continue;
+                                }
+                                Ok(None) => {}
+                                Err(e) => {
+                                    tracing::warn!("Failed to read CUR costs: {}", e);
+                                }
                             }
-                            Ok(None) => {}
-                            Err(_) => {}
                         }
 
-                        match account.provider {
-                            crate::cloud::CloudProvider::AWS => {
-                                let service = crate::cloud::aws::AwsCloudService::new(
-                                    account.id.clone(),
-                                    account.name.clone(),
-                                    account.access_key_id.clone(),
-                                    account.secret_access_key.clone(),
-                                    account.region.clone(),
-                                );
-
-                                use crate::cloud::CloudService;
-                                match service.get_cost_summary() {
-                                    Ok(summary) => {
-                                        // Save to cache
-                                        if let Err(e) = crate::db::save_cost_summary_cache(&summary)
-                                        {
-                                            tracing::warn!("Failed to save cost cache: {}", e);
-                                        }
-                                        summaries.push(summary);
-                                    }
-                                    Err(e) => {
-                                        tracing::error!(
-                                            "Failed to get cost for {}: {}",
-                                            account.name,
-                                            e
-                                        );
-                                    }
-                                }
+                        // Payer accounts with member billing are shown per member
+                        let member_billing = crate::cloud::member_billing_for(&account, &config);
+
+                        // Try to get from cache first
+                        let cached = match &member_billing {
+                            Some(billing) => {
+                                crate::cloud::cached_member_summaries(&account, billing)
                             }
-                            crate::cloud::CloudProvider::Aliyun => {
-                                let service = crate::cloud::aliyun::AliyunCloudService::new(
-                                    account.id.clone(),
-                                    account.name.clone(),
-                                    account.access_key_id.clone(),
-                                    account.secret_access_key.clone(),
-                                    account.region.clone(),
-                                );
-
-                                use crate::cloud::CloudService;
-                                match service.get_cost_summary() {
-                                    Ok(summary) => {
-                                        // Save to cache
-                                        if let Err(e) = crate::db::save_cost_summary_cache(&summary)
-                                        {
-                                            tracing::warn!("Failed to save cost cache: {}", e);
-                                        }
-                                        summaries.push(summary);
-                                    }
-                                    Err(e) => {
-                                        tracing::error!(
-                                            "Failed to get Aliyun {} cost: {}",
-                                            account.name,
-                                            e
-                                        );
+                            None => crate::db::get_cached_cost_summary_with_account(
+                                &account.id,
+                                &account.name,
+                                &account.provider,
+                            )
+                            .ok()
+                            .flatten()
+                            .map(|summary| vec![summary]),
+                        };
+                        if let Some(cached) = cached {
+                            summaries.extend(cached);
+                            continue;
+                        }
+
+                        match crate::cloud::fetch_cost_summaries(&account, &config) {
+                            Ok(fetched) => {
+                                for summary in fetched {
+                                    // Save to cache
+                                    if let Err(e) = crate::db::save_cost_summary_cache(&summary) {
+                                        tracing::warn!("Failed to save cost cache: {}", e);
                                     }
+                                    summaries.push(summary);
                                 }
                             }
-                            crate::cloud::CloudProvider::DeepSeek => {
-                                let service = crate::cloud::deepseek::DeepSeekService::new(
-                                    account.id.clone(),
-                                    account.name.clone(),
-                                    account.access_key_id.clone(),
-                                    account.secret_access_key.clone(),
-                                    account.region.clone(),
-                                );
-
-                                use crate::cloud::CloudService;
-                                match service.get_cost_summary() {
-                                    Ok(summary) => {
-                                        // Save to cache
-                                        if let Err(e) = crate::db::save_cost_summary_cache(&summary)
-                                        {
-                                            tracing::warn!("Failed to save cost cache: {}", e);
-                                        }
-                                        summaries.push(summary);
-                                    }
-                                    Err(e) => {
-                                        tracing::error!(
-                                            "Failed to get DeepSeek {} balance: {}",
-                                            account.name,
-                                            e
-                                        );
-                                    }
-                                }
+                            Err(e) => {
+                                tracing::error!("Failed to get cost for {}: {:#}", account.name, e);
+                                failures
+                                    .push((account.name.clone(), crate::cloud::describe_error(&e)));
                             }
-
-                            _ => {}
                         }
                     }
-                    let _ = tx.send(Ok(summaries));
+                    let _ = tx.send(Ok((summaries, failures)));
                 }
                 Err(e) => {
                     tracing::error!("Failed to get account list: {}", e);
-                    let _ = tx.send(Err(format!("Failed to load data: {}", e)));
+                    let _ = tx.send(Err(format!(
+                        "Failed to load data: {}",
+                        redact::error_message(&e)
+                    )));
                 }
             }
         });
@@ -192,8 +327,9 @@ impl DashboardView {
             cx.update(|cx| {
                 this.update(cx, |this, cx| {
                     match result {
-                        Ok(summaries) => {
+                        Ok((summaries, failures)) => {
                             this.summaries = summaries;
+                            this.account_errors = failures;
                             this.loading = false;
                             this.error = None;
                         }
@@ -219,15 +355,57 @@ impl DashboardView {
             .items_center()
             .child(
                 div()
-                    .text_2xl()
-                    .font_weight(FontWeight::BOLD)
-                    .text_color(cx.theme().foreground)
-                    .child("Dashboard"),
+                    .v_flex()
+                    .child(
+                        div()
+                            .text_2xl()
+                            .font_weight(FontWeight::BOLD)
+                            .text_color(cx.theme().foreground)
+                            .child("Dashboard"),
+                    )
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(cx.theme().muted_foreground)
+                            .child(self.sync_status_text()),
+                    )
+                    .when_some(self.status_message.clone(), |el, message| {
+                        el.child(
+                            div()
+                                .text_sm()
+                                .text_color(cx.theme().muted_foreground)
+                                .child(message),
+                        )
+                    }),
             )
             .child(
                 div()
                     .h_flex()
                     .gap_2()
+                    .child(
+                        Button::new("monthly-report")
+                            .label("Report")
+                            .ghost()
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                this.generate_report(cx);
+                            })),
+                    )
+                    .child(
+                        Button::new("export-costs")
+                            .label("Export")
+                            .ghost()
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                this.export_costs(cx);
+                            })),
+                    )
+                    .child(
+                        Button::new("export-focus")
+                            .label("Export FOCUS")
+                            .ghost()
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                this.export_focus(cx);
+                            })),
+                    )
                     .child(
                         Button::new("refresh")
                             .label("Refresh")
@@ -246,18 +424,248 @@ impl DashboardView {
             )
     }
 
+    /// Default directory offered when saving exports
+    fn export_directory() -> std::path::PathBuf {
+        directories::UserDirs::new()
+            .and_then(|dirs| dirs.download_dir().map(|d| d.to_path_buf()))
+            .or_else(|| crate::config::get_app_data_dir().ok())
+            .unwrap_or_else(std::env::temp_dir)
+    }
+
+    /// Render last month's cost report as HTML (or PDF when the file name ends in .pdf)
+    fn generate_report(&mut self, cx: &mut Context<Self>) {
+        use crate::report::ReportPeriod;
+
+        let period = ReportPeriod::last_month();
+        let file_name = format!("cloudbridge-report-{}.html", &period.start[..7]);
+        let path_prompt = cx.prompt_for_new_path(&Self::export_directory(), Some(&file_name));
+
+        cx.spawn(async move |this, cx| {
+            let Ok(Ok(Some(path))) = path_prompt.await else {
+                return;
+            };
+
+            cx.update(|cx| {
+                this.update(cx, |this, cx| {
+                    this.status_message = Some(format!("Generating {} report...", period.title));
+                    cx.notify();
+                })
+                .ok();
+            })
+            .ok();
+
+            let (tx, rx) = std::sync::mpsc::channel::<Result<std::path::PathBuf, String>>();
+            std::thread::spawn(move || {
+                let result = crate::report::build_report(&period, &[])
+                    .and_then(|report| crate::report::write_report(&report, &path))
+                    .map(|_| path)
+                    .map_err(|e| redact::error_message(&e));
+                let _ = tx.send(result);
+            });
+
+            let result = smol::unblock(move || {
+                rx.recv_timeout(std::time::Duration::from_secs(300))
+                    .unwrap_or(Err("Report timeout".to_string()))
+            })
+            .await;
+
+            cx.update(|cx| {
+                this.update(cx, |this, cx| {
+                    this.status_message = Some(match result {
+                        Ok(path) => format!("Saved report to {}", path.display()),
+                        Err(e) => format!("Report failed: {}", e),
+                    });
+                    cx.notify();
+                })
+                .ok();
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    /// Export this month's summary, service breakdown and daily trend of all enabled
+    /// accounts. The format follows the chosen file extension (XLSX by default).
+    fn export_costs(&mut self, cx: &mut Context<Self>) {
+        use crate::export::{ExportDataset, ExportFormat, ExportRequest};
+        use chrono::{Datelike, Duration, Utc};
+
+        let file_name = format!("cloudbridge-costs-{}.xlsx", Utc::now().format("%Y%m%d"));
+        let path_prompt = cx.prompt_for_new_path(&Self::export_directory(), Some(&file_name));
+
+        cx.spawn(async move |this, cx| {
+            let Ok(Ok(Some(path))) = path_prompt.await else {
+                return;
+            };
+
+            cx.update(|cx| {
+                this.update(cx, |this, cx| {
+                    this.status_message = Some("Exporting costs...".to_string());
+                    cx.notify();
+                })
+                .ok();
+            })
+            .ok();
+
+            let (tx, rx) = std::sync::mpsc::channel::<Result<Vec<std::path::PathBuf>, String>>();
+            std::thread::spawn(move || {
+                let now = Utc::now();
+                let request = ExportRequest {
+                    account_ids: Vec::new(),
+                    start_date: format!("{}-{:02}-01", now.year(), now.month()),
+                    end_date: (now + Duration::days(1)).format("%Y-%m-%d").to_string(),
+                    datasets: ExportDataset::ALL.to_vec(),
+                    format: ExportFormat::from_path(&path).unwrap_or(ExportFormat::Xlsx),
+                    output: path,
+                };
+                let result =
+                    crate::export::export_costs(&request).map_err(|e| redact::error_message(&e));
+                let _ = tx.send(result);
+            });
+
+            let result = smol::unblock(move || {
+                rx.recv_timeout(std::time::Duration::from_secs(300))
+                    .unwrap_or(Err("Export timeout".to_string()))
+            })
+            .await;
+
+            cx.update(|cx| {
+                this.update(cx, |this, cx| {
+                    this.status_message = Some(match result {
+                        Ok(paths) => format!(
+                            "Exported {}",
+                            paths
+                                .iter()
+                                .map(|p| p.display().to_string())
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        ),
+                        Err(e) => format!("Export failed: {}", e),
+                    });
+                    cx.notify();
+                })
+                .ok();
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    /// Export current and last month costs of all accounts as a FOCUS dataset
+    fn export_focus(&mut self, cx: &mut Context<Self>) {
+        use chrono::{Datelike, Duration, Utc};
+
+        let directory = Self::export_directory();
+        let file_name = format!("cloudbridge-focus-{}.parquet", Utc::now().format("%Y%m%d"));
+        let path_prompt = cx.prompt_for_new_path(&directory, Some(&file_name));
+
+        cx.spawn(async move |this, cx| {
+            let Ok(Ok(Some(path))) = path_prompt.await else {
+                return;
+            };
+
+            cx.update(|cx| {
+                this.update(cx, |this, cx| {
+                    this.status_message = Some("Exporting FOCUS dataset...".to_string());
+                    cx.notify();
+                })
+                .ok();
+            })
+            .ok();
+
+            let (tx, rx) =
+                std::sync::mpsc::channel::<Result<(usize, std::path::PathBuf), String>>();
+            std::thread::spawn(move || {
+                let now = Utc::now();
+                let last_month = now.with_day(1).unwrap() - Duration::days(1);
+                let start_date = format!("{}-{:02}-01", last_month.year(), last_month.month());
+                let end_date = (now + Duration::days(1)).format("%Y-%m-%d").to_string();
+
+                let result = crate::export::export_focus(&path, &start_date, &end_date)
+                    .map(|rows| (rows, path))
+                    .map_err(|e| redact::error_message(&e));
+                let _ = tx.send(result);
+            });
+
+            let result = smol::unblock(move || {
+                rx.recv_timeout(std::time::Duration::from_secs(300))
+                    .unwrap_or(Err("FOCUS export timeout".to_string()))
+            })
+            .await;
+
+            cx.update(|cx| {
+                this.update(cx, |this, cx| {
+                    this.status_message = Some(match result {
+                        Ok((rows, path)) => {
+                            format!("Exported {} FOCUS rows to {}", rows, path.display())
+                        }
+                        Err(e) => format!("FOCUS export failed: {}", e),
+                    });
+                    cx.notify();
+                })
+                .ok();
+            })
+            .ok();
+        })
+        .detach();
+    }
+
     /// Force refresh (clear cache and refetch)
     fn force_refresh(&mut self, cx: &mut Context<Self>) {
         // Clear all cache
         if let Err(e) = crate::db::clear_all_cache() {
             tracing::warn!("Failed to clear cache: {}", e);
         }
-        // Clear trend cache in memory
+        // Clear trend and breakdown cache in memory
         self.cost_trends.clear();
+        self.breakdowns.clear();
+        self.commitments.clear();
+        self.anomalies.clear();
         // Then refresh
         self.refresh(cx);
     }
 
+    /// Accounts that failed to load, with the suggested fix and a link to the required
+    /// permissions where that is the cause
+    fn render_account_errors(&self, cx: &Context<Self>) -> impl IntoElement {
+        div()
+            .w_full()
+            .v_flex()
+            .gap_2()
+            .children(
+                self.account_errors
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (name, message))| {
+                        div()
+                            .w_full()
+                            .h_flex()
+                            .gap_2()
+                            .p_3()
+                            .rounded_md()
+                            .bg(cx.theme().danger.opacity(0.1))
+                            .child(
+                                div()
+                                    .flex_1()
+                                    .text_sm()
+                                    .text_color(cx.theme().danger)
+                                    .child(format!("{}: {}", name, message)),
+                            )
+                            .when(message.contains(POLICIES_DOC_PATH), |el| {
+                                el.child(
+                                    Button::new(SharedString::from(format!("policies-doc-{}", i)))
+                                        .label("Required permissions")
+                                        .ghost()
+                                        .small()
+                                        .on_click(cx.listener(|_, _, _, cx| {
+                                            cx.open_url(POLICIES_DOC_URL);
+                                        })),
+                                )
+                            })
+                    }),
+            )
+    }
+
     fn render_summary_cards(&self, cx: &Context<Self>) -> impl IntoElement {
         if self.summaries.is_empty() {
             return div().w_full().p_8().items_center().justify_center().child(
@@ -425,7 +833,14 @@ impl DashboardView {
         };
 
         let account_id = summary.account_id.clone();
-        let details = summary.current_month_details.clone();
+
+        // Fall back to service grouping when the provider can't group by the selected dimension
+        let dimensions = CostDimension::available_for(summary.provider, &self.tag_keys);
+        let group_by = if dimensions.contains(&self.group_by) {
+            self.group_by.clone()
+        } else {
+            CostDimension::Service
+        };
 
         // Pre-render trend chart (render outside closure to avoid borrow issues)
         let trend_chart = if is_expanded {
@@ -434,6 +849,20 @@ impl DashboardView {
             None
         };
 
+        // Savings Plans / Reserved Instances are AWS-only
+        let commitments_panel = if is_expanded && summary.provider == CloudProvider::AWS {
+            Some(self.render_commitments(&summary.account_id, cx))
+        } else {
+            None
+        };
+
+        // Cost Anomaly Detection is AWS-only
+        let anomalies_panel = if is_expanded && summary.provider == CloudProvider::AWS {
+            Some(self.render_anomalies(&summary.account_id, cx))
+        } else {
+            None
+        };
+
         div()
             .id(ElementId::Name(format!("account-card-{}", index).into()))
             // Expanded card takes full width, collapsed card has fixed width
@@ -543,24 +972,31 @@ impl DashboardView {
             // Show service details when expanded
             .when(is_expanded, |el| {
                 el.child(div().w_full().h_px().bg(cx.theme().border).my_2())
-                    // Service breakdown section: pie chart with legend
+                    // Cost breakdown section: "group by" selector and pie chart with legend
                     .child(
                         div()
-                            .text_sm()
-                            .font_weight(FontWeight::SEMIBOLD)
-                            .text_color(cx.theme().foreground)
+                            .h_flex()
+                            .justify_between()
+                            .items_center()
                             .mb_2()
-                            .child("Service Cost Breakdown (This Month)"),
+                            .child(
+                                div()
+                                    .text_sm()
+                                    .font_weight(FontWeight::SEMIBOLD)
+                                    .text_color(cx.theme().foreground)
+                                    .child(format!(
+                                        "Cost Breakdown by {} (This Month)",
+                                        group_by.label()
+                                    )),
+                            )
+                            .when(dimensions.len() > 1, |el| {
+                                el.child(self.render_group_by_selector(&dimensions, &group_by, cx))
+                            }),
                     )
                     .child(
                         div()
                             .w_full()
-                            // Pie chart with integrated legend (shows values + percentages)
-                            .child(
-                                ServicePieChart::donut(details.clone(), 80.0, 50.0)
-                                    .with_legend()
-                                    .render(cx),
-                            ),
+                            .child(self.render_breakdown(summary, &group_by, cx)),
                     )
                     // Cost trend chart section
                     .child(div().w_full().h_px().bg(cx.theme().border).my_3())
@@ -573,31 +1009,778 @@ impl DashboardView {
                             .child("Cost Trend"),
                     )
                     .children(trend_chart)
+                    // Commitments section (AWS only)
+                    .when_some(commitments_panel, |el, panel| {
+                        el.child(div().w_full().h_px().bg(cx.theme().border).my_3())
+                            .child(
+                                div()
+                                    .text_sm()
+                                    .font_weight(FontWeight::SEMIBOLD)
+                                    .text_color(cx.theme().foreground)
+                                    .mb_2()
+                                    .child("Commitments (Last 30 Days)"),
+                            )
+                            .child(panel)
+                    })
+                    // Cost anomalies section (AWS only)
+                    .when_some(anomalies_panel, |el, panel| {
+                        el.child(div().w_full().h_px().bg(cx.theme().border).my_3())
+                            .child(
+                                div()
+                                    .text_sm()
+                                    .font_weight(FontWeight::SEMIBOLD)
+                                    .text_color(cx.theme().foreground)
+                                    .mb_2()
+                                    .child("Cost Anomalies (Last 90 Days)"),
+                            )
+                            .child(panel)
+                    })
             })
     }
 
-    /// Render cost trend chart
-    fn render_trend_chart(&self, account_id: &str, cx: &Context<Self>) -> AnyElement {
-        // Check if loading
-        if self
-            .loading_trends
-            .get(account_id)
-            .copied()
-            .unwrap_or(false)
-        {
+    /// Render Savings Plans and Reserved Instances utilization and coverage
+    fn render_commitments(&self, account_id: &str, cx: &Context<Self>) -> AnyElement {
+        let Some(summary) = self.commitments.get(account_id) else {
+            let message = if self
+                .loading_commitments
+                .get(account_id)
+                .copied()
+                .unwrap_or(false)
+            {
+                "Loading commitment data..."
+            } else {
+                "Commitment data unavailable"
+            };
+
             return div()
                 .w_full()
-                .h(px(120.0))
+                .h(px(60.0))
                 .flex()
                 .items_center()
                 .justify_center()
                 .text_color(cx.theme().muted_foreground)
-                .child("Loading trend data...")
+                .child(message)
                 .into_any_element();
-        }
+        };
 
-        // Check for cached data
-        if let Some(trend) = self.cost_trends.get(account_id) {
+        div()
+            .w_full()
+            .h_flex()
+            .gap_4()
+            .child(self.render_commitment_column(
+                "Savings Plans",
+                summary.savings_plans.as_ref(),
+                &summary.currency,
+                cx,
+            ))
+            .child(self.render_commitment_column(
+                "Reserved Instances",
+                summary.reservations.as_ref(),
+                &summary.currency,
+                cx,
+            ))
+            .into_any_element()
+    }
+
+    fn render_commitment_column(
+        &self,
+        title: &str,
+        commitment: Option<&CommitmentUtilization>,
+        currency: &str,
+        cx: &Context<Self>,
+    ) -> impl IntoElement {
+        let symbol = match currency {
+            "CNY" => "¥",
+            "USD" => "$",
+            other => other,
+        };
+
+        let column = div()
+            .flex_1()
+            .p_3()
+            .rounded_md()
+            .bg(cx.theme().secondary)
+            .v_flex()
+            .gap_1()
+            .child(
+                div()
+                    .text_sm()
+                    .font_weight(FontWeight::SEMIBOLD)
+                    .text_color(cx.theme().foreground)
+                    .child(title.to_string()),
+            );
+
+        let Some(c) = commitment else {
+            return column.child(
+                div()
+                    .text_sm()
+                    .text_color(cx.theme().muted_foreground)
+                    .child(format!("No active {}", title)),
+            );
+        };
+
+        let rows = [
+            ("Utilization", format!("{:.1}%", c.utilization_percentage)),
+            ("Coverage", format!("{:.1}%", c.coverage_percentage)),
+            (
+                "On-demand not covered",
+                format!("{}{:.2}", symbol, c.on_demand_not_covered),
+            ),
+            (
+                "Unused commitment",
+                format!("{}{:.2}", symbol, c.unused_commitment),
+            ),
+            ("Net savings", format!("{}{:.2}", symbol, c.net_savings)),
+        ];
+
+        column.children(rows.into_iter().map(|(label, value)| {
+            div()
+                .h_flex()
+                .justify_between()
+                .text_sm()
+                .child(div().text_color(cx.theme().muted_foreground).child(label))
+                .child(div().text_color(cx.theme().foreground).child(value))
+        }))
+    }
+
+    /// Load Savings Plans / Reserved Instances commitments (lazy loading, AWS only)
+    fn load_commitments(&mut self, account_id: &str, cx: &mut Context<Self>) {
+        let account = match crate::db::get_all_accounts() {
+            Ok(accounts) => accounts.into_iter().find(|a| a.id == account_id),
+            Err(_) => None,
+        };
+        let Some(account) = account else {
+            return;
+        };
+        if account.provider != CloudProvider::AWS {
+            return;
+        }
+
+        self.loading_commitments
+            .insert(account_id.to_string(), true);
+
+        let (tx, rx) = std::sync::mpsc::channel::<Result<CommitmentSummary, String>>();
+
+        std::thread::spawn(move || {
+            use chrono::{Duration, Utc};
+
+            // Try to get from cache first
+            if let Ok(Some(cached)) = crate::db::get_cached_commitment_summary(&account.id) {
+                let _ = tx.send(Ok(cached));
+                return;
+            }
+
+            let now = Utc::now();
+            let start_date = (now - Duration::days(30)).format("%Y-%m-%d").to_string();
+            let end_date = now.format("%Y-%m-%d").to_string();
+
+            // Credential references are resolved for this call only
+            let account = match crate::secret_store::resolve_credentials(&account) {
+                Ok(account) => account,
+                Err(e) => {
+                    let _ = tx.send(Err(format!("{:#}", e)));
+                    return;
+                }
+            };
+
+            let service = crate::cloud::aws::AwsCloudService::new(
+                account.id.clone(),
+                account.name.clone(),
+                account.access_key_id.clone(),
+                account.secret_access_key.clone(),
+                account.region.clone(),
+            );
+
+            match service.get_commitment_summary(&start_date, &end_date) {
+                Ok(summary) => {
+                    if let Err(e) = crate::db::save_commitment_cache(&summary) {
+                        tracing::warn!("Failed to save commitment cache: {}", e);
+                    }
+                    let _ = tx.send(Ok(summary));
+                }
+                Err(e) => {
+                    tracing::error!("Failed to get commitments for {}: {}", account.name, e);
+                    let _ = tx.send(Err(format!(
+                        "Failed to get commitment data: {}",
+                        redact::error_message(&e)
+                    )));
+                }
+            }
+        });
+
+        let account_id_for_update = account_id.to_string();
+        cx.spawn(async move |this, cx| {
+            let result = smol::unblock(move || {
+                rx.recv_timeout(std::time::Duration::from_secs(60))
+                    .unwrap_or(Err("Commitment data retrieval timeout".to_string()))
+            })
+            .await;
+
+            cx.update(|cx| {
+                this.update(cx, |this, cx| {
+                    this.loading_commitments
+                        .insert(account_id_for_update.clone(), false);
+
+                    if let Ok(summary) = result {
+                        this.commitments.insert(account_id_for_update, summary);
+                    }
+                    cx.notify();
+                })
+                .ok();
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    /// Render cost anomalies with root causes, impact and feedback actions
+    fn render_anomalies(&self, account_id: &str, cx: &Context<Self>) -> AnyElement {
+        let Some(anomalies) = self.anomalies.get(account_id) else {
+            let message = if self
+                .loading_anomalies
+                .get(account_id)
+                .copied()
+                .unwrap_or(false)
+            {
+                "Loading anomalies..."
+            } else {
+                "Anomaly data unavailable"
+            };
+
+            return div()
+                .w_full()
+                .h(px(60.0))
+                .flex()
+                .items_center()
+                .justify_center()
+                .text_color(cx.theme().muted_foreground)
+                .child(message)
+                .into_any_element();
+        };
+
+        if anomalies.is_empty() {
+            return div()
+                .w_full()
+                .text_sm()
+                .text_color(cx.theme().muted_foreground)
+                .child("No anomalies detected")
+                .into_any_element();
+        }
+
+        div()
+            .w_full()
+            .v_flex()
+            .gap_2()
+            .children(
+                anomalies
+                    .iter()
+                    .map(|anomaly| self.render_anomaly_row(account_id, anomaly, cx)),
+            )
+            .into_any_element()
+    }
+
+    fn render_anomaly_row(
+        &self,
+        account_id: &str,
+        anomaly: &CostAnomaly,
+        cx: &Context<Self>,
+    ) -> impl IntoElement {
+        let symbol = match anomaly.currency.as_str() {
+            "CNY" => "¥",
+            "USD" => "$",
+            other => other,
+        };
+        let period = match &anomaly.end_date {
+            Some(end) => format!("{} → {}", anomaly.start_date, end),
+            None => format!("{} → ongoing", anomaly.start_date),
+        };
+        let root_cause = anomaly
+            .root_causes
+            .first()
+            .map(|c| c.describe())
+            .unwrap_or_else(|| "Unknown root cause".to_string());
+
+        let actions = match anomaly.feedback {
+            Some(feedback) => div()
+                .text_xs()
+                .px_2()
+                .py_1()
+                .rounded_md()
+                .bg(cx.theme().accent.opacity(0.1))
+                .text_color(cx.theme().accent)
+                .child(feedback.label())
+                .into_any_element(),
+            None => div()
+                .h_flex()
+                .gap_1()
+                .children(
+                    [
+                        AnomalyFeedback::Yes,
+                        AnomalyFeedback::PlannedActivity,
+                        AnomalyFeedback::No,
+                    ]
+                    .into_iter()
+                    .map(|feedback| {
+                        let account_id = account_id.to_string();
+                        let anomaly_id = anomaly.anomaly_id.clone();
+                        Button::new(SharedString::from(format!(
+                            "anomaly-{}-{}",
+                            anomaly.anomaly_id,
+                            feedback.key()
+                        )))
+                        .label(feedback.label())
+                        .ghost()
+                        .small()
+                        .on_click(cx.listener(move |this, _, _, cx| {
+                            cx.stop_propagation();
+                            this.acknowledge_anomaly(&account_id, &anomaly_id, feedback, cx);
+                        }))
+                    }),
+                )
+                .into_any_element(),
+        };
+
+        div()
+            .w_full()
+            .p_3()
+            .rounded_md()
+            .bg(cx.theme().secondary)
+            .h_flex()
+            .justify_between()
+            .items_center()
+            .gap_4()
+            .child(
+                div()
+                    .v_flex()
+                    .gap_1()
+                    .child(
+                        div()
+                            .h_flex()
+                            .gap_2()
+                            .child(
+                                div()
+                                    .text_sm()
+                                    .font_weight(FontWeight::SEMIBOLD)
+                                    .text_color(cx.theme().danger)
+                                    .child(format!("+{}{:.2}", symbol, anomaly.total_impact)),
+                            )
+                            .child(
+                                div()
+                                    .text_sm()
+                                    .text_color(cx.theme().foreground)
+                                    .child(root_cause),
+                            ),
+                    )
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(cx.theme().muted_foreground)
+                            .child(format!(
+                            "{} · actual {}{:.2} vs expected {}{:.2} · max daily impact {}{:.2}{}",
+                            period,
+                            symbol,
+                            anomaly.actual_spend,
+                            symbol,
+                            anomaly.expected_spend,
+                            symbol,
+                            anomaly.max_impact,
+                            anomaly
+                                .monitor_name
+                                .as_ref()
+                                .map(|name| format!(" · monitor: {}", name))
+                                .unwrap_or_default()
+                        )),
+                    ),
+            )
+            .child(actions)
+    }
+
+    /// Load cost anomalies for the last 90 days (lazy loading, AWS only)
+    fn load_anomalies(&mut self, account_id: &str, cx: &mut Context<Self>) {
+        let account = match crate::db::get_all_accounts() {
+            Ok(accounts) => accounts.into_iter().find(|a| a.id == account_id),
+            Err(_) => None,
+        };
+        let Some(account) = account else {
+            return;
+        };
+        if account.provider != CloudProvider::AWS {
+            return;
+        }
+
+        self.loading_anomalies.insert(account_id.to_string(), true);
+
+        let (tx, rx) = std::sync::mpsc::channel::<Result<Vec<CostAnomaly>, String>>();
+
+        std::thread::spawn(move || {
+            use chrono::{Duration, Utc};
+
+            // Try to get from cache first
+            if let Ok(Some(cached)) = crate::db::get_cached_anomalies(&account.id) {
+                let _ = tx.send(Ok(cached));
+                return;
+            }
+
+            let now = Utc::now();
+            let start_date = (now - Duration::days(90)).format("%Y-%m-%d").to_string();
+            let end_date = now.format("%Y-%m-%d").to_string();
+
+            // Credential references are resolved for this call only
+            let account = match crate::secret_store::resolve_credentials(&account) {
+                Ok(account) => account,
+                Err(e) => {
+                    let _ = tx.send(Err(format!("{:#}", e)));
+                    return;
+                }
+            };
+
+            let service = crate::cloud::aws::AwsCloudService::new(
+                account.id.clone(),
+                account.name.clone(),
+                account.access_key_id.clone(),
+                account.secret_access_key.clone(),
+                account.region.clone(),
+            );
+
+            match service.get_anomalies(&start_date, &end_date) {
+                Ok(anomalies) => {
+                    if let Err(e) = crate::db::save_anomaly_cache(&account.id, &anomalies) {
+                        tracing::warn!("Failed to save anomaly cache: {}", e);
+                    }
+                    let _ = tx.send(Ok(anomalies));
+                }
+                Err(e) => {
+                    tracing::error!("Failed to get anomalies for {}: {}", account.name, e);
+                    let _ = tx.send(Err(format!(
+                        "Failed to get anomalies: {}",
+                        redact::error_message(&e)
+                    )));
+                }
+            }
+        });
+
+        let account_id_for_update = account_id.to_string();
+        cx.spawn(async move |this, cx| {
+            let result = smol::unblock(move || {
+                rx.recv_timeout(std::time::Duration::from_secs(60))
+                    .unwrap_or(Err("Anomaly data retrieval timeout".to_string()))
+            })
+            .await;
+
+            cx.update(|cx| {
+                this.update(cx, |this, cx| {
+                    this.loading_anomalies
+                        .insert(account_id_for_update.clone(), false);
+
+                    if let Ok(anomalies) = result {
+                        this.anomalies.insert(account_id_for_update, anomalies);
+                    }
+                    cx.notify();
+                })
+                .ok();
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    /// Send anomaly feedback to AWS (ProvideAnomalyFeedback) and record it locally
+    fn acknowledge_anomaly(
+        &mut self,
+        account_id: &str,
+        anomaly_id: &str,
+        feedback: AnomalyFeedback,
+        cx: &mut Context<Self>,
+    ) {
+        let account = match crate::db::get_all_accounts() {
+            Ok(accounts) => accounts.into_iter().find(|a| a.id == account_id),
+            Err(_) => None,
+        };
+        let Some(account) = account else {
+            return;
+        };
+
+        let (tx, rx) = std::sync::mpsc::channel::<Result<(), String>>();
+        let anomaly_id_for_thread = anomaly_id.to_string();
+
+        std::thread::spawn(move || {
+            // Credential references are resolved for this call only
+            let account = match crate::secret_store::resolve_credentials(&account) {
+                Ok(account) => account,
+                Err(e) => {
+                    let _ = tx.send(Err(format!("{:#}", e)));
+                    return;
+                }
+            };
+
+            let service = crate::cloud::aws::AwsCloudService::new(
+                account.id.clone(),
+                account.name.clone(),
+                account.access_key_id.clone(),
+                account.secret_access_key.clone(),
+                account.region.clone(),
+            );
+
+            let result = service
+                .provide_anomaly_feedback(&anomaly_id_for_thread, feedback)
+                .and_then(|_| {
+                    crate::db::update_anomaly_feedback(
+                        &account.id,
+                        &anomaly_id_for_thread,
+                        feedback,
+                    )
+                })
+                .map_err(|e| redact::error_message(&e));
+            let _ = tx.send(result);
+        });
+
+        let account_id = account_id.to_string();
+        let anomaly_id = anomaly_id.to_string();
+        cx.spawn(async move |this, cx| {
+            let result = smol::unblock(move || {
+                rx.recv_timeout(std::time::Duration::from_secs(30))
+                    .unwrap_or(Err("Anomaly feedback timeout".to_string()))
+            })
+            .await;
+
+            cx.update(|cx| {
+                this.update(cx, |this, cx| {
+                    match result {
+                        Ok(()) => {
+                            if let Some(anomalies) = this.anomalies.get_mut(&account_id) {
+                                for anomaly in
+                                    anomalies.iter_mut().filter(|a| a.anomaly_id == anomaly_id)
+                                {
+                                    anomaly.feedback = Some(feedback);
+                                }
+                            }
+                        }
+                        Err(e) => {
+                            this.status_message =
+                                Some(format!("Failed to send anomaly feedback: {}", e));
+                        }
+                    }
+                    cx.notify();
+                })
+                .ok();
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    /// Render the "group by" dimension selector
+    fn render_group_by_selector(
+        &self,
+        dimensions: &[CostDimension],
+        selected: &CostDimension,
+        cx: &Context<Self>,
+    ) -> impl IntoElement {
+        div()
+            .h_flex()
+            .gap_1()
+            .children(dimensions.iter().map(|dimension| {
+                let button =
+                    Button::new(SharedString::from(format!("group-by-{}", dimension.key())))
+                        .label(dimension.label())
+                        .small();
+                let button = if dimension == selected {
+                    button.primary()
+                } else {
+                    button.ghost()
+                };
+
+                let dimension = dimension.clone();
+                button.on_click(cx.listener(move |this, _, _, cx| {
+                    // Don't collapse the account card
+                    cx.stop_propagation();
+                    this.set_group_by(dimension.clone(), cx);
+                }))
+            }))
+    }
+
+    /// Render the cost breakdown pie chart for the selected dimension
+    fn render_breakdown(
+        &self,
+        summary: &CostSummary,
+        dimension: &CostDimension,
+        cx: &Context<Self>,
+    ) -> AnyElement {
+        // Service breakdown is already part of the cost summary
+        if *dimension == CostDimension::Service {
+            return ServicePieChart::donut(summary.current_month_details.clone(), 80.0, 50.0)
+                .with_legend()
+                .render(cx);
+        }
+
+        let key = Self::breakdown_key(&summary.account_id, dimension);
+        if let Some(groups) = self.breakdowns.get(&key) {
+            return ServicePieChart::donut(groups.clone(), 80.0, 50.0)
+                .with_legend()
+                .render(cx);
+        }
+
+        let message = if self.loading_breakdowns.get(&key).copied().unwrap_or(false) {
+            "Loading breakdown data..."
+        } else {
+            "Breakdown data unavailable"
+        };
+
+        div()
+            .w_full()
+            .h(px(80.0))
+            .flex()
+            .items_center()
+            .justify_center()
+            .text_color(cx.theme().muted_foreground)
+            .child(message)
+            .into_any_element()
+    }
+
+    fn breakdown_key(account_id: &str, dimension: &CostDimension) -> String {
+        format!("{}|{}", account_id, dimension.key())
+    }
+
+    /// Change the "group by" dimension and load the breakdown for the expanded account
+    fn set_group_by(&mut self, dimension: CostDimension, cx: &mut Context<Self>) {
+        self.group_by = dimension;
+        if let Some(account_id) = self.expanded_account.clone() {
+            self.ensure_breakdown_loaded(&account_id, cx);
+        }
+        cx.notify();
+    }
+
+    /// Start loading the breakdown for the selected dimension unless it is cached or loading
+    fn ensure_breakdown_loaded(&mut self, account_id: &str, cx: &mut Context<Self>) {
+        if self.group_by == CostDimension::Service {
+            return;
+        }
+        let key = Self::breakdown_key(account_id, &self.group_by);
+        if self.breakdowns.contains_key(&key)
+            || self.loading_breakdowns.get(&key).copied().unwrap_or(false)
+        {
+            return;
+        }
+        self.load_cost_breakdown(account_id, cx);
+    }
+
+    /// Load current month cost breakdown for the selected dimension (lazy loading)
+    fn load_cost_breakdown(&mut self, account_id: &str, cx: &mut Context<Self>) {
+        let dimension = self.group_by.clone();
+        let key = Self::breakdown_key(account_id, &dimension);
+
+        let account = match crate::db::get_all_accounts() {
+            Ok(accounts) => crate::cloud::find_account(accounts, account_id),
+            Err(_) => None,
+        };
+        let Some(account) = account else {
+            return;
+        };
+        if !CostDimension::available_for(account.provider, &self.tag_keys).contains(&dimension) {
+            return;
+        }
+
+        self.loading_breakdowns.insert(key.clone(), true);
+
+        let (tx, rx) = std::sync::mpsc::channel::<Result<Vec<ServiceCost>, String>>();
+
+        std::thread::spawn(move || {
+            use chrono::{Datelike, Duration, Utc};
+
+            let now = Utc::now();
+            let start_date = format!("{}-{:02}-01", now.year(), now.month());
+            let tomorrow = now + Duration::days(1);
+            let end_date = format!(
+                "{}-{:02}-{:02}",
+                tomorrow.year(),
+                tomorrow.month(),
+                tomorrow.day()
+            );
+
+            // Try to get from cache first
+            if let Ok(Some(cached)) = crate::db::get_cached_cost_breakdown(
+                &account.id,
+                &dimension,
+                &start_date,
+                &end_date,
+            ) {
+                let groups = crate::cloud::group_costs_by_dimension(&cached, &dimension);
+                let _ = tx.send(Ok(groups));
+                return;
+            }
+
+            let result = crate::cloud::service_for_account(&account)
+                .and_then(|service| service.get_cost_breakdown(&start_date, &end_date, &dimension));
+
+            match result {
+                Ok(costs) => {
+                    if let Err(e) = crate::db::save_cost_breakdown_cache(
+                        &account.id,
+                        &dimension,
+                        &start_date,
+                        &end_date,
+                        &costs,
+                    ) {
+                        tracing::warn!("Failed to save breakdown cache: {}", e);
+                    }
+                    let groups = crate::cloud::group_costs_by_dimension(&costs, &dimension);
+                    let _ = tx.send(Ok(groups));
+                }
+                Err(e) => {
+                    tracing::error!("Failed to get cost breakdown for {}: {}", account.name, e);
+                    let _ = tx.send(Err(format!(
+                        "Failed to get breakdown data: {}",
+                        redact::error_message(&e)
+                    )));
+                }
+            }
+        });
+
+        cx.spawn(async move |this, cx| {
+            let result = smol::unblock(move || {
+                rx.recv_timeout(std::time::Duration::from_secs(60))
+                    .unwrap_or(Err("Breakdown data retrieval timeout".to_string()))
+            })
+            .await;
+
+            cx.update(|cx| {
+                this.update(cx, |this, cx| {
+                    this.loading_breakdowns.insert(key.clone(), false);
+
+                    if let Ok(groups) = result {
+                        this.breakdowns.insert(key, groups);
+                    }
+                    cx.notify();
+                })
+                .ok();
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    /// Render cost trend chart
+    fn render_trend_chart(&self, account_id: &str, cx: &Context<Self>) -> AnyElement {
+        // Check if loading
+        if self
+            .loading_trends
+            .get(account_id)
+            .copied()
+            .unwrap_or(false)
+        {
+            return div()
+                .w_full()
+                .h(px(120.0))
+                .flex()
+                .items_center()
+                .justify_center()
+                .text_color(cx.theme().muted_foreground)
+                .child("Loading trend data...")
+                .into_any_element();
+        }
+
+        // Check for cached data
+        if let Some(trend) = self.cost_trends.get(account_id) {
             // Use BarChart with labels for daily cost visualization
             let bar_chart = CostBarChart::new(trend.daily_costs.clone(), 550.0, 150.0);
 
@@ -656,6 +1839,32 @@ impl DashboardView {
             {
                 self.load_cost_trend(account_id, cx);
             }
+            self.ensure_breakdown_loaded(account_id, cx);
+
+            let is_aws = self
+                .summaries
+                .iter()
+                .any(|s| s.account_id == account_id && s.provider == CloudProvider::AWS);
+            if is_aws
+                && !self.commitments.contains_key(account_id)
+                && !self
+                    .loading_commitments
+                    .get(account_id)
+                    .copied()
+                    .unwrap_or(false)
+            {
+                self.load_commitments(account_id, cx);
+            }
+            if is_aws
+                && !self.anomalies.contains_key(account_id)
+                && !self
+                    .loading_anomalies
+                    .get(account_id)
+                    .copied()
+                    .unwrap_or(false)
+            {
+                self.load_anomalies(account_id, cx);
+            }
         }
         cx.notify();
     }
@@ -667,7 +1876,7 @@ impl DashboardView {
 
         // Get account info
         let account = match crate::db::get_all_accounts() {
-            Ok(accounts) => accounts.into_iter().find(|a| a.id == account_id_clone),
+            Ok(accounts) => crate::cloud::find_account(accounts, &account_id_clone),
             Err(_) => None,
         };
 
@@ -708,6 +1917,36 @@ impl DashboardView {
                 return;
             }
 
+            // Accounts with a local CUR directory use the ingested report data
+            let config = crate::config::load_config().unwrap_or_default();
+            if config
+                .cur_directory_for(&account.id, &account.name)
+                .is_some()
+            {
+                let tomorrow = now + Duration::days(1);
+                let cur_end = format!(
+                    "{}-{:02}-{:02}",
+                    tomorrow.year(),
+                    tomorrow.month(),
+                    tomorrow.day()
+                );
+                if let Ok(Some(trend)) =
+                    crate::db::get_cost_trend_from_cost_data(&account.id, &start_date, &cur_end)
+                {
+                    let _ = tx.send(Ok(trend));
+                    return;
+                }
+            }
+
+            // Credential references are resolved for this call only
+            let account = match crate::secret_store::resolve_credentials(&account) {
+                Ok(account) => account,
+                Err(e) => {
+                    let _ = tx.send(Err(format!("{:#}", e)));
+                    return;
+                }
+            };
+
             match account.provider {
                 crate::cloud::CloudProvider::AWS => {
                     let service = crate::cloud::aws::AwsCloudService::new(
@@ -728,18 +1967,15 @@ impl DashboardView {
                             let _ = tx.send(Ok(trend));
                         }
                         Err(e) => {
-                            let _ = tx.send(Err(format!("Failed to get trend data: {}", e)));
+                            let _ = tx.send(Err(format!(
+                                "Failed to get trend data: {}",
+                                redact::error_message(&e)
+                            )));
                         }
                     }
                 }
                 crate::cloud::CloudProvider::Aliyun => {
-                    let service = crate::cloud::aliyun::AliyunCloudService::new(
-                        account.id.clone(),
-                        account.name.clone(),
-                        account.access_key_id.clone(),
-                        account.secret_access_key.clone(),
-                        account.region.clone(),
-                    );
+                    let service = crate::cloud::aliyun::AliyunCloudService::for_account(&account);
 
                     use crate::cloud::CloudService;
                     match service.get_cost_trend(&start_date, &end_date) {
@@ -751,7 +1987,10 @@ impl DashboardView {
                             let _ = tx.send(Ok(trend));
                         }
                         Err(e) => {
-                            let _ = tx.send(Err(format!("Failed to get Aliyun trend data: {}", e)));
+                            let _ = tx.send(Err(format!(
+                                "Failed to get Aliyun trend data: {}",
+                                redact::error_message(&e)
+                            )));
                         }
                     }
                 }
@@ -830,7 +2069,13 @@ impl Render for DashboardView {
                             .child(error.clone())
                             .into_any_element()
                     } else {
-                        self.render_summary_cards(cx).into_any_element()
+                        div()
+                            .w_full()
+                            .v_flex()
+                            .gap_4()
+                            .child(self.render_account_errors(cx))
+                            .child(self.render_summary_cards(cx))
+                            .into_any_element()
                     }),
             )
     }
@@ -2,9 +2,40 @@
 
 use gpui::prelude::FluentBuilder;
 use gpui::*;
-use gpui_component::{switch::*, *};
+use gpui_component::{
+    button::*,
+    input::{Input, InputState},
+    switch::*,
+    *,
+};
 
-use crate::config::{load_config, save_config, AppConfig};
+use crate::backup;
+use crate::cache::{self, CacheDataset, CacheEntry};
+use crate::cloud::CloudAccount;
+use crate::config::{
+    load_config, save_config, AppConfig, SecretBackendKind, SmtpConfig, SmtpSecurity,
+};
+use crate::crypto::{self, MIN_PASSPHRASE_LEN};
+use crate::db;
+use crate::master_key;
+use crate::redact;
+use crate::secret_store;
+use crate::transfer::{self, ConflictPolicy};
+
+/// Background sync intervals offered in the Data section (minutes, label)
+const REFRESH_INTERVALS: &[(u32, &str)] = &[
+    (0, "Off"),
+    (30, "30m"),
+    (60, "1h"),
+    (180, "3h"),
+    (360, "6h"),
+];
+
+/// Automatic backup counts offered in the Backup section
+const AUTO_BACKUP_COUNTS: &[u32] = &[0, 3, 5, 10];
+
+/// Idle times offered for auto-lock (minutes, label)
+const AUTO_LOCK_MINUTES: &[(u32, &str)] = &[(0, "Never"), (5, "5m"), (15, "15m"), (60, "1h")];
 
 /// Settings View
 pub struct SettingsView {
@@ -12,16 +43,893 @@ pub struct SettingsView {
     config: AppConfig,
     /// Save status
     save_status: Option<String>,
+    /// SMTP form inputs
+    smtp_host_input: Entity<InputState>,
+    smtp_port_input: Entity<InputState>,
+    smtp_username_input: Entity<InputState>,
+    smtp_password_input: Entity<InputState>,
+    smtp_from_input: Entity<InputState>,
+    /// Selected SMTP connection security
+    smtp_security: SmtpSecurity,
+    /// Whether digests are being sent
+    sending_digests: bool,
+    /// Accounts and their cached datasets shown in the Cache section
+    accounts: Vec<CloudAccount>,
+    cache_entries: Vec<CacheEntry>,
+    /// Whether a backup, restore, account export or import is running
+    file_task_running: bool,
+    /// Passphrase protecting account exports
+    transfer_passphrase_input: Entity<InputState>,
+    /// Handling of imported accounts whose ID already exists
+    transfer_conflict: ConflictPolicy,
+    /// Whether an import also replaces settings
+    transfer_settings: bool,
+    /// Master passphrase and its confirmation
+    master_passphrase_input: Entity<InputState>,
+    master_confirm_input: Entity<InputState>,
+    /// Secret backend actually in use (the keyring may fall back to the encrypted file)
+    secret_backend_in_use: Option<SecretBackendKind>,
+}
+
+fn active_secret_backend() -> Option<SecretBackendKind> {
+    secret_store::active_backend()
+        .ok()
+        .map(|backend| backend.kind())
 }
 
 impl SettingsView {
-    pub fn new(_window: &mut Window, _cx: &mut Context<Self>) -> Self {
+    pub fn new(window: &mut Window, cx: &mut Context<Self>) -> Self {
         let config = load_config().unwrap_or_default();
+        let smtp = config.smtp.clone();
+
+        let smtp_host_input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .placeholder("SMTP host (e.g. smtp.example.com)")
+                .default_value(smtp.as_ref().map(|s| s.host.clone()).unwrap_or_default())
+        });
+        let smtp_port_input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .placeholder("Port")
+                .default_value(smtp.as_ref().map(|s| s.port).unwrap_or(587).to_string())
+        });
+        let smtp_username_input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .placeholder("Username (optional)")
+                .default_value(
+                    smtp.as_ref()
+                        .and_then(|s| s.username.clone())
+                        .unwrap_or_default(),
+                )
+        });
+        let smtp_password_input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .placeholder("Password (leave empty to keep the stored one)")
+                .masked(true)
+        });
+        let smtp_from_input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .placeholder("From address (e.g. CloudBridge <reports@example.com>)")
+                .default_value(smtp.as_ref().map(|s| s.from.clone()).unwrap_or_default())
+        });
+
+        let transfer_passphrase_input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .placeholder(format!(
+                    "Passphrase (at least {} characters)",
+                    MIN_PASSPHRASE_LEN
+                ))
+                .masked(true)
+        });
+
+        let master_passphrase_input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .placeholder("Master passphrase")
+                .masked(true)
+        });
+        let master_confirm_input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .placeholder("Confirm master passphrase")
+                .masked(true)
+        });
 
         Self {
+            smtp_security: smtp.map(|s| s.security).unwrap_or_default(),
             config,
             save_status: None,
+            smtp_host_input,
+            smtp_port_input,
+            smtp_username_input,
+            smtp_password_input,
+            smtp_from_input,
+            sending_digests: false,
+            accounts: db::get_all_accounts().unwrap_or_default(),
+            cache_entries: db::get_cache_entries().unwrap_or_default(),
+            file_task_running: false,
+            transfer_passphrase_input,
+            transfer_conflict: ConflictPolicy::default(),
+            transfer_settings: false,
+            master_passphrase_input,
+            master_confirm_input,
+            secret_backend_in_use: active_secret_backend(),
+        }
+    }
+
+    /// Turn master passphrase mode on or off, re-encrypting stored credentials
+    fn toggle_master_passphrase(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if self.file_task_running {
+            return;
+        }
+        let passphrase = self.master_passphrase_input.read(cx).value().to_string();
+        let enabled = self.config.master_key.is_some();
+        if !enabled {
+            if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+                self.save_status = Some(format!(
+                    "Enter a master passphrase of at least {} characters",
+                    MIN_PASSPHRASE_LEN
+                ));
+                cx.notify();
+                return;
+            }
+            if passphrase != self.master_confirm_input.read(cx).value().as_ref() {
+                self.save_status = Some("Passphrases do not match".to_string());
+                cx.notify();
+                return;
+            }
+        }
+
+        for input in [&self.master_passphrase_input, &self.master_confirm_input] {
+            input.update(cx, |input, cx| input.set_value("", window, cx));
+        }
+        if enabled {
+            self.run_file_task(
+                "Disabling master passphrase...",
+                move || {
+                    master_key::disable(&passphrase)
+                        .map(|_| "Master passphrase disabled".to_string())
+                        .map_err(|e| {
+                            format!(
+                                "Failed to disable master passphrase: {}",
+                                redact::error_message(&e)
+                            )
+                        })
+                },
+                cx,
+            );
+        } else {
+            self.run_file_task(
+                "Enabling master passphrase...",
+                move || {
+                    master_key::enable(&passphrase)
+                        .map(|_| "Master passphrase enabled; credentials re-encrypted".to_string())
+                        .map_err(|e| {
+                            format!(
+                                "Failed to enable master passphrase: {}",
+                                redact::error_message(&e)
+                            )
+                        })
+                },
+                cx,
+            );
+        }
+    }
+
+    fn set_auto_lock(&mut self, minutes: u32, cx: &mut Context<Self>) {
+        match master_key::set_auto_lock(minutes) {
+            Ok(()) => {
+                self.config = load_config().unwrap_or_default();
+                self.save_status = Some("Settings saved".to_string());
+            }
+            Err(e) => {
+                self.save_status = Some(format!("Failed to save: {}", redact::error_message(&e)))
+            }
+        }
+        cx.notify();
+    }
+
+    /// Select the secret backend and move stored secrets to it
+    fn set_secret_backend(&mut self, kind: SecretBackendKind, cx: &mut Context<Self>) {
+        if self.file_task_running || self.config.secret_backend == kind {
+            return;
+        }
+        self.run_file_task(
+            "Moving secrets...",
+            move || {
+                secret_store::switch_backend(kind)
+                    .map(|moved| format!("Secrets stored in {} ({} moved)", kind.label(), moved))
+                    .map_err(|e| {
+                        format!(
+                            "Failed to change secret storage: {}",
+                            redact::error_message(&e)
+                        )
+                    })
+            },
+            cx,
+        );
+    }
+
+    fn render_secret_backend(&self, cx: &Context<Self>) -> impl IntoElement {
+        let in_use = self
+            .secret_backend_in_use
+            .map(|kind| format!("Currently using: {}", kind.label()))
+            .unwrap_or_default();
+
+        div()
+            .h_flex()
+            .justify_between()
+            .items_center()
+            .child(
+                div().v_flex().child(div().child("Secret Storage")).child(
+                    div()
+                        .text_sm()
+                        .text_color(cx.theme().muted_foreground)
+                        .child(in_use),
+                ),
+            )
+            .child(
+                div()
+                    .h_flex()
+                    .gap_1()
+                    .children(SecretBackendKind::ALL.into_iter().map(|kind| {
+                        let button = Button::new(SharedString::from(format!(
+                            "secret-backend-{}",
+                            kind.label()
+                        )))
+                        .label(kind.label())
+                        .small();
+                        let button = if self.config.secret_backend == kind {
+                            button.primary()
+                        } else {
+                            button.ghost()
+                        };
+                        button.on_click(cx.listener(move |this, _, _, cx| {
+                            this.set_secret_backend(kind, cx);
+                        }))
+                    })),
+            )
+    }
+
+    fn render_security(&self, cx: &Context<Self>) -> impl IntoElement {
+        div()
+            .v_flex()
+            .gap_4()
+            .child(self.render_secret_backend(cx))
+            .child(self.render_master_passphrase(cx))
+    }
+
+    fn render_master_passphrase(&self, cx: &Context<Self>) -> impl IntoElement {
+        let Some(master) = &self.config.master_key else {
+            return div()
+                .v_flex()
+                .gap_3()
+                .child(
+                    div()
+                        .text_sm()
+                        .text_color(cx.theme().muted_foreground)
+                        .child(
+                            "Protect credentials with a master passphrase entered at startup instead of a key stored in config.json. The passphrase cannot be recovered.",
+                        ),
+                )
+                .child(Input::new(&self.master_passphrase_input))
+                .child(Input::new(&self.master_confirm_input))
+                .child(
+                    div().h_flex().child(
+                        Button::new("enable-master-passphrase")
+                            .label("Enable Master Passphrase")
+                            .primary()
+                            .on_click(cx.listener(|this, _, window, cx| {
+                                this.toggle_master_passphrase(window, cx);
+                            })),
+                    ),
+                );
+        };
+
+        div()
+            .v_flex()
+            .gap_3()
+            .child(
+                div()
+                    .h_flex()
+                    .justify_between()
+                    .items_center()
+                    .child(
+                        div().v_flex().child(div().child("Auto-Lock")).child(
+                            div()
+                                .text_sm()
+                                .text_color(cx.theme().muted_foreground)
+                                .child("Lock after this long without mouse or keyboard input"),
+                        ),
+                    )
+                    .child(
+                        div()
+                            .h_flex()
+                            .gap_1()
+                            .children(AUTO_LOCK_MINUTES.iter().map(|&(minutes, label)| {
+                                let button = Button::new(SharedString::from(format!(
+                                    "auto-lock-{}",
+                                    minutes
+                                )))
+                                .label(label)
+                                .small();
+                                let button = if master.auto_lock_minutes == minutes {
+                                    button.primary()
+                                } else {
+                                    button.ghost()
+                                };
+                                button.on_click(cx.listener(move |this, _, _, cx| {
+                                    this.set_auto_lock(minutes, cx);
+                                }))
+                            })),
+                    ),
+            )
+            .child(
+                div()
+                    .h_flex()
+                    .child(Button::new("lock-now").label("Lock Now").ghost().on_click(
+                        cx.listener(|_, _, _, _| {
+                            crypto::lock();
+                        }),
+                    )),
+            )
+            .child(Input::new(&self.master_passphrase_input))
+            .child(
+                div().h_flex().child(
+                    Button::new("disable-master-passphrase")
+                        .label("Disable Master Passphrase")
+                        .ghost()
+                        .on_click(cx.listener(|this, _, window, cx| {
+                            this.toggle_master_passphrase(window, cx);
+                        })),
+                ),
+            )
+    }
+
+    /// Export accounts with credentials, budgets and settings to an encrypted file
+    fn export_accounts(&mut self, cx: &mut Context<Self>) {
+        if self.file_task_running {
+            return;
+        }
+        let passphrase = self.transfer_passphrase_input.read(cx).value().to_string();
+        if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+            self.save_status = Some(format!(
+                "Enter a passphrase of at least {} characters",
+                MIN_PASSPHRASE_LEN
+            ));
+            cx.notify();
+            return;
+        }
+
+        let directory = directories::UserDirs::new()
+            .and_then(|dirs| dirs.document_dir().map(|d| d.to_path_buf()))
+            .or_else(|| crate::config::get_app_data_dir().ok())
+            .unwrap_or_else(std::env::temp_dir);
+        let path_prompt =
+            cx.prompt_for_new_path(&directory, Some("cloudbridge-accounts.cbaccounts"));
+
+        cx.spawn(async move |this, cx| {
+            let Ok(Ok(Some(path))) = path_prompt.await else {
+                return;
+            };
+            cx.update(|cx| {
+                this.update(cx, |this, cx| {
+                    this.run_file_task(
+                        "Exporting accounts...",
+                        move || {
+                            transfer::export_accounts(&path, &passphrase)
+                                .map(|count| {
+                                    format!("Exported {} accounts to {}", count, path.display())
+                                })
+                                .map_err(|e| {
+                                    format!("Export failed: {}", redact::error_message(&e))
+                                })
+                        },
+                        cx,
+                    );
+                })
+                .ok();
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    /// Import an encrypted account export
+    fn import_accounts(&mut self, cx: &mut Context<Self>) {
+        if self.file_task_running {
+            return;
+        }
+        let passphrase = self.transfer_passphrase_input.read(cx).value().to_string();
+        let policy = self.transfer_conflict;
+        let include_settings = self.transfer_settings;
+        let paths_prompt = cx.prompt_for_paths(PathPromptOptions {
+            files: true,
+            directories: false,
+            multiple: false,
+            prompt: Some("Import".into()),
+        });
+
+        cx.spawn(async move |this, cx| {
+            let Ok(Ok(Some(paths))) = paths_prompt.await else {
+                return;
+            };
+            let Some(path) = paths.into_iter().next() else {
+                return;
+            };
+            cx.update(|cx| {
+                this.update(cx, |this, cx| {
+                    this.run_file_task(
+                        "Importing accounts...",
+                        move || {
+                            transfer::import_accounts(&path, &passphrase, policy, include_settings)
+                                .map(|summary| summary.describe())
+                                .map_err(|e| {
+                                    format!("Import failed: {}", redact::error_message(&e))
+                                })
+                        },
+                        cx,
+                    );
+                })
+                .ok();
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    fn render_transfer(&self, cx: &Context<Self>) -> impl IntoElement {
+        let conflict_button = |policy: ConflictPolicy| {
+            let button = Button::new(SharedString::from(format!(
+                "transfer-conflict-{}",
+                policy.label()
+            )))
+            .label(policy.label())
+            .small();
+            let button = if self.transfer_conflict == policy {
+                button.primary()
+            } else {
+                button.ghost()
+            };
+            button.on_click(cx.listener(move |this, _, _, cx| {
+                this.transfer_conflict = policy;
+                cx.notify();
+            }))
+        };
+
+        div()
+            .v_flex()
+            .gap_3()
+            .child(
+                div()
+                    .text_sm()
+                    .text_color(cx.theme().muted_foreground)
+                    .child(
+                        "Moves accounts with their credentials, budgets and settings to another machine in a file encrypted with a passphrase.",
+                    ),
+            )
+            .child(Input::new(&self.transfer_passphrase_input))
+            .child(
+                div()
+                    .h_flex()
+                    .gap_2()
+                    .items_center()
+                    .child(
+                        div()
+                            .text_sm()
+                            .text_color(cx.theme().muted_foreground)
+                            .child("Existing accounts:"),
+                    )
+                    .children(ConflictPolicy::ALL.into_iter().map(conflict_button)),
+            )
+            .child(
+                div()
+                    .h_flex()
+                    .justify_between()
+                    .items_center()
+                    .child(div().text_sm().child("Also import settings"))
+                    .child(
+                        Switch::new("transfer-settings")
+                            .checked(self.transfer_settings)
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                this.transfer_settings = !this.transfer_settings;
+                                cx.notify();
+                            })),
+                    ),
+            )
+            .child(
+                div()
+                    .h_flex()
+                    .gap_2()
+                    .child(
+                        Button::new("export-accounts")
+                            .label("Export Accounts...")
+                            .primary()
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                this.export_accounts(cx);
+                            })),
+                    )
+                    .child(
+                        Button::new("import-accounts")
+                            .label("Import Accounts...")
+                            .ghost()
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                this.import_accounts(cx);
+                            })),
+                    ),
+            )
+    }
+
+    /// Run a file task (backup, restore, account export or import) in a background
+    /// thread and report the result
+    fn run_file_task(
+        &mut self,
+        running: &str,
+        task: impl FnOnce() -> Result<String, String> + Send + 'static,
+        cx: &mut Context<Self>,
+    ) {
+        self.file_task_running = true;
+        self.save_status = Some(running.to_string());
+        cx.notify();
+
+        let (tx, rx) = std::sync::mpsc::channel::<Result<String, String>>();
+        std::thread::spawn(move || {
+            let _ = tx.send(task());
+        });
+
+        cx.spawn(async move |this, cx| {
+            let result = smol::unblock(move || {
+                rx.recv_timeout(std::time::Duration::from_secs(600))
+                    .unwrap_or(Err("Operation timeout".to_string()))
+            })
+            .await;
+
+            cx.update(|cx| {
+                this.update(cx, |this, cx| {
+                    this.file_task_running = false;
+                    this.config = load_config().unwrap_or_default();
+                    this.secret_backend_in_use = active_secret_backend();
+                    this.save_status = Some(result.unwrap_or_else(|e| e));
+                    this.reload_cache_entries(cx);
+                })
+                .ok();
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    /// Archive the database and configuration to a chosen file
+    fn backup_now(&mut self, cx: &mut Context<Self>) {
+        if self.file_task_running {
+            return;
         }
+        let directory = directories::UserDirs::new()
+            .and_then(|dirs| dirs.document_dir().map(|d| d.to_path_buf()))
+            .or_else(|| crate::config::get_app_data_dir().ok())
+            .unwrap_or_else(std::env::temp_dir);
+        let file_name = format!(
+            "cloudbridge-backup-{}.tar.gz",
+            chrono::Utc::now().format("%Y%m%d-%H%M")
+        );
+        let path_prompt = cx.prompt_for_new_path(&directory, Some(&file_name));
+
+        cx.spawn(async move |this, cx| {
+            let Ok(Ok(Some(path))) = path_prompt.await else {
+                return;
+            };
+            cx.update(|cx| {
+                this.update(cx, |this, cx| {
+                    this.run_file_task(
+                        "Backing up...",
+                        move || {
+                            backup::create_backup(&path)
+                                .map(|_| format!("Backup saved to {}", path.display()))
+                                .map_err(|e| {
+                                    format!("Backup failed: {}", redact::error_message(&e))
+                                })
+                        },
+                        cx,
+                    );
+                })
+                .ok();
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    /// Restore a chosen backup archive
+    fn restore(&mut self, cx: &mut Context<Self>) {
+        if self.file_task_running {
+            return;
+        }
+        let paths_prompt = cx.prompt_for_paths(PathPromptOptions {
+            files: true,
+            directories: false,
+            multiple: false,
+            prompt: Some("Restore".into()),
+        });
+
+        cx.spawn(async move |this, cx| {
+            let Ok(Ok(Some(paths))) = paths_prompt.await else {
+                return;
+            };
+            let Some(path) = paths.into_iter().next() else {
+                return;
+            };
+            cx.update(|cx| {
+                this.update(cx, |this, cx| {
+                    this.run_file_task(
+                        "Restoring...",
+                        move || {
+                            backup::restore_backup(&path)
+                                .map(|manifest| {
+                                    format!(
+                                        "Restored backup from {}. Re-enter credentials if it came from another machine, then refresh the dashboard.",
+                                        manifest.created_at.format("%Y-%m-%d %H:%M UTC")
+                                    )
+                                })
+                                .map_err(|e| format!("Restore failed: {}", redact::error_message(&e)))
+                        },
+                        cx,
+                    );
+                })
+                .ok();
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    fn render_backup(&self, cx: &Context<Self>) -> impl IntoElement {
+        let backup_dir = backup::backup_dir()
+            .map(|dir| dir.display().to_string())
+            .unwrap_or_default();
+
+        div()
+            .v_flex()
+            .gap_3()
+            .child(
+                div()
+                    .text_sm()
+                    .text_color(cx.theme().muted_foreground)
+                    .child(
+                        "Archives the database and settings. Credentials stay in the secret storage and are not included.",
+                    ),
+            )
+            .child(
+                div()
+                    .h_flex()
+                    .justify_between()
+                    .items_center()
+                    .child(
+                        div()
+                            .v_flex()
+                            .child(div().child("Automatic Backups"))
+                            .child(
+                                div()
+                                    .text_sm()
+                                    .text_color(cx.theme().muted_foreground)
+                                    .child(format!(
+                                        "Kept before database upgrades and restores in {}",
+                                        backup_dir
+                                    )),
+                            ),
+                    )
+                    .child(div().h_flex().gap_1().children(AUTO_BACKUP_COUNTS.iter().map(
+                        |&count| {
+                            let button = Button::new(SharedString::from(format!(
+                                "auto-backups-{}",
+                                count
+                            )))
+                            .label(if count == 0 {
+                                "Off".to_string()
+                            } else {
+                                count.to_string()
+                            })
+                            .small();
+                            let button = if self.config.auto_backups == count {
+                                button.primary()
+                            } else {
+                                button.ghost()
+                            };
+                            button.on_click(cx.listener(move |this, _, _, cx| {
+                                this.config.auto_backups = count;
+                                this.save_config(cx);
+                            }))
+                        },
+                    ))),
+            )
+            .child(
+                div()
+                    .h_flex()
+                    .gap_2()
+                    .child(
+                        Button::new("backup-now")
+                            .label(if self.file_task_running {
+                                "Working..."
+                            } else {
+                                "Back Up Now"
+                            })
+                            .primary()
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                this.backup_now(cx);
+                            })),
+                    )
+                    .child(
+                        Button::new("restore-backup")
+                            .label("Restore...")
+                            .ghost()
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                this.restore(cx);
+                            })),
+                    ),
+            )
+    }
+
+    /// Re-read accounts and cache entries
+    fn reload_cache_entries(&mut self, cx: &mut Context<Self>) {
+        self.accounts = db::get_all_accounts().unwrap_or_default();
+        match db::get_cache_entries() {
+            Ok(entries) => self.cache_entries = entries,
+            Err(e) => {
+                self.save_status = Some(format!(
+                    "Failed to read cache: {}",
+                    redact::error_message(&e)
+                ))
+            }
+        }
+        cx.notify();
+    }
+
+    /// Invalidate cached data of an account, optionally only one dataset
+    fn invalidate_cache(
+        &mut self,
+        account_id: &str,
+        dataset: Option<CacheDataset>,
+        cx: &mut Context<Self>,
+    ) {
+        match db::invalidate_cache(Some(account_id), dataset) {
+            Ok(()) => {
+                self.save_status = Some(match dataset {
+                    Some(dataset) => format!("{} cache cleared", dataset.label()),
+                    None => "Account cache cleared".to_string(),
+                })
+            }
+            Err(e) => {
+                self.save_status = Some(format!(
+                    "Failed to clear cache: {}",
+                    redact::error_message(&e)
+                ))
+            }
+        }
+        self.reload_cache_entries(cx);
+    }
+
+    fn render_cache(&self, cx: &Context<Self>) -> impl IntoElement {
+        let now = chrono::Utc::now();
+
+        let accounts = self.accounts.iter().filter_map(|account| {
+            let entries: Vec<&CacheEntry> = self
+                .cache_entries
+                .iter()
+                .filter(|e| e.account_id == account.id)
+                .collect();
+            if entries.is_empty() {
+                return None;
+            }
+
+            let account_id = account.id.clone();
+            let rows = entries.into_iter().map(move |entry| {
+                let ttl = cache::ttl(&self.config, account.provider, entry.dataset);
+                let age = now - entry.updated_at;
+                let freshness = if cache::is_expired(entry.updated_at, ttl, now) {
+                    "expired".to_string()
+                } else {
+                    format!(
+                        "fresh for {}",
+                        crate::scheduler::format_countdown(ttl - age)
+                    )
+                };
+                let account_id = entry.account_id.clone();
+                let dataset = entry.dataset;
+
+                div()
+                    .h_flex()
+                    .justify_between()
+                    .items_center()
+                    .pl_3()
+                    .child(
+                        div()
+                            .text_sm()
+                            .text_color(cx.theme().muted_foreground)
+                            .child(format!(
+                                "{} · {} rows · updated {} · {}",
+                                dataset.label(),
+                                entry.rows,
+                                cache::format_age(age),
+                                freshness
+                            )),
+                    )
+                    .child(
+                        Button::new(SharedString::from(format!(
+                            "clear-cache-{}-{}",
+                            account_id,
+                            dataset.key()
+                        )))
+                        .label("Clear")
+                        .ghost()
+                        .small()
+                        .on_click(cx.listener(move |this, _, _, cx| {
+                            this.invalidate_cache(&account_id, Some(dataset), cx);
+                        })),
+                    )
+            });
+
+            Some(
+                div()
+                    .v_flex()
+                    .gap_1()
+                    .child(
+                        div()
+                            .h_flex()
+                            .justify_between()
+                            .items_center()
+                            .child(div().child(format!(
+                                "{} ({})",
+                                account.name,
+                                account.provider.short_name()
+                            )))
+                            .child(
+                                Button::new(SharedString::from(format!(
+                                    "clear-cache-{}",
+                                    account.id
+                                )))
+                                .label("Clear Account")
+                                .ghost()
+                                .small()
+                                .on_click(cx.listener(
+                                    move |this, _, _, cx| {
+                                        this.invalidate_cache(&account_id, None, cx);
+                                    },
+                                )),
+                            ),
+                    )
+                    .children(rows),
+            )
+        });
+
+        div()
+            .v_flex()
+            .gap_3()
+            .child(
+                div()
+                    .h_flex()
+                    .justify_between()
+                    .items_center()
+                    .child(
+                        div()
+                            .text_sm()
+                            .text_color(cx.theme().muted_foreground)
+                            .child("Cost data of settled months is kept until cleared."),
+                    )
+                    .child(
+                        Button::new("reload-cache")
+                            .label("Reload")
+                            .ghost()
+                            .small()
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                this.reload_cache_entries(cx);
+                            })),
+                    ),
+            )
+            .when(self.cache_entries.is_empty(), |el| {
+                el.child(
+                    div()
+                        .text_sm()
+                        .text_color(cx.theme().muted_foreground)
+                        .child("Nothing cached yet"),
+                )
+            })
+            .children(accounts)
     }
 
     fn toggle_dark_mode(&mut self, cx: &mut Context<Self>) {
@@ -35,12 +943,236 @@ impl SettingsView {
                 self.save_status = Some("Settings saved".to_string());
             }
             Err(e) => {
-                self.save_status = Some(format!("Save failed: {}", e));
+                self.save_status = Some(format!("Save failed: {}", redact::error_message(&e)));
             }
         }
         cx.notify();
     }
 
+    /// Save the SMTP form; the password goes to the OS keyring
+    fn save_smtp(&mut self, cx: &mut Context<Self>) {
+        let host = self.smtp_host_input.read(cx).value().trim().to_string();
+        let port = self.smtp_port_input.read(cx).value().trim().to_string();
+        let username = self.smtp_username_input.read(cx).value().trim().to_string();
+        let password = self.smtp_password_input.read(cx).value().to_string();
+        let from = self.smtp_from_input.read(cx).value().trim().to_string();
+
+        if host.is_empty() {
+            self.config.smtp = None;
+            self.save_config(cx);
+            return;
+        }
+        let Ok(port) = port.parse::<u16>() else {
+            self.save_status = Some("SMTP port must be a number".to_string());
+            cx.notify();
+            return;
+        };
+        if from.is_empty() {
+            self.save_status = Some("SMTP from address is required".to_string());
+            cx.notify();
+            return;
+        }
+
+        if !username.is_empty() && !password.is_empty() {
+            if let Err(e) = secret_store::store_smtp_password(&username, &password) {
+                self.save_status = Some(format!(
+                    "Failed to store SMTP password: {}",
+                    redact::error_message(&e)
+                ));
+                cx.notify();
+                return;
+            }
+        }
+
+        self.config.smtp = Some(SmtpConfig {
+            host,
+            port,
+            security: self.smtp_security,
+            username: (!username.is_empty()).then_some(username),
+            from,
+        });
+        self.save_config(cx);
+    }
+
+    /// Send the latest period of every configured digest now
+    fn send_digests(&mut self, cx: &mut Context<Self>) {
+        if self.sending_digests {
+            return;
+        }
+        self.sending_digests = true;
+        self.save_status = Some("Sending digests...".to_string());
+        cx.notify();
+
+        let (tx, rx) = std::sync::mpsc::channel::<Result<Vec<String>, String>>();
+        std::thread::spawn(move || {
+            let result =
+                crate::digest::run_due_digests(true).map_err(|e| redact::error_message(&e));
+            let _ = tx.send(result);
+        });
+
+        cx.spawn(async move |this, cx| {
+            let result = smol::unblock(move || {
+                rx.recv_timeout(std::time::Duration::from_secs(600))
+                    .unwrap_or(Err("Digest delivery timeout".to_string()))
+            })
+            .await;
+
+            cx.update(|cx| {
+                this.update(cx, |this, cx| {
+                    this.sending_digests = false;
+                    this.save_status = Some(match result {
+                        Ok(sent) if sent.is_empty() => "No digests configured".to_string(),
+                        Ok(sent) => format!("Sent {}", sent.join(", ")),
+                        Err(e) => e,
+                    });
+                    cx.notify();
+                })
+                .ok();
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    fn render_digests(&self, cx: &Context<Self>) -> impl IntoElement {
+        let security_button = |security: SmtpSecurity| {
+            let button = Button::new(SharedString::from(format!(
+                "smtp-security-{}",
+                security.label()
+            )))
+            .label(security.label())
+            .small();
+            let button = if self.smtp_security == security {
+                button.primary()
+            } else {
+                button.ghost()
+            };
+            button.on_click(cx.listener(move |this, _, _, cx| {
+                this.smtp_security = security;
+                cx.notify();
+            }))
+        };
+
+        div()
+            .v_flex()
+            .gap_3()
+            .child(
+                div()
+                    .text_sm()
+                    .text_color(cx.theme().muted_foreground)
+                    .child("Weekly or monthly cost reports emailed to each account group."),
+            )
+            .child(
+                div()
+                    .h_flex()
+                    .gap_2()
+                    .child(div().flex_1().child(Input::new(&self.smtp_host_input)))
+                    .child(div().w(px(90.0)).child(Input::new(&self.smtp_port_input))),
+            )
+            .child(
+                div()
+                    .h_flex()
+                    .gap_2()
+                    .child(div().flex_1().child(Input::new(&self.smtp_username_input)))
+                    .child(div().flex_1().child(Input::new(&self.smtp_password_input))),
+            )
+            .child(Input::new(&self.smtp_from_input))
+            .child(
+                div()
+                    .h_flex()
+                    .gap_2()
+                    .items_center()
+                    .child(
+                        div()
+                            .text_sm()
+                            .text_color(cx.theme().muted_foreground)
+                            .child("Security:"),
+                    )
+                    .child(security_button(SmtpSecurity::StartTls))
+                    .child(security_button(SmtpSecurity::Tls))
+                    .child(security_button(SmtpSecurity::None)),
+            )
+            .child(
+                div()
+                    .v_flex()
+                    .gap_1()
+                    .when(self.config.digests.is_empty(), |el| {
+                        el.child(
+                            div()
+                                .text_sm()
+                                .text_color(cx.theme().muted_foreground)
+                                .child(
+                                "No digests scheduled. Add them under \"digests\" in config.json.",
+                            ),
+                        )
+                    })
+                    .children(self.config.digests.iter().map(|digest| {
+                        let accounts = if digest.accounts.is_empty() {
+                            "all accounts".to_string()
+                        } else {
+                            digest.accounts.join(", ")
+                        };
+                        div().text_sm().child(format!(
+                            "{} · {} · {} → {}",
+                            digest.name,
+                            digest.frequency.label(),
+                            accounts,
+                            digest.recipients.join(", ")
+                        ))
+                    })),
+            )
+            .child(
+                div()
+                    .h_flex()
+                    .gap_2()
+                    .child(
+                        Button::new("save-smtp")
+                            .label("Save Email Settings")
+                            .primary()
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                this.save_smtp(cx);
+                            })),
+                    )
+                    .child(
+                        Button::new("send-digests")
+                            .label(if self.sending_digests {
+                                "Sending..."
+                            } else {
+                                "Send Digests Now"
+                            })
+                            .ghost()
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                this.send_digests(cx);
+                            })),
+                    ),
+            )
+    }
+
+    fn set_refresh_interval(&mut self, minutes: u32, cx: &mut Context<Self>) {
+        self.config.refresh_interval_minutes = minutes;
+        self.save_config(cx);
+    }
+
+    fn render_interval_options(&self, cx: &Context<Self>) -> impl IntoElement {
+        div()
+            .h_flex()
+            .gap_1()
+            .children(REFRESH_INTERVALS.iter().map(|&(minutes, label)| {
+                let button =
+                    Button::new(SharedString::from(format!("refresh-interval-{}", minutes)))
+                        .label(label)
+                        .small();
+                let button = if self.config.refresh_interval_minutes == minutes {
+                    button.primary()
+                } else {
+                    button.ghost()
+                };
+                button.on_click(cx.listener(move |this, _, _, cx| {
+                    this.set_refresh_interval(minutes, cx);
+                }))
+            }))
+    }
+
     fn render_section(
         &self,
         title: &str,
@@ -115,24 +1247,42 @@ impl Render for SettingsView {
                 self.render_section(
                     "Data",
                     div().v_flex().gap_3().child(
-                        div().h_flex().justify_between().items_center().child(
-                            div()
-                                .v_flex()
-                                .child(div().child("Data Refresh Interval"))
-                                .child(
-                                    div()
-                                        .text_sm()
-                                        .text_color(cx.theme().muted_foreground)
-                                        .child(format!(
-                                            "{} minutes",
-                                            self.config.refresh_interval_minutes
-                                        )),
-                                ),
-                        ),
+                        div()
+                            .h_flex()
+                            .justify_between()
+                            .items_center()
+                            .child(
+                                div()
+                                    .v_flex()
+                                    .child(div().child("Data Refresh Interval"))
+                                    .child(
+                                        div()
+                                            .text_sm()
+                                            .text_color(cx.theme().muted_foreground)
+                                            .child(if self.config.refresh_interval_minutes == 0 {
+                                                "Automatic sync is off".to_string()
+                                            } else {
+                                                format!(
+                                                "Sync accounts in the background every {} minutes",
+                                                self.config.refresh_interval_minutes
+                                            )
+                                            }),
+                                    ),
+                            )
+                            .child(self.render_interval_options(cx)),
                     ),
                     cx,
                 ),
             )
+            // Cache
+            .child(self.render_section("Cache", self.render_cache(cx), cx))
+            // Backup
+            .child(self.render_section("Security", self.render_security(cx), cx))
+            .child(self.render_section("Backup", self.render_backup(cx), cx))
+            // Account transfer
+            .child(self.render_section("Move Accounts", self.render_transfer(cx), cx))
+            // Email digests
+            .child(self.render_section("Email Digests", self.render_digests(cx), cx))
             // About
             .child(
                 self.render_section(